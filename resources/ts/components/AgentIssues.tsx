@@ -144,6 +144,27 @@ export function AgentIssues({ issues }: { issues: Array<AgentIssue> }) {
           );
         }
 
+        if ("UnableToEstimateSlots" in issue) {
+          return (
+            <li className={agentIssues__issue} key={index}>
+              <strong>
+                Unable to estimate the number of slots:{" "}
+                {issue.UnableToEstimateSlots}
+              </strong>
+              <strong>What will Paddler do?</strong>{" "}
+              <p>
+                Paddler will not load the model until the desired state
+                changes.
+              </p>
+              <strong>What can you do?</strong>{" "}
+              <p>
+                <Link href="/model">Set the number of slots explicitly</Link>{" "}
+                instead of estimating it from the available memory.
+              </p>
+            </li>
+          );
+        }
+
         if ("UnableToFindChatTemplate" in issue) {
           return (
             <li className={agentIssues__issue} key={index}>
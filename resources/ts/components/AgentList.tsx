@@ -48,11 +48,13 @@ export function AgentList({
     <div className={agentList}>
       {agents.map(function (agent: Agent) {
         const {
+          deployment,
           download_current,
           download_filename,
           download_total,
           id,
           issues,
+          labels,
           model_path,
           name,
           uses_chat_template_override,
@@ -66,7 +68,18 @@ export function AgentList({
             key={id}
           >
             <div className={agentList__agent__issues}>
-              <div className={agentList__agent__name}>{name}</div>
+              <div className={agentList__agent__name}>
+                {name}
+                {"Canary" === deployment && <i> (canary)</i>}
+                {"Shadow" === deployment && <i> (shadow)</i>}
+              </div>
+              {Object.entries(labels).map(function ([key, value]) {
+                return (
+                  <code key={key}>
+                    {key}={value}
+                  </code>
+                );
+              })}
               {issues.length > 0 ? (
                 <div className={agentList__agent__issues__list}>
                   <AgentIssuesPreviewButton agentName={name} issues={issues} />
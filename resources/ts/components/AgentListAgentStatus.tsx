@@ -7,6 +7,9 @@ import { agentListAgentStatus__progress } from "./AgentListAgentStatus.module.cs
 export function AgentListAgentStatus({
   agent: {
     desired_slots_total,
+    is_desired_state_outdated,
+    is_desired_state_pending,
+    is_draining,
     slots_processing,
     slots_total,
     state_application_status,
@@ -14,6 +17,36 @@ export function AgentListAgentStatus({
 }: {
   agent: Agent;
 }) {
+  if (is_draining) {
+    return (
+      <div className={agentListAgentStatus__progress}>
+        <div>
+          🚰 <i>Draining ({slots_processing} requests left)</i>
+        </div>
+      </div>
+    );
+  }
+
+  if (is_desired_state_outdated) {
+    return (
+      <div className={agentListAgentStatus__progress}>
+        <div>
+          ⏳ <i>Waiting for update</i>
+        </div>
+      </div>
+    );
+  }
+
+  if (is_desired_state_pending && "Applied" === state_application_status) {
+    return (
+      <div className={agentListAgentStatus__progress}>
+        <div>
+          📥 <i>Preparing update (still serving)</i>
+        </div>
+      </div>
+    );
+  }
+
   switch (state_application_status) {
     case "Applied":
       return (
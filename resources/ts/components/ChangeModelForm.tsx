@@ -34,8 +34,10 @@ import {
 
 export function ChangeModelForm({
   defaultModelUri,
+  etag,
 }: {
   defaultModelUri: null | string;
+  etag: null | string;
 }) {
   const [, navigate] = useLocation();
   const { chatTemplateOverride, useChatTemplateOverride } =
@@ -87,12 +89,17 @@ export function ChangeModelForm({
         method: "PUT",
         headers: {
           "Content-Type": "application/json",
+          ...(etag ? { "If-Match": etag } : {}),
         },
         body: JSON.stringify(balancerDesiredState),
       })
         .then(function (response) {
           if (response.ok) {
             navigate("/");
+          } else if (409 === response.status) {
+            throw new Error(
+              "Desired state was changed by someone else in the meantime, reload the page to see the changes",
+            );
           } else {
             throw new Error(
               `Failed to update agent desired state: ${response.statusText}`,
@@ -103,7 +110,7 @@ export function ChangeModelForm({
           console.error("Error updating agent desired state:", error);
         });
     },
-    [managementAddr, navigate, balancerDesiredState],
+    [balancerDesiredState, etag, managementAddr, navigate],
   );
 
   return (
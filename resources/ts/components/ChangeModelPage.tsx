@@ -29,9 +29,9 @@ function modelSchemaToUrl(model: AgentDesiredModel): string {
 
 export function ChangeModelPage() {
   const { managementAddr } = useContext(PaddlerConfigurationContext);
-  const loadingState = useBalancerDesiredState({ managementAddr });
+  const { etag, fetchState } = useBalancerDesiredState({ managementAddr });
 
-  return matchFetchJsonState(loadingState, {
+  return matchFetchJsonState(fetchState, {
     empty() {
       return (
         <FloatingStatus>Unable to pick the desired state source</FloatingStatus>
@@ -61,7 +61,10 @@ export function ChangeModelPage() {
           <InferenceParametersContextProvider
             defaultInferenceParameters={inference_parameters}
           >
-            <ChangeModelForm defaultModelUri={modelSchemaToUrl(model)} />
+            <ChangeModelForm
+              defaultModelUri={modelSchemaToUrl(model)}
+              etag={etag}
+            />
           </InferenceParametersContextProvider>
         </ChatTemplateContextProvider>
       );
@@ -1,4 +1,4 @@
-import { useCallback } from "react";
+import { useCallback, useState } from "react";
 
 import { BalancerDesiredStateSchema } from "../schemas/BalancerDesiredState";
 import { useFetchJson } from "./useFetchJson";
@@ -8,19 +8,28 @@ export function useBalancerDesiredState({
 }: {
   managementAddr: string;
 }) {
+  const [etag, setEtag] = useState<null | string>(null);
+
   const produceFetchPromise = useCallback(
     function (signal: AbortSignal) {
       return fetch(`//${managementAddr}/api/v1/balancer_desired_state`, {
         signal,
+      }).then(function (response) {
+        setEtag(response.headers.get("ETag"));
+
+        return response;
       });
     },
-    [managementAddr],
+    [managementAddr, setEtag],
   );
 
-  const result = useFetchJson({
+  const fetchState = useFetchJson({
     produceFetchPromise,
     responseSchema: BalancerDesiredStateSchema,
   });
 
-  return result;
+  return Object.freeze({
+    etag,
+    fetchState,
+  });
 }
@@ -1,15 +1,22 @@
 import { z } from "zod";
 
+import { AgentDesiredStateSchema } from "./AgentDesiredState";
 import { AgentIssueSchema } from "./AgentIssue";
 
 export const AgentSchema = z
   .object({
+    deployment: z.enum(["Canary", "Shadow", "Stable"]),
     desired_slots_total: z.number(),
     download_current: z.number(),
     download_filename: z.string().nullable(),
     download_total: z.number(),
+    effective_desired_state: AgentDesiredStateSchema.nullable(),
     id: z.string(),
+    is_desired_state_outdated: z.boolean(),
+    is_desired_state_pending: z.boolean(),
+    is_draining: z.boolean(),
     issues: z.array(AgentIssueSchema),
+    labels: z.record(z.string(), z.string()),
     model_path: z.string().nullable(),
     name: z.string().nullable(),
     slots_processing: z.number(),
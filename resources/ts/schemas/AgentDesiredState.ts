@@ -0,0 +1,17 @@
+import { z } from "zod";
+
+import { AgentDesiredModelSchema } from "./AgentDesiredModel";
+import { ChatTemplateSchema } from "./ChatTemplate";
+import { DesiredSlotsSchema } from "./DesiredSlots";
+import { InferenceParametersSchema } from "./InferenceParameters";
+
+export const AgentDesiredStateSchema = z
+  .object({
+    chat_template_override: ChatTemplateSchema.nullable(),
+    inference_parameters: InferenceParametersSchema,
+    model: AgentDesiredModelSchema,
+    slots: DesiredSlotsSchema.nullable(),
+  })
+  .strict();
+
+export type AgentDesiredState = z.infer<typeof AgentDesiredStateSchema>;
@@ -25,6 +25,9 @@ export const AgentIssueSchema = z.union([
       slot_index: z.number(),
     }),
   }),
+  z.object({
+    UnableToEstimateSlots: z.string(),
+  }),
   z.object({
     UnableToFindChatTemplate: z.string(),
   }),
@@ -2,15 +2,52 @@ import { z } from "zod";
 
 import { AgentDesiredModelSchema } from "./AgentDesiredModel";
 import { ChatTemplateSchema } from "./ChatTemplate";
+import { DesiredSlotsSchema } from "./DesiredSlots";
 import { InferenceParametersSchema } from "./InferenceParameters";
 
-export const BalancerDesiredStateSchema = z
+const BaseBalancerDesiredStateSchema = z
   .object({
     chat_template_override: ChatTemplateSchema.nullable(),
     inference_parameters: InferenceParametersSchema,
     model: AgentDesiredModelSchema,
+    slots: DesiredSlotsSchema.nullable().optional(),
     use_chat_template_override: z.boolean(),
   })
   .strict();
 
+const NestedBalancerDesiredStateSchema = BaseBalancerDesiredStateSchema.extend(
+  {
+    canary: z.null().optional(),
+    shadow: z.null().optional(),
+  },
+).strict();
+
+export const CanaryDeploymentSchema = z
+  .object({
+    agent_selector: z.record(z.string(), z.string()).optional(),
+    agents: z.number(),
+    desired_state: NestedBalancerDesiredStateSchema,
+    sticky: z.boolean(),
+    traffic_percentage: z.number(),
+  })
+  .strict();
+
+export const ShadowDeploymentSchema = z
+  .object({
+    agent_selector: z.record(z.string(), z.string()).optional(),
+    agents: z.number(),
+    desired_state: NestedBalancerDesiredStateSchema,
+    mirror_percentage: z.number(),
+  })
+  .strict();
+
+export const BalancerDesiredStateSchema = BaseBalancerDesiredStateSchema.extend(
+  {
+    canary: CanaryDeploymentSchema.nullable().optional(),
+    shadow: ShadowDeploymentSchema.nullable().optional(),
+  },
+).strict();
+
 export type BalancerDesiredState = z.infer<typeof BalancerDesiredStateSchema>;
+export type CanaryDeployment = z.infer<typeof CanaryDeploymentSchema>;
+export type ShadowDeployment = z.infer<typeof ShadowDeploymentSchema>;
@@ -0,0 +1,10 @@
+import { z } from "zod";
+
+export const DesiredSlotsSchema = z.union([
+  z.object({
+    Count: z.number(),
+  }),
+  z.literal("Auto"),
+]);
+
+export type DesiredSlots = z.infer<typeof DesiredSlotsSchema>;
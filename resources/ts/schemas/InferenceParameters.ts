@@ -9,6 +9,90 @@ export const poolingTypes = [
   "Unspecified",
 ] as const;
 
+export const SamplerStageSchema = z.union([
+  z.enum(["MinP", "Penalties", "Temperature", "TopK", "TopP"]),
+  z
+    .object({
+      Dry: z
+        .object({
+          allowed_length: z.number(),
+          base: z.number(),
+          multiplier: z.number(),
+          penalty_last_n: z.number(),
+          sequence_breakers: z.array(z.string()),
+        })
+        .strict(),
+    })
+    .strict(),
+  z
+    .object({
+      Grammar: z
+        .object({
+          grammar: z.string(),
+          root: z.string(),
+        })
+        .strict(),
+    })
+    .strict(),
+  z
+    .object({
+      LogitBias: z.array(
+        z
+          .object({
+            bias: z.number(),
+            token: z.union([
+              z.object({ Id: z.number() }).strict(),
+              z.object({ Text: z.string() }).strict(),
+            ]),
+          })
+          .strict(),
+      ),
+    })
+    .strict(),
+  z
+    .object({
+      Mirostat: z
+        .object({
+          eta: z.number(),
+          m: z.number(),
+          tau: z.number(),
+        })
+        .strict(),
+    })
+    .strict(),
+  z
+    .object({
+      MirostatV2: z
+        .object({
+          eta: z.number(),
+          tau: z.number(),
+        })
+        .strict(),
+    })
+    .strict(),
+  z
+    .object({
+      TypicalP: z
+        .object({
+          p: z.number(),
+        })
+        .strict(),
+    })
+    .strict(),
+  z
+    .object({
+      Xtc: z
+        .object({
+          probability: z.number(),
+          threshold: z.number(),
+        })
+        .strict(),
+    })
+    .strict(),
+]);
+
+export type SamplerStage = z.infer<typeof SamplerStageSchema>;
+
 export const InferenceParametersSchema = z
   .object({
     batch_n_tokens: z.number(),
@@ -20,6 +104,7 @@ export const InferenceParametersSchema = z
     penalty_presence: z.number(),
     penalty_repeat: z.number(),
     pooling_type: z.enum(poolingTypes),
+    sampler_chain: z.array(SamplerStageSchema),
     temperature: z.number(),
     top_k: z.number(),
     top_p: z.number(),
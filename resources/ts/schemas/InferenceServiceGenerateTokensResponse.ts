@@ -19,9 +19,21 @@ export const InferenceServiceGenerateTokensResponseSchema = z
             z.object({
               ChatTemplateError: z.string(),
             }),
-            z.literal("Done"),
             z.object({
-              Token: z.string(),
+              Done: z.object({
+                prompt_tokens: z.number(),
+                seed: z.number(),
+              }),
+            }),
+            z.object({
+              Error: z.string(),
+            }),
+            z.object({
+              Token: z.object({
+                choice_index: z.number(),
+                content: z.string(),
+                logprobs: z.unknown().nullable(),
+              }),
             }),
           ]),
         }),
@@ -63,7 +75,7 @@ export const InferenceServiceGenerateTokensResponseSchema = z
       });
     }
 
-    if (data.Response.response.GeneratedToken === "Done") {
+    if ("Done" in data.Response.response.GeneratedToken) {
       return Object.freeze({
         done: true,
         error: null,
@@ -86,12 +98,25 @@ export const InferenceServiceGenerateTokensResponseSchema = z
       });
     }
 
+    if ("Error" in data.Response.response.GeneratedToken) {
+      return Object.freeze({
+        done: true,
+        error: Object.freeze({
+          code: 500,
+          description: data.Response.response.GeneratedToken.Error,
+        }),
+        ok: false,
+        request_id: data.Response.request_id,
+        token: null,
+      });
+    }
+
     return Object.freeze({
       done: false,
       error: null,
       ok: true,
       request_id: data.Response.request_id,
-      token: data.Response.response.GeneratedToken.Token,
+      token: data.Response.response.GeneratedToken.Token.content,
     });
   });
 
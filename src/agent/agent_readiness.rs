@@ -0,0 +1,18 @@
+use serde::Serialize;
+
+use crate::agent::balancer_connection_state::BalancerConnectionState;
+use crate::agent_state_application_status::AgentStateApplicationStatus;
+
+#[derive(Serialize)]
+pub struct AgentReadiness {
+    pub balancer_connection_state: BalancerConnectionState,
+    pub desired_slots_total: i32,
+    pub is_draining: bool,
+    pub is_model_loaded: bool,
+    pub is_ready: bool,
+    pub model_path: Option<String>,
+    pub slots_processing: i32,
+    /// Slots that loaded the model and can take requests
+    pub slots_total: i32,
+    pub state_application_status: AgentStateApplicationStatus,
+}
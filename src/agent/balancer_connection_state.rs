@@ -0,0 +1,8 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum BalancerConnectionState {
+    Connected,
+    Connecting,
+    Disconnected,
+}
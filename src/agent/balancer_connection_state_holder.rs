@@ -0,0 +1,34 @@
+use std::sync::RwLock;
+
+use crate::agent::balancer_connection_state::BalancerConnectionState;
+
+pub struct BalancerConnectionStateHolder {
+    balancer_connection_state: RwLock<BalancerConnectionState>,
+}
+
+impl BalancerConnectionStateHolder {
+    pub fn get_balancer_connection_state(&self) -> BalancerConnectionState {
+        *self
+            .balancer_connection_state
+            .read()
+            .expect("Poisoned lock on balancer connection state")
+    }
+
+    pub fn set_balancer_connection_state(
+        &self,
+        balancer_connection_state: BalancerConnectionState,
+    ) {
+        *self
+            .balancer_connection_state
+            .write()
+            .expect("Poisoned lock on balancer connection state") = balancer_connection_state;
+    }
+}
+
+impl Default for BalancerConnectionStateHolder {
+    fn default() -> Self {
+        Self {
+            balancer_connection_state: RwLock::new(BalancerConnectionState::Disconnected),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use log::debug;
+use log::error;
+use log::warn;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+
+use crate::agent::continue_from_conversation_history_request::ContinueFromConversationHistoryRequest;
+use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
+use crate::agent::from_request_params::FromRequestParams;
+use crate::agent::generate_embedding_batch_request::GenerateEmbeddingBatchRequest;
+use crate::agent::jsonrpc::Message as JsonRpcMessage;
+use crate::agent::jsonrpc::Notification as JsonRpcNotification;
+use crate::agent::jsonrpc::Request as JsonRpcRequest;
+use crate::agent::jsonrpc::Response as JsonRpcResponse;
+use crate::agent::jsonrpc::notification_params::SetStateParams;
+use crate::agent::jsonrpc::notification_params::VersionParams;
+use crate::agent::model_metadata_holder::ModelMetadataHolder;
+use crate::agent::receive_stream_stopper_collection::ReceiveStreamStopperCollection;
+use crate::agent_applicable_state_holder::AgentApplicableStateHolder;
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Message as ManagementJsonRpcMessage;
+use crate::jsonrpc::Error as JsonRpcError;
+use crate::jsonrpc::ErrorEnvelope;
+use crate::jsonrpc::RequestEnvelope;
+use crate::jsonrpc::ResponseEnvelope;
+
+/// Handles the messages that the balancer sends to the agent, with the channels of a single connection
+pub struct BalancerMessageHandler {
+    pub agent_applicable_state_holder: Arc<AgentApplicableStateHolder>,
+    pub agent_desired_state_tx: mpsc::UnboundedSender<SetStateParams>,
+    pub connection_close_tx: broadcast::Sender<()>,
+    pub continue_from_conversation_history_request_tx:
+        mpsc::UnboundedSender<ContinueFromConversationHistoryRequest>,
+    pub continue_from_raw_prompt_request_tx: mpsc::UnboundedSender<ContinueFromRawPromptRequest>,
+    pub generate_embedding_batch_request_tx: mpsc::UnboundedSender<GenerateEmbeddingBatchRequest>,
+    pub message_tx: mpsc::UnboundedSender<ManagementJsonRpcMessage>,
+    pub model_metadata_holder: Arc<ModelMetadataHolder>,
+    pub receive_stream_stopper_collection: Arc<ReceiveStreamStopperCollection>,
+}
+
+impl BalancerMessageHandler {
+    async fn generate_responses<TRequest: FromRequestParams + 'static>(
+        connection_close_tx: broadcast::Sender<()>,
+        id: String,
+        message_tx: mpsc::UnboundedSender<ManagementJsonRpcMessage>,
+        request_params: TRequest::RequestParams,
+        receive_stream_stopper_collection: Arc<ReceiveStreamStopperCollection>,
+        request_tx: mpsc::UnboundedSender<TRequest>,
+    ) -> Result<()> {
+        let (response_tx, mut response_rx) = mpsc::unbounded_channel::<TRequest::Response>();
+        let (stop_tx, stop_rx) = mpsc::unbounded_channel::<()>();
+
+        let _guard = receive_stream_stopper_collection
+            .register_stopper_with_guard(id.clone(), stop_tx)
+            .context(format!("Failed to register stopper for request: {id}"))?;
+
+        request_tx.send(TRequest::from_request_params(
+            request_params,
+            response_tx,
+            stop_rx,
+        ))?;
+
+        let mut connection_close_rx = connection_close_tx.subscribe();
+
+        loop {
+            tokio::select! {
+                _ = connection_close_rx.recv() => break,
+                response = response_rx.recv() => {
+                    match response {
+                        Some(response) => {
+                            message_tx.send(
+                                ManagementJsonRpcMessage::Response(
+                                    ResponseEnvelope {
+                                        request_id: id.clone(),
+                                        response: response.into(),
+                                    }
+                                ),
+                            )?;
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn handle_message(self, deserialized_message: JsonRpcMessage) -> Result<()> {
+        let BalancerMessageHandler {
+            agent_applicable_state_holder,
+            agent_desired_state_tx,
+            connection_close_tx,
+            continue_from_conversation_history_request_tx,
+            continue_from_raw_prompt_request_tx,
+            generate_embedding_batch_request_tx,
+            message_tx,
+            model_metadata_holder,
+            receive_stream_stopper_collection,
+        } = self;
+
+        match deserialized_message {
+            JsonRpcMessage::Error(ErrorEnvelope {
+                request_id,
+                error: JsonRpcError { code, description },
+            }) => {
+                error!(
+                    "Received error from server: code: {code}, description: {description:?}, request_id: {request_id:?}"
+                );
+
+                Ok(())
+            }
+            JsonRpcMessage::Notification(JsonRpcNotification::SetState(set_state_params)) => {
+                agent_desired_state_tx.send(set_state_params)?;
+
+                Ok(())
+            }
+            JsonRpcMessage::Notification(JsonRpcNotification::StopRespondingTo(request_id)) => {
+                debug!("Received StopGeneratingTokens notification for request ID: {request_id:?}");
+                receive_stream_stopper_collection
+                    .stop(request_id.clone())
+                    .context(format!(
+                        "Failed to stop generating tokens for request ID: {request_id}"
+                    ))?;
+
+                Ok(())
+            }
+            JsonRpcMessage::Notification(JsonRpcNotification::Version(VersionParams {
+                version,
+            })) => {
+                if version != env!("CARGO_PKG_VERSION") {
+                    warn!(
+                        "Version mismatch: server version is {version}, client version is {}",
+                        env!("CARGO_PKG_VERSION")
+                    );
+                }
+
+                Ok(())
+            }
+            JsonRpcMessage::Request(RequestEnvelope {
+                id,
+                request:
+                    JsonRpcRequest::ContinueFromConversationHistory(
+                        continue_from_conversation_history_params,
+                    ),
+            }) => {
+                Self::generate_responses(
+                    connection_close_tx,
+                    id,
+                    message_tx,
+                    continue_from_conversation_history_params,
+                    receive_stream_stopper_collection,
+                    continue_from_conversation_history_request_tx,
+                )
+                .await
+            }
+            JsonRpcMessage::Request(RequestEnvelope {
+                id,
+                request: JsonRpcRequest::ContinueFromRawPrompt(generate_tokens_params),
+            }) => {
+                Self::generate_responses(
+                    connection_close_tx,
+                    id,
+                    message_tx,
+                    generate_tokens_params,
+                    receive_stream_stopper_collection,
+                    continue_from_raw_prompt_request_tx,
+                )
+                .await
+            }
+            JsonRpcMessage::Request(RequestEnvelope {
+                id,
+                request: JsonRpcRequest::GenerateEmbeddingBatch(generate_embedding_batch_params),
+            }) => {
+                Self::generate_responses(
+                    connection_close_tx,
+                    id,
+                    message_tx,
+                    generate_embedding_batch_params,
+                    receive_stream_stopper_collection,
+                    generate_embedding_batch_request_tx,
+                )
+                .await
+            }
+            JsonRpcMessage::Request(RequestEnvelope {
+                id,
+                request: JsonRpcRequest::GetChatTemplateOverride,
+            }) => Ok(
+                message_tx.send(ManagementJsonRpcMessage::Response(ResponseEnvelope {
+                    request_id: id.clone(),
+                    response: JsonRpcResponse::ChatTemplateOverride(
+                        if let Some(agent_applicable_state) =
+                            agent_applicable_state_holder.get_agent_applicable_state()
+                        {
+                            agent_applicable_state.chat_template_override.clone()
+                        } else {
+                            None
+                        },
+                    ),
+                }))?,
+            ),
+            JsonRpcMessage::Request(RequestEnvelope {
+                id,
+                request: JsonRpcRequest::GetModelMetadata,
+            }) => Ok(
+                message_tx.send(ManagementJsonRpcMessage::Response(ResponseEnvelope {
+                    request_id: id.clone(),
+                    response: JsonRpcResponse::ModelMetadata(
+                        model_metadata_holder.get_model_metadata(),
+                    ),
+                }))?,
+            ),
+        }
+    }
+}
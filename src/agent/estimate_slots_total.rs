@@ -0,0 +1,138 @@
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+use llama_cpp_2::model::LlamaModel;
+use log::warn;
+
+/// Both keys and values are cached
+const KV_CACHE_TENSORS: u64 = 2;
+/// KV cache uses f16 by default
+const KV_CACHE_BYTES_PER_ELEMENT: u64 = 2;
+/// Part of the memory left for compute buffers and the rest of the system
+const MEMORY_HEADROOM_PERCENTAGE: u64 = 10;
+
+/// Memory for the model and the KV caches of the slots, measured before the model is loaded.
+/// Both of them go to the GPU memory when the model layers are offloaded.
+pub fn read_available_memory(uses_gpu: bool) -> Result<u64> {
+    if uses_gpu {
+        read_available_gpu_memory()
+    } else {
+        read_available_system_memory()
+    }
+}
+
+/// Each slot holds its own context, so the number of slots is limited by how
+/// many KV caches fit into the memory that is left after loading the model.
+pub fn estimate_slots_total(available_memory: u64, model: &LlamaModel, context_size: u32) -> i32 {
+    let n_head = (model.n_head() as u64).max(1);
+    let kv_cache_bytes_per_token = KV_CACHE_TENSORS
+        * KV_CACHE_BYTES_PER_ELEMENT
+        * model.n_layer() as u64
+        * model.n_embd() as u64
+        * model.n_head_kv() as u64
+        / n_head;
+
+    slots_fitting_in_memory(
+        available_memory.saturating_sub(model.size()),
+        kv_cache_bytes_per_token * context_size as u64,
+    )
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn parse_available_memory(meminfo: &str) -> Result<u64> {
+    for line in meminfo.lines() {
+        if let Some(value) = line.strip_prefix("MemAvailable:") {
+            let kilobytes: u64 = value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse()
+                .context(format!("Unable to parse available memory: {value:?}"))?;
+
+            return Ok(kilobytes * 1024);
+        }
+    }
+
+    Err(anyhow!("MemAvailable is missing from /proc/meminfo"))
+}
+
+fn read_available_gpu_memory() -> Result<u64> {
+    let mut available_memory: u64 = 0;
+
+    // SAFETY: devices are registered with the backend and stay valid for the whole process
+    unsafe {
+        for device_index in 0..llama_cpp_sys_2::ggml_backend_dev_count() {
+            let device = llama_cpp_sys_2::ggml_backend_dev_get(device_index);
+
+            if llama_cpp_sys_2::ggml_backend_dev_type(device)
+                != llama_cpp_sys_2::GGML_BACKEND_DEVICE_TYPE_GPU
+            {
+                continue;
+            }
+
+            let mut free: usize = 0;
+            let mut total: usize = 0;
+
+            llama_cpp_sys_2::ggml_backend_dev_memory(device, &mut free, &mut total);
+
+            available_memory += free as u64;
+        }
+    }
+
+    if available_memory == 0 {
+        return Err(anyhow!("None of the GPU devices reports free memory"));
+    }
+
+    Ok(available_memory)
+}
+
+#[cfg(target_os = "linux")]
+fn read_available_system_memory() -> Result<u64> {
+    let meminfo =
+        std::fs::read_to_string("/proc/meminfo").context("Unable to read /proc/meminfo")?;
+
+    parse_available_memory(&meminfo)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_available_system_memory() -> Result<u64> {
+    Err(anyhow!(
+        "Automatic number of slots is not supported on this platform without GPU offloading, set the number of slots explicitly"
+    ))
+}
+
+fn slots_fitting_in_memory(memory: u64, kv_cache_bytes_per_slot: u64) -> i32 {
+    let usable_memory = memory / 100 * (100 - MEMORY_HEADROOM_PERCENTAGE);
+    let slots_total = usable_memory / kv_cache_bytes_per_slot.max(1);
+
+    if slots_total < 1 {
+        warn!(
+            "Not enough memory for a single slot ({usable_memory} bytes available, {kv_cache_bytes_per_slot} bytes needed), starting one anyway"
+        );
+
+        return 1;
+    }
+
+    i32::try_from(slots_total).unwrap_or(i32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_available_memory() -> Result<()> {
+        let meminfo = "MemTotal:       32803036 kB\nMemFree:         1049876 kB\nMemAvailable:   16384000 kB\n";
+
+        assert_eq!(parse_available_memory(meminfo)?, 16384000 * 1024);
+        assert!(parse_available_memory("MemTotal: 1 kB\n").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_slots_fitting_in_memory() {
+        assert_eq!(slots_fitting_in_memory(1000, 100), 9);
+        assert_eq!(slots_fitting_in_memory(50, 100), 1);
+    }
+}
@@ -0,0 +1,11 @@
+use encoding_rs::Decoder;
+use llama_cpp_2::sampling::LlamaSampler;
+
+/// State of one of the parallel sampling streams forked from a shared prompt
+pub struct GenerationChoice {
+    pub decoder: Decoder,
+    pub index: u32,
+    /// Position of this choice's logits in the most recently decoded batch
+    pub logits_index: i32,
+    pub sampler: LlamaSampler,
+}
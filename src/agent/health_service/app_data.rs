@@ -0,0 +1,11 @@
+use std::sync::Arc;
+
+use crate::agent::balancer_connection_state_holder::BalancerConnectionStateHolder;
+use crate::service_health_registry::ServiceHealthRegistry;
+use crate::slot_aggregated_status::SlotAggregatedStatus;
+
+pub struct AppData {
+    pub balancer_connection_state_holder: Arc<BalancerConnectionStateHolder>,
+    pub service_health_registry: Arc<ServiceHealthRegistry>,
+    pub slot_aggregated_status: Arc<SlotAggregatedStatus>,
+}
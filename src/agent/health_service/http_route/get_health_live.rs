@@ -0,0 +1,27 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+
+use crate::agent::health_service::app_data::AppData;
+use crate::produces_snapshot::ProducesSnapshot as _;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[get("/health/live")]
+async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
+    let snapshot = app_data
+        .service_health_registry
+        .make_snapshot()
+        .map_err(ErrorInternalServerError)?;
+
+    if snapshot.is_healthy {
+        Ok(HttpResponse::Ok().json(snapshot))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(snapshot))
+    }
+}
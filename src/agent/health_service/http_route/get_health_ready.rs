@@ -0,0 +1,138 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+
+use crate::agent::agent_readiness::AgentReadiness;
+use crate::agent::balancer_connection_state::BalancerConnectionState;
+use crate::agent::health_service::app_data::AppData;
+use crate::produces_snapshot::ProducesSnapshot as _;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Agent is ready once it is connected to the balancer and serves the model with at least one slot
+#[get("/health/ready")]
+async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
+    let balancer_connection_state = app_data
+        .balancer_connection_state_holder
+        .get_balancer_connection_state();
+    let slot_aggregated_status_snapshot = app_data
+        .slot_aggregated_status
+        .make_snapshot()
+        .map_err(ErrorInternalServerError)?;
+    let is_model_loaded = slot_aggregated_status_snapshot.model_path.is_some();
+
+    let readiness = AgentReadiness {
+        balancer_connection_state,
+        desired_slots_total: slot_aggregated_status_snapshot.desired_slots_total,
+        is_draining: slot_aggregated_status_snapshot.is_draining,
+        is_model_loaded,
+        is_ready: balancer_connection_state == BalancerConnectionState::Connected
+            && is_model_loaded
+            && slot_aggregated_status_snapshot.slots_total > 0
+            && !slot_aggregated_status_snapshot.is_draining,
+        model_path: slot_aggregated_status_snapshot.model_path,
+        slots_processing: slot_aggregated_status_snapshot.slots_processing,
+        slots_total: slot_aggregated_status_snapshot.slots_total,
+        state_application_status: slot_aggregated_status_snapshot.state_application_status,
+    };
+
+    if readiness.is_ready {
+        Ok(HttpResponse::Ok().json(readiness))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(readiness))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::App;
+    use actix_web::http::StatusCode;
+    use actix_web::test;
+
+    use super::*;
+    use crate::agent::balancer_connection_state_holder::BalancerConnectionStateHolder;
+    use crate::slot_aggregated_status::SlotAggregatedStatus;
+
+    fn make_serving_app_data() -> AppData {
+        let balancer_connection_state_holder = Arc::new(BalancerConnectionStateHolder::default());
+        let slot_aggregated_status = Arc::new(SlotAggregatedStatus::new(1));
+
+        balancer_connection_state_holder
+            .set_balancer_connection_state(BalancerConnectionState::Connected);
+        slot_aggregated_status.set_model_path(Some("model.gguf".to_string()));
+        slot_aggregated_status.increment_total_slots();
+
+        AppData {
+            balancer_connection_state_holder,
+            service_health_registry: Default::default(),
+            slot_aggregated_status,
+        }
+    }
+
+    async fn get_status(app_data: AppData) -> StatusCode {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(app_data))
+                .configure(register),
+        )
+        .await;
+
+        test::call_service(
+            &app,
+            test::TestRequest::get().uri("/health/ready").to_request(),
+        )
+        .await
+        .status()
+    }
+
+    #[actix_web::test]
+    async fn test_ready_when_serving() {
+        assert_eq!(get_status(make_serving_app_data()).await, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_not_ready_while_draining() {
+        let app_data = make_serving_app_data();
+
+        app_data.slot_aggregated_status.set_is_draining(true);
+
+        assert_eq!(get_status(app_data).await, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[actix_web::test]
+    async fn test_state_change_does_not_end_the_shutdown_drain() {
+        let app_data = make_serving_app_data();
+
+        app_data.slot_aggregated_status.set_is_shutting_down();
+        app_data.slot_aggregated_status.set_is_draining(false);
+
+        assert_eq!(get_status(app_data).await, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[actix_web::test]
+    async fn test_not_ready_without_slots() {
+        let app_data = make_serving_app_data();
+
+        app_data.slot_aggregated_status.decrement_total_slots();
+
+        assert_eq!(get_status(app_data).await, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[actix_web::test]
+    async fn test_not_ready_when_disconnected_from_balancer() {
+        let app_data = make_serving_app_data();
+
+        app_data
+            .balancer_connection_state_holder
+            .set_balancer_connection_state(BalancerConnectionState::Connecting);
+
+        assert_eq!(get_status(app_data).await, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod get_health_live;
+pub mod get_health_ready;
@@ -0,0 +1,65 @@
+pub mod app_data;
+pub mod http_route;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use actix_web::App;
+use actix_web::HttpServer;
+use actix_web::web::Data;
+use anyhow::Result;
+use async_trait::async_trait;
+use log::error;
+use tokio::sync::broadcast;
+
+use crate::agent::balancer_connection_state_holder::BalancerConnectionStateHolder;
+use crate::agent::health_service::app_data::AppData;
+use crate::service::Service;
+use crate::service_health_registry::ServiceHealthRegistry;
+use crate::service_restart_policy::ServiceRestartPolicy;
+use crate::slot_aggregated_status::SlotAggregatedStatus;
+
+/// Liveness and readiness endpoints for probes (for example, in Kubernetes)
+pub struct HealthService {
+    pub addr: SocketAddr,
+    pub balancer_connection_state_holder: Arc<BalancerConnectionStateHolder>,
+    pub service_health_registry: Arc<ServiceHealthRegistry>,
+    pub slot_aggregated_status: Arc<SlotAggregatedStatus>,
+}
+
+#[async_trait]
+impl Service for HealthService {
+    fn name(&self) -> &'static str {
+        "agent::health_service"
+    }
+
+    fn restart_policy(&self) -> ServiceRestartPolicy {
+        ServiceRestartPolicy::Fatal
+    }
+
+    async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let app_data = Data::new(AppData {
+            balancer_connection_state_holder: self.balancer_connection_state_holder.clone(),
+            service_health_registry: self.service_health_registry.clone(),
+            slot_aggregated_status: self.slot_aggregated_status.clone(),
+        });
+
+        HttpServer::new(move || {
+            App::new()
+                .app_data(app_data.clone())
+                .configure(http_route::get_health_live::register)
+                .configure(http_route::get_health_ready::register)
+        })
+        .shutdown_signal(async move {
+            if let Err(err) = shutdown.recv().await {
+                error!("Failed to receive shutdown signal: {err}");
+            }
+        })
+        .bind(self.addr)
+        .expect("Unable to bind server to address")
+        .run()
+        .await?;
+
+        Ok(())
+    }
+}
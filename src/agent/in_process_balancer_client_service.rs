@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use actix_web::rt;
+use anyhow::Result;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use log::error;
+use log::info;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tokio::time::interval;
+
+use crate::agent::balancer_connection_state::BalancerConnectionState;
+use crate::agent::balancer_connection_state_holder::BalancerConnectionStateHolder;
+use crate::agent::balancer_message_handler::BalancerMessageHandler;
+use crate::agent::continue_from_conversation_history_request::ContinueFromConversationHistoryRequest;
+use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
+use crate::agent::generate_embedding_batch_request::GenerateEmbeddingBatchRequest;
+use crate::agent::jsonrpc::Message as AgentJsonRpcMessage;
+use crate::agent::jsonrpc::notification_params::SetStateParams;
+use crate::agent::model_metadata_holder::ModelMetadataHolder;
+use crate::agent::receive_stream_stopper_collection::ReceiveStreamStopperCollection;
+use crate::agent_applicable_state_holder::AgentApplicableStateHolder;
+use crate::balancer::agent_message_handler::AgentMessageHandler;
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Message as ManagementJsonRpcMessage;
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Notification as ManagementJsonRpcNotification;
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::notification_params::RegisterAgentParams;
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::notification_params::UpdateAgentStatusParams;
+use crate::controls_websocket_endpoint::ContinuationDecision;
+use crate::produces_snapshot::ProducesSnapshot as _;
+use crate::service::Service;
+use crate::slot_aggregated_status::SlotAggregatedStatus;
+
+/// Agent of `paddler serve`, which exchanges the same messages with the balancer running
+/// in the same process, but over channels instead of the agent socket
+pub struct InProcessBalancerClientService {
+    pub agent_applicable_state_holder: Arc<AgentApplicableStateHolder>,
+    pub agent_desired_state_tx: mpsc::UnboundedSender<SetStateParams>,
+    pub agent_id: String,
+    pub agent_message_handler: AgentMessageHandler,
+    pub balancer_connection_state_holder: Arc<BalancerConnectionStateHolder>,
+    pub continue_from_conversation_history_request_tx:
+        mpsc::UnboundedSender<ContinueFromConversationHistoryRequest>,
+    pub continue_from_raw_prompt_request_tx: mpsc::UnboundedSender<ContinueFromRawPromptRequest>,
+    pub generate_embedding_batch_request_tx: mpsc::UnboundedSender<GenerateEmbeddingBatchRequest>,
+    pub labels: BTreeMap<String, String>,
+    pub model_metadata_holder: Arc<ModelMetadataHolder>,
+    pub name: Option<String>,
+    pub receive_stream_stopper_collection: Arc<ReceiveStreamStopperCollection>,
+    pub slot_aggregated_status: Arc<SlotAggregatedStatus>,
+}
+
+impl InProcessBalancerClientService {
+    async fn exchange_messages(
+        &self,
+        connection_close_tx: &broadcast::Sender<()>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> Result<()> {
+        let (agent_message_tx, mut agent_message_rx) =
+            mpsc::unbounded_channel::<AgentJsonRpcMessage>();
+        let (message_tx, mut message_rx) = mpsc::unbounded_channel::<ManagementJsonRpcMessage>();
+        let mut connection_close_rx = connection_close_tx.subscribe();
+
+        message_tx.send(ManagementJsonRpcMessage::Notification(
+            ManagementJsonRpcNotification::RegisterAgent(RegisterAgentParams {
+                labels: self.labels.clone(),
+                name: self.name.clone(),
+                slot_aggregated_status_snapshot: self.slot_aggregated_status.make_snapshot()?,
+            }),
+        ))?;
+
+        let send_status_update = || match self.slot_aggregated_status.make_snapshot() {
+            Ok(slot_aggregated_status_snapshot) => {
+                message_tx
+                    .send(ManagementJsonRpcMessage::Notification(
+                        ManagementJsonRpcNotification::UpdateAgentStatus(UpdateAgentStatusParams {
+                            slot_aggregated_status_snapshot,
+                        }),
+                    ))
+                    .unwrap_or_else(|err| {
+                        error!("Failed to send status update notification: {err}");
+                    });
+            }
+            Err(err) => error!("Failed to create slot aggregated status snapshot: {err}"),
+        };
+
+        let mut ticker = interval(Duration::from_secs(1));
+
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = connection_close_rx.recv() => {
+                    return Err(anyhow!("Balancer closed the connection with the in-process agent"));
+                }
+                _ = shutdown.recv() => return Ok(()),
+                _ = self.slot_aggregated_status.update_notifier.notified() => send_status_update(),
+                _ = ticker.tick() => send_status_update(),
+                Some(message) = message_rx.recv() => {
+                    // Handled in order, so the agent is registered before its status updates arrive
+                    match self
+                        .agent_message_handler
+                        .handle_message(&self.agent_id, connection_close_tx, message, || {
+                            agent_message_tx.clone()
+                        })
+                        .await?
+                    {
+                        ContinuationDecision::Continue => {}
+                        ContinuationDecision::Stop => return Ok(()),
+                    }
+                }
+                Some(message) = agent_message_rx.recv() => {
+                    let balancer_message_handler = BalancerMessageHandler {
+                        agent_applicable_state_holder: self.agent_applicable_state_holder.clone(),
+                        agent_desired_state_tx: self.agent_desired_state_tx.clone(),
+                        connection_close_tx: connection_close_tx.clone(),
+                        continue_from_conversation_history_request_tx: self
+                            .continue_from_conversation_history_request_tx
+                            .clone(),
+                        continue_from_raw_prompt_request_tx: self
+                            .continue_from_raw_prompt_request_tx
+                            .clone(),
+                        generate_embedding_batch_request_tx: self
+                            .generate_embedding_batch_request_tx
+                            .clone(),
+                        message_tx: message_tx.clone(),
+                        model_metadata_holder: self.model_metadata_holder.clone(),
+                        receive_stream_stopper_collection: self
+                            .receive_stream_stopper_collection
+                            .clone(),
+                    };
+                    let mut connection_close_rx = connection_close_tx.subscribe();
+
+                    rt::spawn(async move {
+                        tokio::select! {
+                            _ = connection_close_rx.recv() => {}
+                            result = balancer_message_handler.handle_message(message) => {
+                                if let Err(err) = result {
+                                    error!("Error handling balancer message: {err}");
+                                }
+                            }
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Service for InProcessBalancerClientService {
+    fn name(&self) -> &'static str {
+        "agent::in_process_balancer_client_service"
+    }
+
+    async fn run(&mut self, shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let (connection_close_tx, _) = broadcast::channel::<()>(1);
+
+        self.balancer_connection_state_holder
+            .set_balancer_connection_state(BalancerConnectionState::Connected);
+
+        let result = self.exchange_messages(&connection_close_tx, shutdown).await;
+
+        // Stops the requests in progress, the same way a closed agent socket does
+        let _ = connection_close_tx.send(());
+
+        self.agent_message_handler.remove_agent(&self.agent_id);
+        self.balancer_connection_state_holder
+            .set_balancer_connection_state(BalancerConnectionState::Disconnected);
+
+        info!("Disconnected the in-process agent");
+
+        result
+    }
+}
@@ -7,4 +7,6 @@ use crate::agent_desired_state::AgentDesiredState;
 #[serde(deny_unknown_fields)]
 pub struct SetStateParams {
     pub desired_state: AgentDesiredState,
+    /// Agent reports it back once the desired state is applied
+    pub generation: i32,
 }
@@ -18,8 +18,11 @@ use llama_cpp_2::model::params::LlamaModelParams;
 use log::error;
 use tokio::sync::oneshot;
 
+use crate::agent::estimate_slots_total::estimate_slots_total;
+use crate::agent::estimate_slots_total::read_available_memory;
 use crate::agent::llamacpp_arbiter_handle::LlamaCppArbiterHandle;
 use crate::agent::llamacpp_slot::LlamaCppSlot;
+use crate::agent::llamacpp_slot::MAX_CHOICES;
 use crate::agent::llamacpp_slot_context::LlamaCppSlotContext;
 use crate::agent::model_metadata_holder::ModelMetadataHolder;
 use crate::agent_issue::AgentIssue;
@@ -28,6 +31,7 @@ use crate::agent_issue_params::ChatTemplateDoesNotCompileParams;
 use crate::agent_issue_params::SlotCannotStartParams;
 use crate::chat_template::ChatTemplate;
 use crate::chat_template_renderer::ChatTemplateRenderer;
+use crate::desired_slots::DesiredSlots;
 use crate::inference_parameters::InferenceParameters;
 use crate::model_metadata::ModelMetadata;
 use crate::slot_aggregated_status_manager::SlotAggregatedStatusManager;
@@ -35,7 +39,7 @@ use crate::slot_aggregated_status_manager::SlotAggregatedStatusManager;
 pub struct LlamaCppArbiter {
     pub agent_name: Option<String>,
     pub chat_template_override: Option<ChatTemplate>,
-    pub desired_slots_total: i32,
+    pub desired_slots: DesiredSlots,
     pub inference_parameters: InferenceParameters,
     pub model_metadata_holder: Arc<ModelMetadataHolder>,
     pub model_path: PathBuf,
@@ -51,7 +55,7 @@ impl LlamaCppArbiter {
         let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
         let agent_name_clone = self.agent_name.clone();
-        let desired_slots_total = self.desired_slots_total;
+        let desired_slots = self.desired_slots.clone();
         let inference_parameters = self.inference_parameters.clone();
         let model_metadata_holder = self.model_metadata_holder.clone();
         let model_path = self.model_path.clone();
@@ -67,23 +71,44 @@ impl LlamaCppArbiter {
                 LlamaContextParams::default()
                     .with_embeddings(inference_parameters.enable_embeddings)
                     .with_n_ctx(NonZeroU32::new(inference_parameters.context_size))
+                    .with_n_seq_max(MAX_CHOICES)
+                    // Otherwise every sequence gets only 1/MAX_CHOICES of the context
+                    .with_kv_unified(true)
                     // n_threads_batch > 1 causes some unpredictability
                     .with_n_threads_batch(1)
                     .with_pooling_type(inference_parameters.pooling_type.clone().into()),
             );
+            let model_params = if cfg!(any(
+                feature = "cuda",
+                feature = "vulkan",
+                target_os = "macos"
+            )) {
+                LlamaModelParams::default().with_n_gpu_layers(1000)
+            } else {
+                LlamaModelParams::default()
+            };
+            let uses_gpu = model_params.n_gpu_layers() > 0 && llama_backend.supports_gpu_offload();
+            // Measured before the model is loaded, since loading it takes up the memory
+            let available_memory = match desired_slots {
+                DesiredSlots::Auto => match read_available_memory(uses_gpu) {
+                    Ok(available_memory) => Some(available_memory),
+                    Err(err) => {
+                        slot_aggregated_status_manager
+                            .slot_aggregated_status
+                            .register_issue(AgentIssue::UnableToEstimateSlots(format!("{err:#}")));
+
+                        return Err(err);
+                    }
+                },
+                DesiredSlots::Count(_) => None,
+            };
             let backend_clone = llama_backend.clone();
             let model = Arc::new(
-                LlamaModel::load_from_file(&backend_clone.clone(), model_path.clone(), &{
-                    if cfg!(any(
-                        feature = "cuda",
-                        feature = "vulkan",
-                        target_os = "macos"
-                    )) {
-                        LlamaModelParams::default().with_n_gpu_layers(1000)
-                    } else {
-                        LlamaModelParams::default()
-                    }
-                })
+                LlamaModel::load_from_file(
+                    &backend_clone.clone(),
+                    model_path.clone(),
+                    &model_params,
+                )
                 .context("Unable to load model from file")?,
             );
 
@@ -107,6 +132,19 @@ impl LlamaCppArbiter {
 
             model_metadata_holder.set_model_metadata(model_metadata);
 
+            let desired_slots_total = match desired_slots {
+                DesiredSlots::Auto => estimate_slots_total(
+                    available_memory.context("Available memory was not measured")?,
+                    &model,
+                    inference_parameters.context_size,
+                ),
+                DesiredSlots::Count(count) => count,
+            };
+
+            slot_aggregated_status_manager
+                .slot_aggregated_status
+                .set_desired_slots_total(desired_slots_total);
+
             let llama_chat_template_string = match chat_template_override {
                 Some(chat_template) => chat_template.content,
                 None => model
@@ -235,9 +273,16 @@ impl LlamaCppArbiter {
             Err(err) => {
                 error!("Failed to load model: {err}");
 
-                self.slot_aggregated_status_manager
+                // Model was not loaded at all if the slots could not be estimated
+                if !self
+                    .slot_aggregated_status_manager
                     .slot_aggregated_status
-                    .register_issue(AgentIssue::ModelCannotBeLoaded(model_path_string.clone()));
+                    .has_issue_like(|issue| matches!(issue, AgentIssue::UnableToEstimateSlots(_)))
+                {
+                    self.slot_aggregated_status_manager
+                        .slot_aggregated_status
+                        .register_issue(AgentIssue::ModelCannotBeLoaded(model_path_string.clone()));
+                }
             }
         }
 
@@ -323,7 +368,7 @@ mod tests {
         let llamacpp_arbiter = LlamaCppArbiter {
             agent_name: Some("test_agent".to_string()),
             chat_template_override: None,
-            desired_slots_total: SLOTS_TOTAL,
+            desired_slots: DesiredSlots::Count(SLOTS_TOTAL),
             inference_parameters: applicable_state.inference_parameters,
             model_metadata_holder: Arc::new(ModelMetadataHolder::new()),
             model_path: model_path.clone(),
@@ -347,8 +392,14 @@ mod tests {
                     generated_tokens_tx: generated_tokens_tx.clone(),
                     generate_tokens_stop_rx: generate_tokens_stop_rx_1,
                     params: ContinueFromRawPromptParams {
+                        logit_bias: vec![],
+                        logprobs: false,
                         max_tokens: 30,
+                        n: 1,
                         raw_prompt: raw_prompt.to_string(),
+                        sampler_chain: None,
+                        seed: None,
+                        top_logprobs: 0,
                     },
                 }),
             controller
@@ -357,8 +408,14 @@ mod tests {
                     generated_tokens_tx: generated_tokens_tx.clone(),
                     generate_tokens_stop_rx: generate_tokens_stop_rx_2,
                     params: ContinueFromRawPromptParams {
+                        logit_bias: vec![],
+                        logprobs: false,
                         max_tokens: 30,
+                        n: 1,
                         raw_prompt: raw_prompt.to_string(),
+                        sampler_chain: None,
+                        seed: None,
+                        top_logprobs: 0,
                     },
                 }),
             controller
@@ -367,8 +424,14 @@ mod tests {
                     generated_tokens_tx,
                     generate_tokens_stop_rx: generate_tokens_stop_rx_3,
                     params: ContinueFromRawPromptParams {
+                        logit_bias: vec![],
+                        logprobs: false,
                         max_tokens: 30,
+                        n: 1,
                         raw_prompt: raw_prompt.to_string(),
+                        sampler_chain: None,
+                        seed: None,
+                        top_logprobs: 0,
                     },
                 }),
         ];
@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::sync::atomic::AtomicI32;
 
 use actix::Message;
 use actix_web::rt;
@@ -14,6 +15,7 @@ use tokio::fs;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 use tokio::time::Duration;
+use tokio::time::Instant;
 use tokio::time::MissedTickBehavior;
 use tokio::time::interval;
 
@@ -29,9 +31,13 @@ use crate::agent_applicable_state_holder::AgentApplicableStateHolder;
 use crate::agent_issue::AgentIssue;
 use crate::agent_issue_fix::AgentIssueFix;
 use crate::agent_state_application_status::AgentStateApplicationStatus;
+use crate::atomic_value::AtomicValue;
+use crate::desired_slots::DesiredSlots;
 use crate::service::Service;
 use crate::slot_aggregated_status_manager::SlotAggregatedStatusManager;
 
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
 pub struct LlamaCppArbiterService {
     pub agent_applicable_state: Option<AgentApplicableState>,
     pub agent_applicable_state_holder: Arc<AgentApplicableStateHolder>,
@@ -39,11 +45,18 @@ pub struct LlamaCppArbiterService {
     pub continue_from_conversation_history_request_rx:
         mpsc::UnboundedReceiver<ContinueFromConversationHistoryRequest>,
     pub continue_from_raw_prompt_request_rx: mpsc::UnboundedReceiver<ContinueFromRawPromptRequest>,
+    /// Used when the desired state does not specify the number of slots
     pub desired_slots_total: i32,
+    /// Set while the slots finish their requests before a new state is applied
+    pub drain_deadline: Option<Instant>,
     pub generate_embedding_batch_request_rx: mpsc::UnboundedReceiver<GenerateEmbeddingBatchRequest>,
     pub llamacpp_arbiter_handle: Option<LlamaCppArbiterHandle>,
     pub model_metadata_holder: Arc<ModelMetadataHolder>,
+    /// Forwarded requests that did not finish yet, including the ones still waiting
+    /// in the arbiter mailbox for a free slot
+    pub requests_in_flight: Arc<AtomicValue<AtomicI32>>,
     pub slot_aggregated_status_manager: Arc<SlotAggregatedStatusManager>,
+    pub state_change_drain_timeout: Duration,
 }
 
 impl LlamaCppArbiterService {
@@ -56,8 +69,10 @@ impl LlamaCppArbiterService {
 
         if let Some(AgentApplicableState {
             chat_template_override,
+            desired_state_generation,
             inference_parameters,
             model_path,
+            slots,
         }) = self.agent_applicable_state.clone()
         {
             self.slot_aggregated_status_manager.reset();
@@ -114,6 +129,22 @@ impl LlamaCppArbiterService {
                     ));
                 }
 
+                if self
+                    .slot_aggregated_status_manager
+                    .slot_aggregated_status
+                    .has_issue_like(|issue| matches!(issue, AgentIssue::UnableToEstimateSlots(_)))
+                {
+                    self.slot_aggregated_status_manager
+                        .slot_aggregated_status
+                        .set_state_application_status(
+                            AgentStateApplicationStatus::AttemptedAndNotAppliable,
+                        );
+
+                    return Err(anyhow!(
+                        "Unable to estimate the number of slots for model at path: {model_path_string}"
+                    ));
+                }
+
                 self.slot_aggregated_status_manager
                     .slot_aggregated_status
                     .register_fix(AgentIssueFix::ModelFileExists);
@@ -121,7 +152,8 @@ impl LlamaCppArbiterService {
                     LlamaCppArbiter {
                         agent_name: self.agent_name.clone(),
                         chat_template_override,
-                        desired_slots_total: self.desired_slots_total,
+                        desired_slots: slots
+                            .unwrap_or(DesiredSlots::Count(self.desired_slots_total)),
                         inference_parameters,
                         model_metadata_holder: self.model_metadata_holder.clone(),
                         model_path,
@@ -136,6 +168,10 @@ impl LlamaCppArbiterService {
             }
 
             info!("Reconciled state change applied successfully");
+
+            self.slot_aggregated_status_manager
+                .slot_aggregated_status
+                .set_applied_desired_state_generation(desired_state_generation);
         }
 
         self.slot_aggregated_status_manager
@@ -145,6 +181,46 @@ impl LlamaCppArbiterService {
         Ok(())
     }
 
+    /// Balancer stops routing requests to a draining agent, so we only need to
+    /// wait for the in-flight ones to finish before the slots can be shut down.
+    /// The service keeps forwarding requests in the meantime.
+    async fn begin_state_change(&mut self) {
+        if self.drain_deadline.is_some() {
+            // The most recent state is applied once the drain finishes
+            return;
+        }
+
+        if self.llamacpp_arbiter_handle.is_none() {
+            self.try_to_apply_state().await;
+
+            return;
+        }
+
+        self.drain_deadline = Some(Instant::now() + self.state_change_drain_timeout);
+        self.slot_aggregated_status_manager
+            .slot_aggregated_status
+            .set_is_draining(true);
+    }
+
+    async fn check_drain(&mut self) {
+        let drain_deadline = match self.drain_deadline {
+            Some(drain_deadline) => drain_deadline,
+            None => return,
+        };
+        let requests_in_flight = self.requests_in_flight.get();
+
+        if requests_in_flight > 0 {
+            if Instant::now() < drain_deadline {
+                return;
+            }
+
+            warn!("Drain timed out with {requests_in_flight} requests still in progress");
+        }
+
+        self.drain_deadline = None;
+        self.try_to_apply_state().await;
+    }
+
     async fn forward_request_to_arbiter<TRequest>(
         &mut self,
         request: TRequest,
@@ -156,6 +232,9 @@ impl LlamaCppArbiterService {
     {
         if let Some(llamacpp_arbiter_handle) = &self.llamacpp_arbiter_handle {
             let llamacpp_slot_addr = llamacpp_arbiter_handle.llamacpp_slot_addr.clone();
+            let requests_in_flight = self.requests_in_flight.clone();
+
+            requests_in_flight.increment();
 
             rt::spawn(async move {
                 tokio::select! {
@@ -168,6 +247,8 @@ impl LlamaCppArbiterService {
                         }
                     }
                 }
+
+                requests_in_flight.decrement();
             });
         } else {
             error!("LlamaCppArbiterHandle is not initialized");
@@ -178,6 +259,13 @@ impl LlamaCppArbiterService {
         if let Err(err) = self.apply_state().await {
             error!("Failed to apply reconciled state change: {err}");
         }
+
+        self.slot_aggregated_status_manager
+            .slot_aggregated_status
+            .set_is_draining(false);
+        self.slot_aggregated_status_manager
+            .slot_aggregated_status
+            .set_is_desired_state_pending(false);
     }
 }
 
@@ -188,18 +276,23 @@ impl Service for LlamaCppArbiterService {
     }
 
     async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let mut drain_ticker = interval(DRAIN_CHECK_INTERVAL);
         let mut reconciled_state = self.agent_applicable_state_holder.subscribe();
         let mut ticker = interval(Duration::from_secs(1));
 
+        drain_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
         ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         loop {
             tokio::select! {
                 _ = shutdown.recv() => break Ok(()),
+                _ = drain_ticker.tick(), if self.drain_deadline.is_some() => {
+                    self.check_drain().await;
+                }
                 _ = ticker.tick() => {
                     let current_status = self.slot_aggregated_status_manager.slot_aggregated_status.get_state_application_status()?;
 
-                    if current_status.should_try_to_apply() {
+                    if current_status.should_try_to_apply() && self.drain_deadline.is_none() {
                         self.slot_aggregated_status_manager
                             .slot_aggregated_status
                             .set_state_application_status(
@@ -219,7 +312,7 @@ impl Service for LlamaCppArbiterService {
                         .slot_aggregated_status
                         .set_state_application_status(AgentStateApplicationStatus::Fresh);
 
-                    self.try_to_apply_state().await;
+                    self.begin_state_change().await;
                 }
                 continue_from_conversation_history_request = self.continue_from_conversation_history_request_rx.recv() => {
                     match continue_from_conversation_history_request {
@@ -15,6 +15,8 @@ use llama_cpp_2::model::AddBos;
 use llama_cpp_2::model::LlamaModel;
 use llama_cpp_2::model::Special;
 use llama_cpp_2::sampling::LlamaSampler;
+use llama_cpp_2::token::LlamaToken;
+use llama_cpp_2::token::logit_bias::LlamaLogitBias;
 use log::debug;
 use log::error;
 use log::info;
@@ -26,17 +28,31 @@ use tokio::sync::mpsc;
 use crate::agent::continue_from_conversation_history_request::ContinueFromConversationHistoryRequest;
 use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
 use crate::agent::generate_embedding_batch_request::GenerateEmbeddingBatchRequest;
+use crate::agent::generation_choice::GenerationChoice;
 use crate::agent::kv_cache_repair_action::KVCacheRepairAction;
 use crate::agent::llamacpp_slot_context::LlamaCppSlotContext;
+use crate::agent::required_context_size::required_context_size;
 use crate::embedding::Embedding;
 use crate::embedding_input_tokenized::EmbeddingInputTokenized;
 use crate::embedding_normalization_method::EmbeddingNormalizationMethod;
 use crate::embedding_result::EmbeddingResult;
+use crate::generated_token::GeneratedToken;
+use crate::generated_token_logprobs::GeneratedTokenLogprobs;
 use crate::generated_token_result::GeneratedTokenResult;
+use crate::generation_summary::GenerationSummary;
+use crate::logit_bias_token::LogitBiasToken;
+use crate::logit_distribution::LogitDistribution;
 use crate::request_params::ContinueFromConversationHistoryParams;
 use crate::request_params::ContinueFromRawPromptParams;
 use crate::request_params::GenerateEmbeddingBatchParams;
+use crate::sampler_stage::SamplerStage;
 use crate::slot_status::SlotStatus;
+use crate::token_logit_bias::TokenLogitBias;
+use crate::token_logprob::TokenLogprob;
+use crate::validates::Validates as _;
+
+pub const MAX_CHOICES: u32 = 16;
+const MAX_TOP_LOGPROBS: usize = 20;
 
 pub struct LlamaCppSlot {
     index: u32,
@@ -155,9 +171,65 @@ impl LlamaCppSlot {
         &mut self,
         mut generate_tokens_stop_rx: mpsc::UnboundedReceiver<()>,
         generated_tokens_tx: mpsc::UnboundedSender<GeneratedTokenResult>,
-        max_tokens: i32,
-        prompt: String,
+        ContinueFromRawPromptParams {
+            logit_bias,
+            logprobs,
+            max_tokens,
+            n,
+            raw_prompt,
+            sampler_chain,
+            seed,
+            top_logprobs,
+        }: ContinueFromRawPromptParams,
     ) -> Result<()> {
+        if n < 1 || n > MAX_CHOICES {
+            return self.reject_generation(
+                &generated_tokens_tx,
+                format!("Number of choices must be between 1 and {MAX_CHOICES}, got {n}"),
+            );
+        }
+
+        let mut sampler_chain = sampler_chain
+            .unwrap_or_else(|| self.slot_context.inference_parameters.sampler_chain.clone());
+
+        if !logit_bias.is_empty() {
+            // Tokens outside of the vocabulary are the client's mistake, not a slot failure
+            if let Err(err) = self.resolve_logit_biases(&logit_bias) {
+                return self.reject_generation(
+                    &generated_tokens_tx,
+                    format!("Invalid logit bias: {err:#}"),
+                );
+            }
+
+            sampler_chain.insert(0, SamplerStage::LogitBias(logit_bias));
+        }
+
+        let sampler_chain = match sampler_chain.validate() {
+            Ok(sampler_chain) => sampler_chain,
+            Err(err) => {
+                return self.reject_generation(
+                    &generated_tokens_tx,
+                    format!("Invalid sampler chain: {err:#}"),
+                );
+            }
+        };
+        let seed = seed.unwrap_or_else(|| self.rng.random::<u32>());
+        // Grammars can still fail to compile against the model vocabulary
+        let samplers = match (0..n)
+            .map(|choice_index| {
+                self.create_sampler(&sampler_chain, seed.wrapping_add(choice_index))
+            })
+            .collect::<Result<Vec<_>>>()
+        {
+            Ok(samplers) => samplers,
+            Err(err) => {
+                return self.reject_generation(
+                    &generated_tokens_tx,
+                    format!("Unable to create the sampler chain: {err:#}"),
+                );
+            }
+        };
+
         let _guard = self.status.take_slot_with_guard();
 
         self.llama_context.clear_kv_cache();
@@ -165,7 +237,19 @@ impl LlamaCppSlot {
         let tokens_list = self
             .slot_context
             .model
-            .str_to_token(&prompt, AddBos::Always)?;
+            .str_to_token(&raw_prompt, AddBos::Always)?;
+        let prompt_tokens = tokens_list.len();
+        let context_size = self.slot_context.inference_parameters.context_size as usize;
+
+        if required_context_size(prompt_tokens, max_tokens, n) > context_size {
+            return self.reject_generation(
+                &generated_tokens_tx,
+                format!(
+                    "Prompt of {prompt_tokens} tokens with max_tokens {max_tokens} and {n} choices does not fit in the context size of {context_size} tokens"
+                ),
+            );
+        }
+
         let mut batch = LlamaBatch::new(self.slot_context.inference_parameters.batch_n_tokens, 1);
         let last_index = tokens_list.len() as i32 - 1;
 
@@ -177,37 +261,71 @@ impl LlamaCppSlot {
 
         self.continuation_batch_decode(&mut batch, &mut vec![])?;
 
+        // The prompt is evaluated once, then every other choice continues from a copy of it
+        for choice_index in 1..n {
+            self.llama_context
+                .copy_kv_cache_seq(0, choice_index as i32, None, None)?;
+        }
+
         let mut n_cur = batch.n_tokens();
-        let mut decoder = encoding_rs::UTF_8.new_decoder();
-
-        let mut sampler = LlamaSampler::chain_simple([
-            LlamaSampler::penalties(
-                self.slot_context.inference_parameters.penalty_last_n,
-                self.slot_context.inference_parameters.penalty_repeat,
-                self.slot_context.inference_parameters.penalty_frequency,
-                self.slot_context.inference_parameters.penalty_presence,
-            ),
-            LlamaSampler::top_k(self.slot_context.inference_parameters.top_k),
-            LlamaSampler::top_p(self.slot_context.inference_parameters.top_p, 0),
-            LlamaSampler::min_p(self.slot_context.inference_parameters.min_p, 0),
-            LlamaSampler::temp(self.slot_context.inference_parameters.temperature),
-            LlamaSampler::dist(self.rng.random::<u32>()),
-            LlamaSampler::greedy(),
-        ]);
+        let mut choices: Vec<GenerationChoice> = (0..n)
+            .zip(samplers)
+            .map(|(choice_index, sampler)| GenerationChoice {
+                decoder: encoding_rs::UTF_8.new_decoder(),
+                index: choice_index,
+                logits_index: batch.n_tokens() - 1,
+                sampler,
+            })
+            .collect();
 
         while n_cur <= max_tokens {
             if generate_tokens_stop_rx.try_recv().is_ok() {
                 break;
             }
 
-            // sample the next token
-            {
-                let token = sampler.sample(&self.llama_context, batch.n_tokens() - 1);
+            let mut sampled_tokens: Vec<(u32, LlamaToken)> = Vec::with_capacity(choices.len());
+
+            // sample the next token of each choice
+            for choice in &mut choices {
+                let (token, token_logprobs) = if logprobs {
+                    // Same as `sample`, but reads the logprobs from the logits before
+                    // the sampler chain truncates or rescales them
+                    let mut candidates =
+                        self.llama_context.token_data_array_ith(choice.logits_index);
+                    let distribution = LogitDistribution::new(
+                        candidates
+                            .data
+                            .iter()
+                            .map(|candidate| (candidate.id().0, candidate.logit())),
+                    );
 
-                sampler.accept(token);
+                    choice.sampler.apply(&mut candidates);
+
+                    let token = candidates
+                        .selected_token()
+                        .ok_or_else(|| anyhow!("Sampler chain did not select a token"))?;
+
+                    (
+                        token,
+                        Some(self.token_logprobs(
+                            &distribution,
+                            token,
+                            top_logprobs.min(MAX_TOP_LOGPROBS),
+                        )?),
+                    )
+                } else {
+                    (
+                        choice
+                            .sampler
+                            .sample(&self.llama_context, choice.logits_index),
+                        None,
+                    )
+                };
+
+                choice.sampler.accept(token);
 
                 if token == self.slot_context.model.token_eos() {
-                    break;
+                    continue;
                 }
 
                 let output_bytes = self
@@ -216,12 +334,34 @@ impl LlamaCppSlot {
                     .token_to_bytes(token, Special::Tokenize)?;
                 let mut output_string = String::with_capacity(32);
                 let _decode_result =
-                    decoder.decode_to_string(&output_bytes, &mut output_string, false);
+                    choice
+                        .decoder
+                        .decode_to_string(&output_bytes, &mut output_string, false);
+
+                generated_tokens_tx.send(GeneratedTokenResult::Token(GeneratedToken {
+                    choice_index: choice.index,
+                    content: output_string,
+                    logprobs: token_logprobs,
+                }))?;
+
+                sampled_tokens.push((choice.index, token));
+            }
 
-                generated_tokens_tx.send(GeneratedTokenResult::Token(output_string))?;
+            choices.retain(|choice| {
+                sampled_tokens
+                    .iter()
+                    .any(|(choice_index, _)| *choice_index == choice.index)
+            });
 
-                batch.clear();
-                batch.add(token, n_cur, &[0], true)?;
+            if choices.is_empty() {
+                break;
+            }
+
+            batch.clear();
+
+            for (choice, (choice_index, token)) in choices.iter_mut().zip(sampled_tokens) {
+                batch.add(token, n_cur, &[choice_index as i32], true)?;
+                choice.logits_index = batch.n_tokens() - 1;
             }
 
             n_cur += 1;
@@ -229,11 +369,98 @@ impl LlamaCppSlot {
             self.continuation_batch_decode(&mut batch, &mut vec![])?;
         }
 
-        generated_tokens_tx.send(GeneratedTokenResult::Done)?;
+        generated_tokens_tx.send(GeneratedTokenResult::Done(GenerationSummary {
+            prompt_tokens,
+            seed,
+        }))?;
 
         Ok(())
     }
 
+    /// Ends the response stream with an error, so the client does not wait for the tokens
+    fn reject_generation(
+        &self,
+        generated_tokens_tx: &mpsc::UnboundedSender<GeneratedTokenResult>,
+        message: String,
+    ) -> Result<()> {
+        error!(
+            "{:?}: slot {} rejected the request: {message}",
+            self.slot_context.agent_name, self.index
+        );
+
+        generated_tokens_tx.send(GeneratedTokenResult::Error(message.clone()))?;
+
+        Err(anyhow!(message))
+    }
+
+    fn create_sampler(&self, sampler_chain: &[SamplerStage], seed: u32) -> Result<LlamaSampler> {
+        let inference_parameters = &self.slot_context.inference_parameters;
+        let model = &self.slot_context.model;
+        let mut samplers = sampler_chain
+            .iter()
+            .map(|sampler_stage| {
+                Ok(match sampler_stage {
+                    SamplerStage::Dry {
+                        allowed_length,
+                        base,
+                        multiplier,
+                        penalty_last_n,
+                        sequence_breakers,
+                    } => LlamaSampler::dry(
+                        model,
+                        *multiplier,
+                        *base,
+                        *allowed_length,
+                        *penalty_last_n,
+                        sequence_breakers,
+                    ),
+                    SamplerStage::Grammar { grammar, root } => {
+                        LlamaSampler::grammar(model, grammar, root)
+                            .context("Unable to compile the grammar")?
+                    }
+                    SamplerStage::LogitBias(biases) => LlamaSampler::logit_bias(
+                        model.n_vocab(),
+                        &self.resolve_logit_biases(biases)?,
+                    ),
+                    SamplerStage::MinP => LlamaSampler::min_p(inference_parameters.min_p, 0),
+                    SamplerStage::Mirostat { eta, m, tau } => {
+                        LlamaSampler::mirostat(model.n_vocab(), seed, *tau, *eta, *m)
+                    }
+                    SamplerStage::MirostatV2 { eta, tau } => {
+                        LlamaSampler::mirostat_v2(seed, *tau, *eta)
+                    }
+                    SamplerStage::Penalties => LlamaSampler::penalties(
+                        inference_parameters.penalty_last_n,
+                        inference_parameters.penalty_repeat,
+                        inference_parameters.penalty_frequency,
+                        inference_parameters.penalty_presence,
+                    ),
+                    SamplerStage::Temperature => {
+                        LlamaSampler::temp(inference_parameters.temperature)
+                    }
+                    SamplerStage::TopK => LlamaSampler::top_k(inference_parameters.top_k),
+                    SamplerStage::TopP => LlamaSampler::top_p(inference_parameters.top_p, 0),
+                    SamplerStage::TypicalP { p } => LlamaSampler::typical(*p, 0),
+                    SamplerStage::Xtc {
+                        probability,
+                        threshold,
+                    } => LlamaSampler::xtc(*probability, *threshold, 0, seed),
+                })
+            })
+            .collect::<Result<Vec<LlamaSampler>>>()?;
+
+        // Mirostat picks the token on its own, anything else needs a final selection stage
+        if !sampler_chain
+            .last()
+            .is_some_and(SamplerStage::selects_token)
+        {
+            samplers.push(LlamaSampler::dist(seed));
+            samplers.push(LlamaSampler::greedy());
+        }
+
+        Ok(LlamaSampler::chain_simple(samplers))
+    }
+
     fn generate_embedding_batch(
         &mut self,
         GenerateEmbeddingBatchRequest {
@@ -318,6 +545,93 @@ impl LlamaCppSlot {
 
         Ok(())
     }
+
+    fn resolve_logit_biases(&self, biases: &[TokenLogitBias]) -> Result<Vec<LlamaLogitBias>> {
+        let n_vocab = self.slot_context.model.n_vocab();
+        let mut resolved_biases: Vec<LlamaLogitBias> = Vec::with_capacity(biases.len());
+
+        for token_logit_bias in biases {
+            let logit_offset = token_logit_bias.logit_offset();
+
+            match &token_logit_bias.token {
+                LogitBiasToken::Id(token_id) => {
+                    if *token_id >= n_vocab {
+                        return Err(anyhow!(
+                            "Logit bias token id {token_id} is outside of the model vocabulary ({n_vocab} tokens)"
+                        ));
+                    }
+
+                    resolved_biases.push(LlamaLogitBias::new(LlamaToken(*token_id), logit_offset));
+                }
+                LogitBiasToken::Text(text) => {
+                    // Words in the middle of a sentence are usually tokenized with the
+                    // preceding space. Sub-tokens of longer texts are not biased, since
+                    // they are shared with unrelated words.
+                    let mut single_tokens: Vec<LlamaToken> = Vec::with_capacity(2);
+
+                    for variant in [text.clone(), format!(" {text}")] {
+                        match self
+                            .slot_context
+                            .model
+                            .str_to_token(&variant, AddBos::Never)?[..]
+                        {
+                            [llama_token] if !single_tokens.contains(&llama_token) => {
+                                single_tokens.push(llama_token);
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    if single_tokens.is_empty() {
+                        return Err(anyhow!(
+                            "Logit bias text {text:?} is not a single token of the model, bias its token ids instead"
+                        ));
+                    }
+
+                    for llama_token in single_tokens {
+                        resolved_biases.push(LlamaLogitBias::new(llama_token, logit_offset));
+                    }
+                }
+            }
+        }
+
+        Ok(resolved_biases)
+    }
+
+    fn token_logprob(&self, token_id: i32, logprob: f32) -> Result<TokenLogprob> {
+        let bytes = self
+            .slot_context
+            .model
+            .token_to_bytes(LlamaToken(token_id), Special::Tokenize)?;
+
+        Ok(TokenLogprob {
+            token: String::from_utf8_lossy(&bytes).to_string(),
+            bytes,
+            logprob,
+            token_id,
+        })
+    }
+
+    fn token_logprobs(
+        &self,
+        distribution: &LogitDistribution,
+        token: LlamaToken,
+        top_logprobs: usize,
+    ) -> Result<GeneratedTokenLogprobs> {
+        Ok(GeneratedTokenLogprobs {
+            bytes: self
+                .slot_context
+                .model
+                .token_to_bytes(token, Special::Tokenize)?,
+            logprob: distribution.logprob(token.0),
+            token_id: token.0,
+            top_logprobs: distribution
+                .top(top_logprobs)
+                .into_iter()
+                .map(|(token_id, logprob)| self.token_logprob(token_id, logprob))
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
 }
 
 impl Actor for LlamaCppSlot {
@@ -357,8 +671,14 @@ impl Handler<ContinueFromConversationHistoryRequest> for LlamaCppSlot {
                     add_generation_prompt,
                     enable_thinking,
                     conversation_history,
+                    logit_bias,
+                    logprobs,
                     max_tokens,
+                    n,
+                    sampler_chain,
+                    seed,
                     tools,
+                    top_logprobs,
                 },
         }: ContinueFromConversationHistoryRequest,
         _ctx: &mut Self::Context,
@@ -404,8 +724,16 @@ impl Handler<ContinueFromConversationHistoryRequest> for LlamaCppSlot {
         self.continue_from_raw_prompt(
             generate_tokens_stop_rx,
             generated_tokens_tx,
-            max_tokens,
-            raw_prompt,
+            ContinueFromRawPromptParams {
+                logit_bias,
+                logprobs,
+                max_tokens,
+                n,
+                raw_prompt,
+                sampler_chain,
+                seed,
+                top_logprobs,
+            },
         )
     }
 }
@@ -418,20 +746,11 @@ impl Handler<ContinueFromRawPromptRequest> for LlamaCppSlot {
         ContinueFromRawPromptRequest {
             generate_tokens_stop_rx,
             generated_tokens_tx,
-            params:
-                ContinueFromRawPromptParams {
-                    max_tokens,
-                    raw_prompt,
-                },
+            params,
         }: ContinueFromRawPromptRequest,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.continue_from_raw_prompt(
-            generate_tokens_stop_rx,
-            generated_tokens_tx,
-            max_tokens,
-            raw_prompt,
-        )
+        self.continue_from_raw_prompt(generate_tokens_stop_rx, generated_tokens_tx, params)
     }
 }
 
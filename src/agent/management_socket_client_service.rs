@@ -1,272 +1,97 @@
+use std::collections::BTreeMap;
+use std::io;
 use std::sync::Arc;
 
 use actix_web::rt;
 use actix_web::web::Bytes;
 use anyhow::Context;
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
 use futures_util::SinkExt as _;
-use log::debug;
 use futures_util::StreamExt;
 use log::error;
 use log::info;
 use log::warn;
+use rustls::ClientConfig;
+use tokio::net::TcpStream;
+use tokio::net::UnixStream;
+use tokio::net::lookup_host;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
-use tokio::time::interval;
 use tokio::time::Duration;
 use tokio::time::MissedTickBehavior;
-use tokio_tungstenite::connect_async;
+use tokio::time::interval;
+use tokio::time::sleep;
+use tokio::time::timeout;
+use tokio_tungstenite::Connector;
+use tokio_tungstenite::MaybeTlsStream;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::client_async_tls_with_config;
 use tokio_tungstenite::tungstenite::protocol::Message;
 
-use crate::agent_desired_state::AgentDesiredState;
-use crate::agent::receive_stream_stopper_collection::ReceiveStreamStopperCollection;
+use crate::agent::balancer_connection_state::BalancerConnectionState;
+use crate::agent::balancer_connection_state_holder::BalancerConnectionStateHolder;
+use crate::agent::balancer_message_handler::BalancerMessageHandler;
+use crate::agent::continue_from_conversation_history_request::ContinueFromConversationHistoryRequest;
+use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
+use crate::agent::generate_embedding_batch_request::GenerateEmbeddingBatchRequest;
 use crate::agent::jsonrpc::Message as JsonRpcMessage;
-use crate::agent::jsonrpc::Notification as JsonRpcNotification;
-use crate::agent::jsonrpc::Request as JsonRpcRequest;
-use crate::agent::jsonrpc::Response as JsonRpcResponse;
-use crate::agent_applicable_state_holder::AgentApplicableStateHolder;
 use crate::agent::jsonrpc::notification_params::SetStateParams;
-use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
-use crate::agent::jsonrpc::notification_params::VersionParams;
-use crate::slot_aggregated_status::SlotAggregatedStatus;
-use crate::agent::from_request_params::FromRequestParams;
+use crate::agent::management_socket_stream::ManagementSocketStream;
+use crate::agent::model_metadata_holder::ModelMetadataHolder;
+use crate::agent::receive_stream_stopper_collection::ReceiveStreamStopperCollection;
+use crate::agent::reconnect_backoff::ReconnectBackoff;
+use crate::agent_applicable_state_holder::AgentApplicableStateHolder;
 use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Message as ManagementJsonRpcMessage;
 use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Notification as ManagementJsonRpcNotification;
-use crate::agent::continue_from_conversation_history_request::ContinueFromConversationHistoryRequest;
-use crate::agent::generate_embedding_batch_request::GenerateEmbeddingBatchRequest;
 use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::notification_params::RegisterAgentParams;
 use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::notification_params::UpdateAgentStatusParams;
-use crate::jsonrpc::Error as JsonRpcError;
-use crate::jsonrpc::ResponseEnvelope;
-use crate::jsonrpc::RequestEnvelope;
+use crate::listen_addr::UNIX_SOCKET_PREFIX;
 use crate::produces_snapshot::ProducesSnapshot;
-use crate::jsonrpc::ErrorEnvelope;
 use crate::service::Service;
-use crate::agent::model_metadata_holder::ModelMetadataHolder;
+use crate::slot_aggregated_status::SlotAggregatedStatus;
 
-struct IncomingMessageContext {
-    agent_applicable_state_holder: Arc<AgentApplicableStateHolder>,
-    agent_desired_state_tx: mpsc::UnboundedSender<AgentDesiredState>,
-    connection_close_tx: broadcast::Sender<()>,
-    continue_from_conversation_history_request_tx:
-        mpsc::UnboundedSender<ContinueFromConversationHistoryRequest>,
-    continue_from_raw_prompt_request_tx: mpsc::UnboundedSender<ContinueFromRawPromptRequest>,
-    generate_embedding_batch_request_tx: mpsc::UnboundedSender<GenerateEmbeddingBatchRequest>,
-    model_metadata_holder: Arc<ModelMetadataHolder>,
-    receive_stream_stopper_collection: Arc<ReceiveStreamStopperCollection>,
-    message_tx: mpsc::UnboundedSender<ManagementJsonRpcMessage>,
-}
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+type ManagementWebSocketStream = WebSocketStream<MaybeTlsStream<Box<dyn ManagementSocketStream>>>;
 
 pub struct ManagementSocketClientService {
     pub agent_applicable_state_holder: Arc<AgentApplicableStateHolder>,
-    pub agent_desired_state_tx: mpsc::UnboundedSender<AgentDesiredState>,
+    pub agent_desired_state_tx: mpsc::UnboundedSender<SetStateParams>,
+    pub agent_id: String,
+    pub balancer_connection_state_holder: Arc<BalancerConnectionStateHolder>,
     pub continue_from_conversation_history_request_tx:
         mpsc::UnboundedSender<ContinueFromConversationHistoryRequest>,
     pub continue_from_raw_prompt_request_tx: mpsc::UnboundedSender<ContinueFromRawPromptRequest>,
     pub generate_embedding_batch_request_tx: mpsc::UnboundedSender<GenerateEmbeddingBatchRequest>,
+    pub labels: BTreeMap<String, String>,
+    pub management_addrs: Vec<String>,
     pub model_metadata_holder: Arc<ModelMetadataHolder>,
     pub name: Option<String>,
     pub receive_stream_stopper_collection: Arc<ReceiveStreamStopperCollection>,
     pub slot_aggregated_status: Arc<SlotAggregatedStatus>,
-    pub socket_url: String,
+    /// Connects over `wss://` when set
+    pub tls_client_config: Option<Arc<ClientConfig>>,
 }
 
 impl ManagementSocketClientService {
-    async fn generate_responses<TRequest: FromRequestParams + 'static>(
-        connection_close_tx: broadcast::Sender<()>,
-        id: String,
-        message_tx: mpsc::UnboundedSender<ManagementJsonRpcMessage>,
-        request_params: TRequest::RequestParams,
-        receive_stream_stopper_collection: Arc<ReceiveStreamStopperCollection>,
-        request_tx: mpsc::UnboundedSender<TRequest>,
-    ) -> Result<()> {
-        let (response_tx, mut response_rx) = mpsc::unbounded_channel::<TRequest::Response>();
-        let (stop_tx, stop_rx) = mpsc::unbounded_channel::<()>();
-
-        let _guard = receive_stream_stopper_collection
-            .register_stopper_with_guard(id.clone(), stop_tx)
-            .context(format!("Failed to register stopper for request: {id}"))?;
-
-        request_tx.send(TRequest::from_request_params(
-            request_params,
-            response_tx,
-            stop_rx,
-        ))?;
-
-        let mut connection_close_rx = connection_close_tx.subscribe();
-
-        loop {
-            tokio::select! {
-                _ = connection_close_rx.recv() => break,
-                response = response_rx.recv() => {
-                    match response {
-                        Some(response) => {
-                            message_tx.send(
-                                ManagementJsonRpcMessage::Response(
-                                    ResponseEnvelope {
-                                        request_id: id.clone(),
-                                        response: response.into(),
-                                    }
-                                ),
-                            )?;
-                        }
-                        None => break,
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    async fn handle_deserialized_message(
-        IncomingMessageContext {
-            agent_applicable_state_holder,
-            agent_desired_state_tx,
-            connection_close_tx,
-            continue_from_conversation_history_request_tx,
-            continue_from_raw_prompt_request_tx,
-            generate_embedding_batch_request_tx,
-            message_tx,
-            model_metadata_holder,
-            receive_stream_stopper_collection,
-        }: IncomingMessageContext,
-        deserialized_message: JsonRpcMessage,
-    ) -> Result<()> {
-        match deserialized_message {
-            JsonRpcMessage::Error(ErrorEnvelope {
-                request_id,
-                error: JsonRpcError { code, description },
-            }) => {
-                error!(
-                    "Received error from server: code: {code}, description: {description:?}, request_id: {request_id:?}"
-                );
-
-                Ok(())
-            }
-            JsonRpcMessage::Notification(JsonRpcNotification::SetState(SetStateParams {
-                desired_state,
-            })) => {
-                agent_desired_state_tx.send(desired_state)?;
-
-                Ok(())
-            }
-            JsonRpcMessage::Notification(JsonRpcNotification::StopRespondingTo(request_id)) => {
-                debug!("Received StopGeneratingTokens notification for request ID: {request_id:?}");
-                receive_stream_stopper_collection
-                    .stop(request_id.clone())
-                    .context(format!(
-                        "Failed to stop generating tokens for request ID: {request_id}"
-                    ))?;
-
-                Ok(())
-            }
-            JsonRpcMessage::Notification(JsonRpcNotification::Version(VersionParams {
-                version,
-            })) => {
-                if version != env!("CARGO_PKG_VERSION") {
-                    warn!(
-                        "Version mismatch: server version is {version}, client version is {}",
-                        env!("CARGO_PKG_VERSION")
-                    );
-                }
-
-                Ok(())
-            }
-            JsonRpcMessage::Request(RequestEnvelope {
-                id,
-                request:
-                    JsonRpcRequest::ContinueFromConversationHistory(
-                        continue_from_conversation_history_params,
-                    ),
-            }) => {
-                Self::generate_responses(
-                    connection_close_tx,
-                    id,
-                    message_tx,
-                    continue_from_conversation_history_params,
-                    receive_stream_stopper_collection,
-                    continue_from_conversation_history_request_tx,
-                )
-                .await
-            }
-            JsonRpcMessage::Request(RequestEnvelope {
-                id,
-                request: JsonRpcRequest::ContinueFromRawPrompt(generate_tokens_params),
-            }) => {
-                Self::generate_responses(
-                    connection_close_tx,
-                    id,
-                    message_tx,
-                    generate_tokens_params,
-                    receive_stream_stopper_collection,
-                    continue_from_raw_prompt_request_tx,
-                )
-                .await
-            }
-            JsonRpcMessage::Request(RequestEnvelope {
-                id,
-                request: JsonRpcRequest::GenerateEmbeddingBatch(generate_embedding_batch_params),
-            }) => {
-                Self::generate_responses(
-                    connection_close_tx,
-                    id,
-                    message_tx,
-                    generate_embedding_batch_params,
-                    receive_stream_stopper_collection,
-                    generate_embedding_batch_request_tx,
-                )
-                .await
-            }
-            JsonRpcMessage::Request(RequestEnvelope {
-                id,
-                request: JsonRpcRequest::GetChatTemplateOverride,
-            }) => Ok(
-                message_tx.send(ManagementJsonRpcMessage::Response(ResponseEnvelope {
-                    request_id: id.clone(),
-                    response: JsonRpcResponse::ChatTemplateOverride(
-                        if let Some(agent_applicable_state) =
-                            agent_applicable_state_holder.get_agent_applicable_state()
-                        {
-                            agent_applicable_state.chat_template_override.clone()
-                        } else {
-                            None
-                        },
-                    ),
-                }))?,
-            ),
-            JsonRpcMessage::Request(RequestEnvelope {
-                id,
-                request: JsonRpcRequest::GetModelMetadata,
-            }) => Ok(
-                message_tx.send(ManagementJsonRpcMessage::Response(ResponseEnvelope {
-                    request_id: id.clone(),
-                    response: JsonRpcResponse::ModelMetadata(
-                        model_metadata_holder.get_model_metadata(),
-                    ),
-                }))?,
-            ),
-        }
-    }
-
     async fn handle_incoming_message(
-        incoming_message_context: IncomingMessageContext,
+        balancer_message_handler: BalancerMessageHandler,
         msg: Message,
         pong_tx: mpsc::UnboundedSender<Bytes>,
     ) -> Result<()> {
         match msg {
             Message::Text(text) => {
                 let mut connection_close_rx =
-                    incoming_message_context.connection_close_tx.subscribe();
+                    balancer_message_handler.connection_close_tx.subscribe();
 
                 rt::spawn(async move {
                     tokio::select! {
                         _ = connection_close_rx.recv() => {
                             info!("Connection close signal received, shutting down");
                         }
-                        result = Self::handle_deserialized_message(
-                            incoming_message_context,
+                        result = balancer_message_handler.handle_message(
                             match serde_json::from_str::<JsonRpcMessage>(&text).context(format!("Failed to parse JSON-RPC message: {text}")) {
                                 Ok(message) => message,
                                 Err(err) => {
@@ -306,12 +131,105 @@ impl ManagementSocketClientService {
         }
     }
 
-    async fn keep_connection_alive(&self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        info!("Connecting to management server at {}", self.socket_url);
+    /// Tries the management addresses in order, with every address their host names resolve to.
+    /// Host names are resolved on each attempt, so a moved balancer is picked up.
+    async fn connect(&self) -> Result<ManagementWebSocketStream> {
+        self.balancer_connection_state_holder
+            .set_balancer_connection_state(BalancerConnectionState::Connecting);
+
+        let (scheme, connector) = match &self.tls_client_config {
+            Some(tls_client_config) => ("wss", Some(Connector::Rustls(tls_client_config.clone()))),
+            None => ("ws", None),
+        };
 
-        let (ws_stream, _response) = connect_async(self.socket_url.clone()).await?;
+        for management_addr in &self.management_addrs {
+            if let Some(path) = management_addr.strip_prefix(UNIX_SOCKET_PREFIX) {
+                // Host name is only needed for the handshake, and TLS is not used over Unix sockets
+                let socket_url = format!("ws://localhost/api/v1/agent_socket/{}", self.agent_id);
 
-        info!("Connected to management server");
+                info!("Connecting to management server at {management_addr}");
+
+                match Self::handshake(socket_url, UnixStream::connect(path), None).await {
+                    Ok(ws_stream) => {
+                        info!("Connected to management server at {management_addr}");
+
+                        return Ok(ws_stream);
+                    }
+                    Err(err) => {
+                        warn!("Failed to connect to management server at {management_addr}: {err}");
+                    }
+                }
+
+                continue;
+            }
+
+            let socket_addrs = match lookup_host(management_addr).await {
+                Ok(socket_addrs) => socket_addrs,
+                Err(err) => {
+                    warn!("Failed to resolve management server address {management_addr}: {err}");
+
+                    continue;
+                }
+            };
+
+            // URL keeps the host name, so the TLS certificate is verified against it
+            let socket_url = format!(
+                "{scheme}://{management_addr}/api/v1/agent_socket/{}",
+                self.agent_id
+            );
+
+            for socket_addr in socket_addrs {
+                info!("Connecting to management server at {socket_url} ({socket_addr})");
+
+                match Self::handshake(
+                    socket_url.clone(),
+                    TcpStream::connect(socket_addr),
+                    connector.clone(),
+                )
+                .await
+                {
+                    Ok(ws_stream) => {
+                        info!("Connected to management server at {socket_addr}");
+
+                        return Ok(ws_stream);
+                    }
+                    Err(err) => {
+                        warn!("Failed to connect to management server at {socket_addr}: {err}");
+                    }
+                }
+            }
+        }
+
+        Err(anyhow!(
+            "None of the management server addresses accepted the connection"
+        ))
+    }
+
+    async fn handshake<TStream: ManagementSocketStream + 'static>(
+        socket_url: String,
+        stream: impl Future<Output = io::Result<TStream>>,
+        connector: Option<Connector>,
+    ) -> Result<ManagementWebSocketStream> {
+        let (ws_stream, _response) = timeout(CONNECT_TIMEOUT, async {
+            let stream: Box<dyn ManagementSocketStream> = Box::new(stream.await?);
+
+            Ok::<_, anyhow::Error>(
+                client_async_tls_with_config(socket_url, stream, None, connector).await?,
+            )
+        })
+        .await
+        .map_err(|_| anyhow!("Timed out after {CONNECT_TIMEOUT:?}"))??;
+
+        Ok(ws_stream)
+    }
+
+    async fn keep_connection_alive(
+        &self,
+        ws_stream: ManagementWebSocketStream,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> Result<()> {
+        self.balancer_connection_state_holder
+            .set_balancer_connection_state(BalancerConnectionState::Connected);
 
         let (connection_close_tx, mut connection_close_rx) = broadcast::channel::<()>(1);
         let (message_tx, mut message_rx) = mpsc::unbounded_channel::<ManagementJsonRpcMessage>();
@@ -385,6 +303,7 @@ impl ManagementSocketClientService {
                 message_tx
                     .send(ManagementJsonRpcMessage::Notification(
                         ManagementJsonRpcNotification::RegisterAgent(RegisterAgentParams {
+                            labels: self.labels.clone(),
                             name: self.name.clone(),
                             slot_aggregated_status_snapshot,
                         }),
@@ -433,7 +352,7 @@ impl ManagementSocketClientService {
                     let should_close = match msg {
                         Some(Ok(msg)) => {
                             if let Err(err) = Self::handle_incoming_message(
-                                    IncomingMessageContext {
+                                    BalancerMessageHandler {
                                         agent_applicable_state_holder: self.agent_applicable_state_holder.clone(),
                                         agent_desired_state_tx: self.agent_desired_state_tx.clone(),
                                         connection_close_tx: connection_close_tx.clone(),
@@ -489,24 +408,43 @@ impl Service for ManagementSocketClientService {
     }
 
     async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        let mut ticker = interval(Duration::from_secs(1));
-
-        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut reconnect_backoff = ReconnectBackoff::default();
 
         loop {
             tokio::select! {
                 _ = shutdown.recv() => break Ok(()),
-                _ = ticker.tick() => {
-                    match self.keep_connection_alive(shutdown.resubscribe()).await {
-                        Err(err) => {
-                            error!("Failed to keep the connection alive: {err:?}");
-                        }
-                        Ok(()) => {
-                            info!("Gracefully closed connection to management server");
+                ws_stream = self.connect() => {
+                    match ws_stream {
+                        Ok(ws_stream) => {
+                            // Registering again sends the current slot status, so a restarted
+                            // balancer learns about the agent right away
+                            reconnect_backoff.reset();
+
+                            match self.keep_connection_alive(ws_stream, shutdown.resubscribe()).await {
+                                Err(err) => {
+                                    error!("Failed to keep the connection alive: {err:?}");
+                                }
+                                Ok(()) => {
+                                    info!("Gracefully closed connection to management server");
+                                }
+                            }
                         }
+                        Err(err) => error!("{err}"),
                     }
+
+                    self.balancer_connection_state_holder
+                        .set_balancer_connection_state(BalancerConnectionState::Disconnected);
                 }
             }
+
+            let reconnect_delay = reconnect_backoff.next_delay();
+
+            info!("Reconnecting to management server in {reconnect_delay:?}");
+
+            tokio::select! {
+                _ = shutdown.recv() => break Ok(()),
+                _ = sleep(reconnect_delay) => {}
+            }
         }
     }
 }
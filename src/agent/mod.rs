@@ -1,7 +1,15 @@
+mod agent_readiness;
+pub mod balancer_connection_state;
+pub mod balancer_connection_state_holder;
+mod balancer_message_handler;
 pub mod continue_from_conversation_history_request;
 pub mod continue_from_raw_prompt_request;
+mod estimate_slots_total;
 mod from_request_params;
 pub mod generate_embedding_batch_request;
+mod generation_choice;
+pub mod health_service;
+pub mod in_process_balancer_client_service;
 pub mod jsonrpc;
 mod kv_cache_repair_action;
 mod llamacpp_arbiter;
@@ -10,7 +18,11 @@ pub mod llamacpp_arbiter_service;
 mod llamacpp_slot;
 mod llamacpp_slot_context;
 pub mod management_socket_client_service;
+mod management_socket_stream;
 pub mod model_metadata_holder;
 pub mod receive_stream_stopper_collection;
 mod receive_stream_stopper_drop_guard;
 pub mod reconciliation_service;
+mod reconnect_backoff;
+mod required_context_size;
+pub mod slot_drain;
@@ -9,6 +9,7 @@ use tokio::time::Duration;
 use tokio::time::MissedTickBehavior;
 use tokio::time::interval;
 
+use crate::agent::jsonrpc::notification_params::SetStateParams;
 use crate::agent_applicable_state_holder::AgentApplicableStateHolder;
 use crate::agent_desired_state::AgentDesiredState;
 use crate::agent_issue_fix::AgentIssueFix;
@@ -19,7 +20,7 @@ use crate::slot_aggregated_status::SlotAggregatedStatus;
 pub struct ReconciliationService {
     pub agent_applicable_state_holder: Arc<AgentApplicableStateHolder>,
     pub agent_desired_state: Option<AgentDesiredState>,
-    pub agent_desired_state_rx: mpsc::UnboundedReceiver<AgentDesiredState>,
+    pub agent_desired_state_rx: mpsc::UnboundedReceiver<SetStateParams>,
     pub is_converted_to_applicable_state: bool,
     pub slot_aggregated_status: Arc<SlotAggregatedStatus>,
 }
@@ -75,16 +76,21 @@ impl Service for ReconciliationService {
                         self.try_convert_to_applicable_state().await;
                     }
                 },
-                next_agent_desired_state = self.agent_desired_state_rx.recv() => {
+                next_set_state_params = self.agent_desired_state_rx.recv() => {
                     self.is_converted_to_applicable_state = false;
-                    self.agent_desired_state = match next_agent_desired_state {
-                        Some(agent_desired_state) => Some(agent_desired_state),
+                    self.agent_desired_state = match next_set_state_params {
+                        Some(SetStateParams { desired_state, generation }) => {
+                            self.slot_aggregated_status.set_desired_state_generation(generation);
+
+                            Some(desired_state)
+                        }
                         None => {
                             error!("Agent desired state channel closed, stopping reconciliation service.");
 
                             break Ok(())
                         }
                     };
+                    self.slot_aggregated_status.set_is_desired_state_pending(true);
                     self.try_convert_to_applicable_state().await;
                 }
             }
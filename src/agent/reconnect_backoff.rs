@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use rand::Rng as _;
+
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Exponential backoff with jitter, so agents that lost the balancer at the same time
+/// do not reconnect all at once
+#[derive(Default)]
+pub struct ReconnectBackoff {
+    failed_attempts: u32,
+}
+
+impl ReconnectBackoff {
+    /// Half of the delay is fixed and the other half is random
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = INITIAL_RECONNECT_DELAY
+            .saturating_mul(2u32.saturating_pow(self.failed_attempts))
+            .min(MAX_RECONNECT_DELAY);
+
+        self.failed_attempts = self.failed_attempts.saturating_add(1);
+
+        delay / 2 + delay.mul_f64(rand::rng().random_range(0.0..0.5))
+    }
+
+    pub fn reset(&mut self) {
+        self.failed_attempts = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_grows_up_to_the_limit_and_resets() {
+        let mut reconnect_backoff = ReconnectBackoff::default();
+
+        let first_delay = reconnect_backoff.next_delay();
+
+        assert!(first_delay >= INITIAL_RECONNECT_DELAY / 2);
+        assert!(first_delay < INITIAL_RECONNECT_DELAY);
+
+        for _ in 0..20 {
+            let delay = reconnect_backoff.next_delay();
+
+            assert!(delay >= first_delay / 2);
+            assert!(delay < MAX_RECONNECT_DELAY);
+        }
+
+        assert!(reconnect_backoff.next_delay() >= MAX_RECONNECT_DELAY / 2);
+
+        reconnect_backoff.reset();
+
+        assert!(reconnect_backoff.next_delay() < INITIAL_RECONNECT_DELAY);
+    }
+}
@@ -0,0 +1,20 @@
+/// KV cache cells used by a generation. `max_tokens` is the last position to
+/// generate, not a budget on top of the prompt. The prompt cells are shared by
+/// all choices in the unified KV cache, only the generated ones are per choice.
+pub fn required_context_size(prompt_tokens: usize, max_tokens: i32, n: u32) -> usize {
+    let generated_tokens = (max_tokens as i64 + 1 - prompt_tokens as i64).max(0) as usize;
+
+    prompt_tokens + n as usize * generated_tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_is_shared_between_choices() {
+        assert_eq!(required_context_size(2_100, 3_000, 1), 3_001);
+        assert_eq!(required_context_size(2_100, 3_000, 4), 5_704);
+        assert_eq!(required_context_size(2_100, 1_000, 4), 2_100);
+    }
+}
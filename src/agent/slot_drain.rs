@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::time::Duration;
+use tokio::time::sleep;
+
+use crate::drains::Drains;
+use crate::slot_aggregated_status::SlotAggregatedStatus;
+
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Balancer stops routing requests to a draining agent, so the agent only has to wait
+/// for the slots to finish their requests. It deregisters once the services shut down.
+/// A state change finishing in the meantime does not make the agent routable again.
+pub struct SlotDrain {
+    pub slot_aggregated_status: Arc<SlotAggregatedStatus>,
+}
+
+#[async_trait]
+impl Drains for SlotDrain {
+    async fn drain(&self) {
+        self.slot_aggregated_status.set_is_shutting_down();
+
+        while self.slot_aggregated_status.get_slots_processing() > 0 {
+            sleep(DRAIN_CHECK_INTERVAL).await;
+        }
+    }
+}
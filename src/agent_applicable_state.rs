@@ -1,11 +1,15 @@
 use std::path::PathBuf;
 
 use crate::chat_template::ChatTemplate;
+use crate::desired_slots::DesiredSlots;
 use crate::inference_parameters::InferenceParameters;
 
 #[derive(Clone, Debug)]
 pub struct AgentApplicableState {
     pub chat_template_override: Option<ChatTemplate>,
+    /// Generation of the desired state this was converted from
+    pub desired_state_generation: i32,
     pub inference_parameters: InferenceParameters,
     pub model_path: Option<PathBuf>,
+    pub slots: Option<DesiredSlots>,
 }
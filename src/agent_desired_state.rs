@@ -9,15 +9,18 @@ use crate::agent_applicable_state::AgentApplicableState;
 use crate::agent_desired_model::AgentDesiredModel;
 use crate::chat_template::ChatTemplate;
 use crate::converts_to_applicable_state::ConvertsToApplicableState;
+use crate::desired_slots::DesiredSlots;
 use crate::inference_parameters::InferenceParameters;
 use crate::slot_aggregated_status::SlotAggregatedStatus;
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct AgentDesiredState {
     pub chat_template_override: Option<ChatTemplate>,
     pub inference_parameters: InferenceParameters,
     pub model: AgentDesiredModel,
+    #[serde(default)]
+    pub slots: Option<DesiredSlots>,
 }
 
 #[async_trait]
@@ -29,13 +32,18 @@ impl ConvertsToApplicableState for AgentDesiredState {
         &self,
         slot_aggregated_status: Self::Context,
     ) -> Result<Option<Self::ApplicableState>> {
+        // Read before the model download, which can take a while
+        let desired_state_generation = slot_aggregated_status.get_desired_state_generation();
+
         Ok(Some(AgentApplicableState {
             chat_template_override: self.chat_template_override.clone(),
+            desired_state_generation,
             inference_parameters: self.inference_parameters.clone(),
             model_path: self
                 .model
                 .to_applicable_state(slot_aggregated_status)
                 .await?,
+            slots: self.slots.clone(),
         }))
     }
 }
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::agent_desired_model::AgentDesiredModel;
+use crate::agent_desired_state::AgentDesiredState;
+use crate::agent_override_target::AgentOverrideTarget;
+use crate::desired_slots::DesiredSlots;
+use crate::inference_parameters::InferenceParameters;
+use crate::validates::Validates;
+
+/// Changes layered on top of the balancer desired state for the targeted agents,
+/// for example a smaller context or a different quantization for agents with less memory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentDesiredStateOverride {
+    /// Inference parameters to replace. Parameters that are not listed here are
+    /// inherited from the balancer desired state.
+    #[serde(default)]
+    pub inference_parameters: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub model: Option<AgentDesiredModel>,
+    #[serde(default)]
+    pub slots: Option<DesiredSlots>,
+    pub target: AgentOverrideTarget,
+}
+
+impl AgentDesiredStateOverride {
+    pub fn apply(&self, agent_desired_state: AgentDesiredState) -> Result<AgentDesiredState> {
+        let mut inference_parameters =
+            serde_json::to_value(&agent_desired_state.inference_parameters)?;
+
+        match inference_parameters.as_object_mut() {
+            Some(inference_parameters) => {
+                for (name, value) in &self.inference_parameters {
+                    inference_parameters.insert(name.clone(), value.clone());
+                }
+            }
+            None => {
+                return Err(anyhow!(
+                    "Inference parameters are not serialized as an object"
+                ));
+            }
+        }
+
+        Ok(AgentDesiredState {
+            inference_parameters: serde_json::from_value::<InferenceParameters>(
+                inference_parameters,
+            )?
+            .validate()?,
+            model: self.model.clone().unwrap_or(agent_desired_state.model),
+            slots: match &self.slots {
+                Some(slots) => Some(slots.clone().validate()?),
+                None => agent_desired_state.slots,
+            },
+            ..agent_desired_state
+        })
+    }
+}
+
+impl Validates<AgentDesiredStateOverride> for AgentDesiredStateOverride {
+    fn validate(self) -> Result<AgentDesiredStateOverride> {
+        self.apply(AgentDesiredState::default())?;
+
+        Ok(self)
+    }
+}
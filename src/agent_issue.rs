@@ -13,5 +13,7 @@ pub enum AgentIssue {
     ModelCannotBeLoaded(String),
     ModelFileDoesNotExist(String),
     SlotCannotStart(SlotCannotStartParams),
+    /// Automatic number of slots needs to know how much memory is available
+    UnableToEstimateSlots(String),
     UnableToFindChatTemplate(String),
 }
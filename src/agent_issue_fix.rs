@@ -40,6 +40,10 @@ impl AgentIssueFix {
                 AgentIssueFix::SlotStarted(started_slot_index) => started_slot_index == slot_index,
                 _ => false,
             },
+            AgentIssue::UnableToEstimateSlots(_) => matches!(
+                self,
+                AgentIssueFix::ModelIsLoaded | AgentIssueFix::ModelStateIsReconciled
+            ),
             AgentIssue::UnableToFindChatTemplate(_) => matches!(
                 self,
                 AgentIssueFix::ModelChatTemplateIsLoaded | AgentIssueFix::ModelStateIsReconciled
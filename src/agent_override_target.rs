@@ -0,0 +1,24 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::label_selector::LabelSelector;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub enum AgentOverrideTarget {
+    Id(String),
+    Labels(LabelSelector),
+    Name(String),
+}
+
+impl AgentOverrideTarget {
+    pub fn matches(&self, id: &str, name: Option<&str>, labels: &BTreeMap<String, String>) -> bool {
+        match self {
+            AgentOverrideTarget::Id(target_id) => target_id == id,
+            AgentOverrideTarget::Labels(label_selector) => label_selector.matches(labels),
+            AgentOverrideTarget::Name(target_name) => Some(target_name.as_str()) == name,
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicI32;
@@ -19,16 +20,19 @@ use crate::agent::jsonrpc::Notification as AgentJsonRpcNotification;
 use crate::agent::jsonrpc::Request as AgentJsonRpcRequest;
 use crate::agent_desired_state::AgentDesiredState;
 use crate::agent_issue::AgentIssue;
+use crate::agent_state_application_status::AgentStateApplicationStatus;
 use crate::atomic_value::AtomicValue;
 use crate::balancer::agent_controller_snapshot::AgentControllerSnapshot;
 use crate::balancer::agent_controller_update_result::AgentControllerUpdateResult;
 use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
+use crate::balancer::deployment::Deployment;
 use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
 use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
 use crate::balancer::handles_agent_streaming_response::HandlesAgentStreamingResponse;
 use crate::balancer::manages_senders::ManagesSenders;
 use crate::balancer::manages_senders_controller::ManagesSendersController;
 use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
+use crate::balancer::request_endpoint::RequestEndpoint;
 use crate::jsonrpc::RequestEnvelope;
 use crate::produces_snapshot::ProducesSnapshot;
 use crate::request_params::ContinueFromConversationHistoryParams;
@@ -40,8 +44,15 @@ use crate::slot_aggregated_status_snapshot::SlotAggregatedStatusSnapshot;
 
 pub struct AgentController {
     pub agent_message_tx: mpsc::UnboundedSender<AgentJsonRpcMessage>,
+    /// Generation of the most recent desired state that the agent reported as applied
+    pub applied_desired_state_generation: AtomicValue<AtomicI32>,
     pub chat_template_override_sender_collection: Arc<ChatTemplateOverrideSenderCollection>,
     pub connection_close_rx: broadcast::Receiver<()>,
+    /// The most recent desired state sent to the agent
+    pub deployment: RwLock<Deployment>,
+    pub desired_state: RwLock<Option<AgentDesiredState>>,
+    /// Incremented every time a desired state is sent to the agent
+    pub desired_state_generation: AtomicValue<AtomicI32>,
     pub desired_slots_total: AtomicValue<AtomicI32>,
     pub download_current: AtomicValue<AtomicUsize>,
     pub download_filename: RwLock<Option<String>>,
@@ -49,7 +60,10 @@ pub struct AgentController {
     pub embedding_sender_collection: Arc<EmbeddingSenderCollection>,
     pub generate_tokens_sender_collection: Arc<GenerateTokensSenderCollection>,
     pub id: String,
+    pub is_desired_state_pending: AtomicValue<AtomicBool>,
+    pub is_draining: AtomicValue<AtomicBool>,
     pub issues: RwLock<BTreeSet<AgentIssue>>,
+    pub labels: BTreeMap<String, String>,
     pub model_metadata_sender_collection: Arc<ModelMetadataSenderCollection>,
     pub model_path: RwLock<Option<String>>,
     pub name: Option<String>,
@@ -71,6 +85,14 @@ impl AgentController {
         .await
     }
 
+    pub fn accepts_requests(&self) -> bool {
+        !self.is_draining.get() && self.slots_processing.get() < self.slots_total.get()
+    }
+
+    pub fn get_deployment(&self) -> Deployment {
+        *self.deployment.read().expect("Poisoned lock on deployment")
+    }
+
     pub fn get_download_filename(&self) -> Option<String> {
         self.download_filename
             .read()
@@ -99,6 +121,33 @@ impl AgentController {
             .clone()
     }
 
+    pub fn has_desired_state(&self, desired_state: &AgentDesiredState) -> bool {
+        self.desired_state
+            .read()
+            .expect("Poisoned lock on desired state")
+            .as_ref()
+            .is_some_and(|sent_desired_state| sent_desired_state == desired_state)
+    }
+
+    /// Agent has applied the most recent desired state it was sent and serves requests.
+    /// Comparing generations discards the reports sent before the agent received that state.
+    pub fn is_desired_state_settled(&self) -> bool {
+        self.applied_desired_state_generation.get() == self.desired_state_generation.get()
+            && !self.is_desired_state_pending.get()
+            && !self.is_draining.get()
+            && self.state_application_status_code.get()
+                == AgentStateApplicationStatus::Applied as i32
+    }
+
+    pub fn set_deployment(&self, deployment: Deployment) {
+        let mut locked_deployment = self
+            .deployment
+            .write()
+            .expect("Poisoned lock on deployment");
+
+        *locked_deployment = deployment;
+    }
+
     pub fn set_download_filename(&self, filename: Option<String>) {
         let mut locked_filename = self
             .download_filename
@@ -135,10 +184,13 @@ impl AgentController {
     pub fn update_from_slot_aggregated_status_snapshot(
         &self,
         SlotAggregatedStatusSnapshot {
+            applied_desired_state_generation,
             desired_slots_total,
             download_current,
             download_filename,
             download_total,
+            is_desired_state_pending,
+            is_draining,
             issues,
             model_path,
             slots_processing,
@@ -158,19 +210,28 @@ impl AgentController {
 
         let mut changed = false;
 
-        changed = changed || self.desired_slots_total.set_check(desired_slots_total);
-        changed = changed || self.download_current.set_check(download_current);
-        changed = changed || self.download_total.set_check(download_total);
-        changed = changed || self.slots_processing.set_check(slots_processing);
-        changed = changed || self.slots_total.set_check(slots_total);
-        changed = changed
-            || self
-                .state_application_status_code
-                .set_check(state_application_status as i32);
-        changed = changed
-            || self
-                .uses_chat_template_override
-                .set_check(uses_chat_template_override);
+        changed = self
+            .applied_desired_state_generation
+            .set_check(applied_desired_state_generation)
+            || changed;
+        changed = self.desired_slots_total.set_check(desired_slots_total) || changed;
+        changed = self.download_current.set_check(download_current) || changed;
+        changed = self.download_total.set_check(download_total) || changed;
+        changed = self
+            .is_desired_state_pending
+            .set_check(is_desired_state_pending)
+            || changed;
+        changed = self.is_draining.set_check(is_draining) || changed;
+        changed = self.slots_processing.set_check(slots_processing) || changed;
+        changed = self.slots_total.set_check(slots_total) || changed;
+        changed = self
+            .state_application_status_code
+            .set_check(state_application_status as i32)
+            || changed;
+        changed = self
+            .uses_chat_template_override
+            .set_check(uses_chat_template_override)
+            || changed;
 
         self.newest_update_version
             .compare_and_swap(newest_update_version, version);
@@ -242,6 +303,8 @@ impl HandlesAgentStreamingResponse<ContinueFromConversationHistoryParams<Validat
 {
     type SenderCollection = GenerateTokensSenderCollection;
 
+    const REQUEST_ENDPOINT: RequestEndpoint = RequestEndpoint::ContinueFromConversationHistory;
+
     async fn handle_streaming_response(
         &self,
         request_id: String,
@@ -263,6 +326,8 @@ impl HandlesAgentStreamingResponse<ContinueFromConversationHistoryParams<Validat
 impl HandlesAgentStreamingResponse<ContinueFromRawPromptParams> for AgentController {
     type SenderCollection = GenerateTokensSenderCollection;
 
+    const REQUEST_ENDPOINT: RequestEndpoint = RequestEndpoint::ContinueFromRawPrompt;
+
     async fn handle_streaming_response(
         &self,
         request_id: String,
@@ -284,6 +349,8 @@ impl HandlesAgentStreamingResponse<ContinueFromRawPromptParams> for AgentControl
 impl HandlesAgentStreamingResponse<GenerateEmbeddingBatchParams> for AgentController {
     type SenderCollection = EmbeddingSenderCollection;
 
+    const REQUEST_ENDPOINT: RequestEndpoint = RequestEndpoint::GenerateEmbeddingBatch;
+
     async fn handle_streaming_response(
         &self,
         request_id: String,
@@ -306,6 +373,7 @@ impl ProducesSnapshot for AgentController {
 
     fn make_snapshot(&self) -> Result<Self::Snapshot> {
         Ok(AgentControllerSnapshot {
+            deployment: self.get_deployment(),
             desired_slots_total: self.desired_slots_total.get(),
             download_current: self.download_current.get(),
             download_filename: self
@@ -314,8 +382,13 @@ impl ProducesSnapshot for AgentController {
                 .expect("Poisoned lock on download filename")
                 .clone(),
             download_total: self.download_total.get(),
+            effective_desired_state: None,
             id: self.id.clone(),
+            is_desired_state_outdated: false,
+            is_desired_state_pending: self.is_desired_state_pending.get(),
+            is_draining: self.is_draining.get(),
             issues: self.get_issues(),
+            labels: self.labels.clone(),
             model_path: self
                 .model_path
                 .read()
@@ -344,8 +417,26 @@ impl SendsRpcMessage for AgentController {
 #[async_trait]
 impl SetsDesiredState for AgentController {
     async fn set_desired_state(&self, desired_state: AgentDesiredState) -> Result<()> {
+        let generation = {
+            let mut locked_desired_state = self
+                .desired_state
+                .write()
+                .expect("Poisoned lock on desired state");
+
+            *locked_desired_state = Some(desired_state.clone());
+
+            self.desired_state_generation.increment();
+            self.desired_state_generation.get()
+        };
+
+        // Until the agent reports back, assume it is busy applying the new state
+        self.is_desired_state_pending.set(true);
+
         self.send_rpc_message(AgentJsonRpcMessage::Notification(
-            AgentJsonRpcNotification::SetState(SetStateParams { desired_state }),
+            AgentJsonRpcNotification::SetState(SetStateParams {
+                desired_state,
+                generation,
+            }),
         ))
         .await
     }
@@ -1,40 +1,136 @@
 use std::sync::Arc;
+use std::sync::RwLock;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use dashmap::DashMap;
+use log::error;
+use log::info;
+use rand::Rng as _;
 use tokio::sync::Notify;
 
 use super::agent_controller::AgentController;
+use super::agent_controller_pool_serving_capacity::AgentControllerPoolServingCapacity;
 use super::agent_controller_pool_total_slots::AgentControllerPoolTotalSlots;
 use crate::agent_desired_state::AgentDesiredState;
+use crate::agent_desired_state_override::AgentDesiredStateOverride;
 use crate::balancer::agent_controller_pool_snapshot::AgentControllerPoolSnapshot;
 use crate::balancer::agent_controller_snapshot::AgentControllerSnapshot;
+use crate::balancer::deployment::Deployment;
+use crate::balancer::deployment_metrics::DeploymentMetrics;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
+use crate::balancer::sticky_key_roll::sticky_key_roll;
+use crate::canary_applicable_state::CanaryApplicableState;
+use crate::label_selector::LabelSelector;
 use crate::produces_snapshot::ProducesSnapshot;
 use crate::sets_desired_state::SetsDesiredState;
+use crate::shadow_applicable_state::ShadowApplicableState;
 
 pub struct AgentControllerPool {
     pub agents: DashMap<String, Arc<AgentController>>,
+    pub canary: RwLock<Option<CanaryApplicableState>>,
+    pub canary_metrics: DeploymentMetrics,
+    /// Desired state that is being rolled out to the agents
+    pub desired_state: RwLock<Option<AgentDesiredState>>,
+    pub desired_state_overrides: RwLock<Vec<AgentDesiredStateOverride>>,
+    pub shadow: RwLock<Option<ShadowApplicableState>>,
+    pub shadow_metrics: DeploymentMetrics,
+    pub stable_metrics: DeploymentMetrics,
     pub update_notifier: Arc<Notify>,
 }
 
 impl AgentControllerPool {
-    pub fn take_least_busy_agent_controller(&self) -> Option<Arc<AgentController>> {
-        let agent_controller: Option<Arc<AgentController>> = self
-            .agents
-            .iter()
-            .map(|entry| entry.value().clone())
-            .filter(|agent| agent.slots_processing.get() < agent.slots_total.get())
-            .min_by_key(|agent| agent.slots_processing.get());
+    pub fn take_least_busy_agent_controller(
+        &self,
+        request_routing_hints: &RequestRoutingHints,
+    ) -> Option<Arc<AgentController>> {
+        let deployment = self.choose_deployment(request_routing_hints);
 
-        if let Some(agent_controller) = agent_controller {
-            agent_controller.slots_processing.increment();
-            self.update_notifier.notify_waiters();
+        self.take_least_busy_agent_controller_matching(|agent_controller| {
+            agent_controller.get_deployment() == deployment
+                && request_routing_hints
+                    .agent_selector
+                    .matches(&agent_controller.labels)
+        })
+    }
 
-            return Some(agent_controller);
+    /// Picks a shadow agent if the request is sampled for mirroring.
+    /// Mirrored requests only use idle shadow slots and never wait in the buffer.
+    pub fn take_shadow_agent_controller(&self) -> Option<Arc<AgentController>> {
+        let shadow = self.get_shadow()?;
+
+        if rand::rng().random_range(0..100) >= shadow.mirror_percentage {
+            return None;
         }
 
-        None
+        self.take_least_busy_agent_controller_matching(|agent_controller| {
+            agent_controller.get_deployment() == Deployment::Shadow
+                && self.is_agent_serving_its_desired_state(agent_controller)
+        })
+    }
+
+    pub fn get_canary(&self) -> Option<CanaryApplicableState> {
+        self.canary.read().expect("Poisoned lock on canary").clone()
+    }
+
+    pub fn get_deployment_metrics(&self, deployment: Deployment) -> &DeploymentMetrics {
+        match deployment {
+            Deployment::Canary => &self.canary_metrics,
+            Deployment::Shadow => &self.shadow_metrics,
+            Deployment::Stable => &self.stable_metrics,
+        }
+    }
+
+    pub fn get_desired_state(&self) -> Option<AgentDesiredState> {
+        self.desired_state
+            .read()
+            .expect("Poisoned lock on desired state")
+            .clone()
+    }
+
+    pub fn get_desired_state_overrides(&self) -> Vec<AgentDesiredStateOverride> {
+        self.desired_state_overrides
+            .read()
+            .expect("Poisoned lock on desired state overrides")
+            .clone()
+    }
+
+    /// Desired state the agent should run, depending on its deployment,
+    /// with the matching overrides applied in order
+    pub fn get_desired_state_for(
+        &self,
+        agent_controller: &AgentController,
+    ) -> Option<AgentDesiredState> {
+        let deployment_desired_state = match agent_controller.get_deployment() {
+            Deployment::Canary => self.get_canary().map(|canary| canary.agent_desired_state),
+            Deployment::Shadow => self.get_shadow().map(|shadow| shadow.agent_desired_state),
+            Deployment::Stable => None,
+        };
+        let mut desired_state = deployment_desired_state.or_else(|| self.get_desired_state())?;
+
+        for desired_state_override in self.get_desired_state_overrides() {
+            if !desired_state_override.target.matches(
+                &agent_controller.id,
+                agent_controller.name.as_deref(),
+                &agent_controller.labels,
+            ) {
+                continue;
+            }
+
+            match desired_state_override.apply(desired_state.clone()) {
+                Ok(overridden_desired_state) => desired_state = overridden_desired_state,
+                Err(err) => error!(
+                    "Failed to apply desired state override to agent {}: {err}",
+                    agent_controller.id
+                ),
+            }
+        }
+
+        Some(desired_state)
+    }
+
+    pub fn get_shadow(&self) -> Option<ShadowApplicableState> {
+        self.shadow.read().expect("Poisoned lock on shadow").clone()
     }
 
     pub fn get_agent_controller(&self, agent_id: &str) -> Option<Arc<AgentController>> {
@@ -65,6 +161,105 @@ impl AgentControllerPool {
         }
     }
 
+    /// Sends the desired state to outdated agents, keeping at most
+    /// `max_unavailable_agents` of them in the middle of an update at once.
+    pub async fn roll_out_desired_state(&self, max_unavailable_agents: usize) -> Result<()> {
+        if self.get_desired_state().is_none() {
+            return Ok(());
+        }
+
+        self.assign_deployments();
+
+        let mut outdated_agents: Vec<(Arc<AgentController>, AgentDesiredState)> = Vec::new();
+        let mut updating_agents: usize = 0;
+
+        for entry in self.agents.iter() {
+            let agent_controller = entry.value();
+
+            if let Some(desired_state) = self.get_desired_state_for(agent_controller) {
+                if !agent_controller.has_desired_state(&desired_state) {
+                    outdated_agents.push((agent_controller.clone(), desired_state));
+                } else if !agent_controller.is_desired_state_settled() {
+                    updating_agents += 1;
+                }
+            }
+        }
+
+        for (agent_controller, desired_state) in outdated_agents
+            .into_iter()
+            .take(max_unavailable_agents.saturating_sub(updating_agents))
+        {
+            info!(
+                "Rolling out {} desired state to agent: {}",
+                agent_controller.get_deployment().label(),
+                agent_controller.id
+            );
+
+            agent_controller.set_desired_state(desired_state).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Shadow agents only receive mirrored requests, so they do not count
+    pub fn serving_capacity(&self) -> AgentControllerPoolServingCapacity {
+        let mut agents_serving = 0;
+        let mut slots_free = 0;
+
+        for entry in self.agents.iter() {
+            let agent_controller = entry.value();
+
+            if agent_controller.get_deployment() != Deployment::Shadow
+                && self.is_agent_serving_its_desired_state(agent_controller)
+            {
+                agents_serving += 1;
+                slots_free += (agent_controller.slots_total.get()
+                    - agent_controller.slots_processing.get())
+                .max(0);
+            }
+        }
+
+        AgentControllerPoolServingCapacity {
+            agents_serving,
+            slots_free,
+        }
+    }
+
+    pub fn set_canary(&self, canary: Option<CanaryApplicableState>) {
+        {
+            let mut locked_canary = self.canary.write().expect("Poisoned lock on canary");
+
+            *locked_canary = canary;
+        }
+
+        self.assign_deployments();
+        self.update_notifier.notify_waiters();
+    }
+
+    pub fn set_desired_state_overrides(&self, overrides: Vec<AgentDesiredStateOverride>) {
+        {
+            let mut locked_overrides = self
+                .desired_state_overrides
+                .write()
+                .expect("Poisoned lock on desired state overrides");
+
+            *locked_overrides = overrides;
+        }
+
+        self.update_notifier.notify_waiters();
+    }
+
+    pub fn set_shadow(&self, shadow: Option<ShadowApplicableState>) {
+        {
+            let mut locked_shadow = self.shadow.write().expect("Poisoned lock on shadow");
+
+            *locked_shadow = shadow;
+        }
+
+        self.assign_deployments();
+        self.update_notifier.notify_waiters();
+    }
+
     pub fn total_slots(&self) -> AgentControllerPoolTotalSlots {
         let mut slots_processing = 0;
         let mut slots_total = 0;
@@ -81,12 +276,136 @@ impl AgentControllerPool {
             slots_total,
         }
     }
+
+    /// Assigns the requested number of agents (matching the selectors) to the
+    /// canary and shadow deployments. Agents keep their current deployment
+    /// when possible, so they do not have to reload the model.
+    fn assign_deployments(&self) {
+        let (canary_agents, canary_agent_selector) = self
+            .get_canary()
+            .map(|canary| (canary.agents, canary.agent_selector))
+            .unwrap_or_default();
+        let (shadow_agents, shadow_agent_selector) = self
+            .get_shadow()
+            .map(|shadow| (shadow.agents, shadow.agent_selector))
+            .unwrap_or_default();
+        let mut unassigned_agent_controllers: Vec<Arc<AgentController>> = self
+            .agents
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+
+        for (deployment, agents, agent_selector) in [
+            (Deployment::Canary, canary_agents, canary_agent_selector),
+            (Deployment::Shadow, shadow_agents, shadow_agent_selector),
+        ] {
+            unassigned_agent_controllers.sort_by_key(|agent_controller| {
+                (
+                    !agent_selector.matches(&agent_controller.labels),
+                    agent_controller.get_deployment() != deployment,
+                    agent_controller.id.clone(),
+                )
+            });
+
+            let matching_agents = unassigned_agent_controllers
+                .iter()
+                .filter(|agent_controller| agent_selector.matches(&agent_controller.labels))
+                .count();
+            let assigned_agents = agents.min(matching_agents);
+
+            for agent_controller in unassigned_agent_controllers.drain(..assigned_agents) {
+                agent_controller.set_deployment(deployment);
+            }
+        }
+
+        for agent_controller in unassigned_agent_controllers {
+            agent_controller.set_deployment(Deployment::Stable);
+        }
+    }
+
+    fn choose_deployment(&self, request_routing_hints: &RequestRoutingHints) -> Deployment {
+        let canary = match self.get_canary() {
+            Some(canary) => canary,
+            None => return Deployment::Stable,
+        };
+
+        let roll: u64 = match &request_routing_hints.sticky_key {
+            Some(sticky_key) if canary.sticky => sticky_key_roll(sticky_key),
+            _ => rand::rng().random_range(0..100),
+        };
+
+        let (preferred_deployment, fallback_deployment) =
+            if roll < u64::from(canary.traffic_percentage) {
+                (Deployment::Canary, Deployment::Stable)
+            } else {
+                (Deployment::Stable, Deployment::Canary)
+            };
+
+        // Do not hold the requests back while one of the deployments is still loading
+        if self.is_deployment_serving(preferred_deployment, &request_routing_hints.agent_selector) {
+            preferred_deployment
+        } else {
+            fallback_deployment
+        }
+    }
+
+    fn is_agent_serving_its_desired_state(&self, agent_controller: &AgentController) -> bool {
+        agent_controller.is_desired_state_settled()
+            && self
+                .get_desired_state_for(agent_controller)
+                .is_some_and(|desired_state| agent_controller.has_desired_state(&desired_state))
+    }
+
+    fn is_deployment_serving(
+        &self,
+        deployment: Deployment,
+        agent_selector: &LabelSelector,
+    ) -> bool {
+        self.agents.iter().any(|entry| {
+            let agent_controller = entry.value();
+
+            agent_controller.get_deployment() == deployment
+                && agent_selector.matches(&agent_controller.labels)
+                && self.is_agent_serving_its_desired_state(agent_controller)
+        })
+    }
+
+    fn take_least_busy_agent_controller_matching<TPredicate>(
+        &self,
+        predicate: TPredicate,
+    ) -> Option<Arc<AgentController>>
+    where
+        TPredicate: Fn(&AgentController) -> bool,
+    {
+        let agent_controller: Option<Arc<AgentController>> = self
+            .agents
+            .iter()
+            .map(|entry| entry.value().clone())
+            .filter(|agent| agent.accepts_requests() && predicate(agent))
+            .min_by_key(|agent| agent.slots_processing.get());
+
+        if let Some(agent_controller) = agent_controller {
+            agent_controller.slots_processing.increment();
+            self.update_notifier.notify_waiters();
+
+            return Some(agent_controller);
+        }
+
+        None
+    }
 }
 
 impl Default for AgentControllerPool {
     fn default() -> Self {
         AgentControllerPool {
             agents: DashMap::new(),
+            canary: RwLock::new(None),
+            canary_metrics: DeploymentMetrics::default(),
+            desired_state: RwLock::new(None),
+            desired_state_overrides: RwLock::new(Vec::new()),
+            shadow: RwLock::new(None),
+            shadow_metrics: DeploymentMetrics::default(),
+            stable_metrics: DeploymentMetrics::default(),
             update_notifier: Arc::new(Notify::new()),
         }
     }
@@ -100,8 +419,15 @@ impl ProducesSnapshot for AgentControllerPool {
 
         for entry in self.agents.iter() {
             let agent_controller = entry.value();
+            let effective_desired_state = self.get_desired_state_for(agent_controller);
 
-            agents.push(agent_controller.make_snapshot()?);
+            agents.push(AgentControllerSnapshot {
+                is_desired_state_outdated: effective_desired_state.as_ref().is_some_and(
+                    |desired_state| !agent_controller.has_desired_state(desired_state),
+                ),
+                effective_desired_state,
+                ..agent_controller.make_snapshot()?
+            });
         }
 
         Ok(AgentControllerPoolSnapshot { agents })
@@ -110,14 +436,16 @@ impl ProducesSnapshot for AgentControllerPool {
 
 #[async_trait]
 impl SetsDesiredState for AgentControllerPool {
+    /// Agents receive the new state gradually, see `roll_out_desired_state`
     async fn set_desired_state(&self, desired_state: AgentDesiredState) -> Result<()> {
-        for agent in self.agents.iter() {
-            let agent_controller = agent.value();
+        let mut locked_desired_state = self
+            .desired_state
+            .write()
+            .expect("Poisoned lock on desired state");
 
-            agent_controller
-                .set_desired_state(desired_state.clone())
-                .await?;
-        }
+        *locked_desired_state = Some(desired_state);
+
+        self.update_notifier.notify_waiters();
 
         Ok(())
     }
@@ -0,0 +1,5 @@
+pub struct AgentControllerPoolServingCapacity {
+    /// Agents that applied their desired state and are not draining
+    pub agents_serving: usize,
+    pub slots_free: i32,
+}
@@ -1,20 +1,31 @@
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::agent_desired_state::AgentDesiredState;
 use crate::agent_issue::AgentIssue;
 use crate::agent_state_application_status::AgentStateApplicationStatus;
+use crate::balancer::deployment::Deployment;
 
 #[derive(Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct AgentControllerSnapshot {
+    pub deployment: Deployment,
     pub desired_slots_total: i32,
     pub download_current: usize,
     pub download_filename: Option<String>,
     pub download_total: usize,
+    /// Desired state of the agent's deployment with the agent overrides applied
+    pub effective_desired_state: Option<AgentDesiredState>,
     pub id: String,
+    /// Agent is waiting for its turn in a rolling update
+    pub is_desired_state_outdated: bool,
+    pub is_desired_state_pending: bool,
+    pub is_draining: bool,
     pub issues: BTreeSet<AgentIssue>,
+    pub labels: BTreeMap<String, String>,
     pub model_path: Option<String>,
     pub name: Option<String>,
     pub slots_processing: i32,
@@ -0,0 +1,243 @@
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicI32;
+use std::sync::atomic::AtomicUsize;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use log::error;
+use log::info;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+
+use crate::agent::jsonrpc::Message as AgentJsonRpcMessage;
+use crate::agent::jsonrpc::Response as AgentJsonRpcResponse;
+use crate::atomic_value::AtomicValue;
+use crate::balancer::agent_controller::AgentController;
+use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::agent_controller_update_result::AgentControllerUpdateResult;
+use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
+use crate::balancer::deployment::Deployment;
+use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
+use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Message as ManagementJsonRpcMessage;
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Notification as ManagementJsonRpcNotification;
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::notification_params::RegisterAgentParams;
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::notification_params::UpdateAgentStatusParams;
+use crate::balancer::manages_senders::ManagesSenders as _;
+use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
+use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
+use crate::controls_websocket_endpoint::ContinuationDecision;
+use crate::jsonrpc::ResponseEnvelope;
+use crate::sets_desired_state::SetsDesiredState as _;
+use crate::slot_aggregated_status_snapshot::SlotAggregatedStatusSnapshot;
+
+/// Applies the messages that agents send to the balancer, both the ones read from the agent socket
+/// and the ones passed directly by the in-process agent of `paddler serve`
+#[derive(Clone)]
+pub struct AgentMessageHandler {
+    pub agent_controller_pool: Arc<AgentControllerPool>,
+    pub balancer_applicable_state_holder: Arc<BalancerApplicableStateHolder>,
+    pub chat_template_override_sender_collection: Arc<ChatTemplateOverrideSenderCollection>,
+    pub embedding_sender_collection: Arc<EmbeddingSenderCollection>,
+    pub generate_tokens_sender_collection: Arc<GenerateTokensSenderCollection>,
+    pub model_metadata_sender_collection: Arc<ModelMetadataSenderCollection>,
+}
+
+impl AgentMessageHandler {
+    /// `open_agent_channel` is called once the agent registers, and returns the sender
+    /// of the messages that go back to that agent
+    pub async fn handle_message(
+        &self,
+        agent_id: &str,
+        connection_close_tx: &broadcast::Sender<()>,
+        message: ManagementJsonRpcMessage,
+        open_agent_channel: impl FnOnce() -> mpsc::UnboundedSender<AgentJsonRpcMessage>,
+    ) -> Result<ContinuationDecision> {
+        match message {
+            ManagementJsonRpcMessage::Error(err) => {
+                error!("Received error message: {err:?}");
+
+                Ok(ContinuationDecision::Continue)
+            }
+            ManagementJsonRpcMessage::Notification(
+                ManagementJsonRpcNotification::DeregisterAgent,
+            ) => {
+                connection_close_tx.send(())?;
+
+                Ok(ContinuationDecision::Stop)
+            }
+            ManagementJsonRpcMessage::Notification(
+                ManagementJsonRpcNotification::RegisterAgent(register_agent_params),
+            ) => {
+                self.register_agent(
+                    agent_id,
+                    connection_close_tx,
+                    register_agent_params,
+                    open_agent_channel(),
+                )
+                .await?;
+
+                Ok(ContinuationDecision::Continue)
+            }
+            ManagementJsonRpcMessage::Notification(
+                ManagementJsonRpcNotification::UpdateAgentStatus(UpdateAgentStatusParams {
+                    slot_aggregated_status_snapshot,
+                }),
+            ) => {
+                if let Some(agent_controller) =
+                    self.agent_controller_pool.get_agent_controller(agent_id)
+                {
+                    match agent_controller.update_from_slot_aggregated_status_snapshot(
+                        slot_aggregated_status_snapshot,
+                    ) {
+                        AgentControllerUpdateResult::NoMeaningfulChanges => {}
+                        AgentControllerUpdateResult::Updated => {
+                            self.agent_controller_pool.update_notifier.notify_waiters();
+                        }
+                    }
+                } else {
+                    error!("Agent controller not found for agent: {agent_id}");
+                }
+
+                Ok(ContinuationDecision::Continue)
+            }
+            ManagementJsonRpcMessage::Response(ResponseEnvelope {
+                request_id,
+                response: AgentJsonRpcResponse::ChatTemplateOverride(chat_template_override),
+            }) => {
+                self.chat_template_override_sender_collection
+                    .forward_response_safe(request_id, chat_template_override)
+                    .await;
+
+                Ok(ContinuationDecision::Continue)
+            }
+            ManagementJsonRpcMessage::Response(ResponseEnvelope {
+                request_id,
+                response: AgentJsonRpcResponse::Embedding(embedding_result),
+            }) => {
+                self.embedding_sender_collection
+                    .forward_response_safe(request_id, embedding_result)
+                    .await;
+
+                Ok(ContinuationDecision::Continue)
+            }
+            ManagementJsonRpcMessage::Response(ResponseEnvelope {
+                request_id,
+                response: AgentJsonRpcResponse::GeneratedToken(generated_token_envelope),
+            }) => {
+                self.generate_tokens_sender_collection
+                    .forward_response_safe(request_id, generated_token_envelope)
+                    .await;
+
+                Ok(ContinuationDecision::Continue)
+            }
+            ManagementJsonRpcMessage::Response(ResponseEnvelope {
+                request_id,
+                response: AgentJsonRpcResponse::ModelMetadata(model_metadata),
+            }) => {
+                self.model_metadata_sender_collection
+                    .forward_response_safe(request_id, model_metadata)
+                    .await;
+
+                Ok(ContinuationDecision::Continue)
+            }
+        }
+    }
+
+    pub fn remove_agent(&self, agent_id: &str) {
+        if let Err(err) = self.agent_controller_pool.remove_agent_controller(agent_id) {
+            error!("Failed to remove agent: {err}");
+        }
+
+        info!("Removed agent: {agent_id}");
+    }
+
+    async fn register_agent(
+        &self,
+        agent_id: &str,
+        connection_close_tx: &broadcast::Sender<()>,
+        RegisterAgentParams {
+            labels,
+            name,
+            slot_aggregated_status_snapshot:
+                SlotAggregatedStatusSnapshot {
+                    applied_desired_state_generation,
+                    desired_slots_total,
+                    download_current,
+                    download_filename,
+                    download_total,
+                    is_desired_state_pending,
+                    is_draining,
+                    issues,
+                    model_path,
+                    slots_processing,
+                    slots_total,
+                    state_application_status,
+                    uses_chat_template_override,
+                    version,
+                },
+        }: RegisterAgentParams,
+        agent_message_tx: mpsc::UnboundedSender<AgentJsonRpcMessage>,
+    ) -> Result<()> {
+        let agent_controller = Arc::new(AgentController {
+            agent_message_tx,
+            applied_desired_state_generation: AtomicValue::<AtomicI32>::new(
+                applied_desired_state_generation,
+            ),
+            chat_template_override_sender_collection: self
+                .chat_template_override_sender_collection
+                .clone(),
+            connection_close_rx: connection_close_tx.subscribe(),
+            deployment: RwLock::new(Deployment::Stable),
+            desired_state: RwLock::new(None),
+            // Continue from what the agent applied, so its reports from before
+            // a reconnection never match the newly sent desired state
+            desired_state_generation: AtomicValue::<AtomicI32>::new(
+                applied_desired_state_generation,
+            ),
+            desired_slots_total: AtomicValue::<AtomicI32>::new(desired_slots_total),
+            download_current: AtomicValue::<AtomicUsize>::new(download_current),
+            download_filename: RwLock::new(download_filename),
+            download_total: AtomicValue::<AtomicUsize>::new(download_total),
+            embedding_sender_collection: self.embedding_sender_collection.clone(),
+            generate_tokens_sender_collection: self.generate_tokens_sender_collection.clone(),
+            model_metadata_sender_collection: self.model_metadata_sender_collection.clone(),
+            id: agent_id.to_string(),
+            is_desired_state_pending: AtomicValue::<AtomicBool>::new(is_desired_state_pending),
+            is_draining: AtomicValue::<AtomicBool>::new(is_draining),
+            issues: RwLock::new(issues),
+            labels,
+            model_path: RwLock::new(model_path),
+            name,
+            newest_update_version: AtomicValue::<AtomicI32>::new(version),
+            slots_processing: AtomicValue::<AtomicI32>::new(slots_processing),
+            slots_total: AtomicValue::<AtomicI32>::new(slots_total),
+            state_application_status_code: AtomicValue::<AtomicI32>::new(
+                state_application_status as i32,
+            ),
+            uses_chat_template_override: AtomicValue::<AtomicBool>::new(
+                uses_chat_template_override,
+            ),
+        });
+
+        self.agent_controller_pool
+            .register_agent_controller(agent_id.to_string(), agent_controller.clone())
+            .context("Unable to register agent controller")?;
+
+        if let Some(desired_state) = self
+            .balancer_applicable_state_holder
+            .get_agent_desired_state()
+        {
+            agent_controller
+                .set_desired_state(desired_state)
+                .await
+                .context("Unable to set desired state")?;
+        }
+
+        info!("Registered agent: {agent_id}");
+
+        Ok(())
+    }
+}
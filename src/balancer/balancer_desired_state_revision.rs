@@ -0,0 +1,38 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer_desired_state::BalancerDesiredState;
+
+/// Balancer desired state as it was stored at some point in time.
+/// Default revision (with id 0) stands for the state that was never stored.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BalancerDesiredStateRevision {
+    /// Who stored the revision, if known
+    pub author: Option<String>,
+    pub balancer_desired_state: BalancerDesiredState,
+    /// Unix timestamp in seconds
+    pub created_at: u64,
+    pub id: u64,
+}
+
+impl BalancerDesiredStateRevision {
+    pub fn new(
+        author: Option<String>,
+        balancer_desired_state: BalancerDesiredState,
+        id: u64,
+    ) -> Self {
+        Self {
+            author,
+            balancer_desired_state,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_secs(),
+            id,
+        }
+    }
+}
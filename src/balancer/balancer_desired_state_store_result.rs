@@ -0,0 +1,9 @@
+use crate::balancer::balancer_desired_state_revision::BalancerDesiredStateRevision;
+
+pub enum BalancerDesiredStateStoreResult {
+    /// Desired state was changed in the meantime, nothing was stored
+    Conflict {
+        current_revision_id: u64,
+    },
+    Stored(BalancerDesiredStateRevision),
+}
@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct BalancerReadiness {
+    pub agents_serving: usize,
+    pub buffered_requests_current: i32,
+    pub is_draining: bool,
+    pub is_ready: bool,
+    pub max_buffered_requests: i32,
+    pub slots_free: i32,
+}
@@ -1,21 +1,33 @@
 use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicI32;
 use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Result;
 use tokio::sync::Notify;
 use tokio::time::timeout;
 
+use crate::atomic_value::AtomicValue;
 use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::balancer_readiness::BalancerReadiness;
 use crate::balancer::buffered_request_agent_wait_result::BufferedRequestAgentWaitResult;
 use crate::balancer::buffered_request_counter::BufferedRequestCounter;
 use crate::balancer::buffered_request_manager_snapshot::BufferedRequestManagerSnapshot;
+use crate::balancer::request_drain::RequestDrain;
+use crate::balancer::request_metrics::RequestMetrics;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
+use crate::balancer::shadow_comparison_log::ShadowComparisonLog;
 use crate::produces_snapshot::ProducesSnapshot;
 
 pub struct BufferedRequestManager {
-    agent_controller_pool: Arc<AgentControllerPool>,
+    pub agent_controller_pool: Arc<AgentControllerPool>,
     pub buffered_request_counter: Arc<BufferedRequestCounter>,
-    buffered_request_timeout: Duration,
-    max_buffered_requests: i32,
+    buffered_request_timeout: RwLock<Duration>,
+    max_buffered_requests: AtomicValue<AtomicI32>,
+    pub request_drain: Arc<RequestDrain>,
+    pub request_metrics: RequestMetrics,
+    pub shadow_comparison_log: Option<Arc<ShadowComparisonLog>>,
     pub update_notifier: Arc<Notify>,
 }
 
@@ -24,6 +36,7 @@ impl BufferedRequestManager {
         agent_controller_pool: Arc<AgentControllerPool>,
         buffered_request_timeout: Duration,
         max_buffered_requests: i32,
+        shadow_comparison_log: Option<Arc<ShadowComparisonLog>>,
     ) -> Self {
         let update_notifier = Arc::new(Notify::new());
 
@@ -32,31 +45,80 @@ impl BufferedRequestManager {
             buffered_request_counter: Arc::new(BufferedRequestCounter::new(
                 update_notifier.clone(),
             )),
-            buffered_request_timeout,
-            max_buffered_requests,
+            buffered_request_timeout: RwLock::new(buffered_request_timeout),
+            max_buffered_requests: AtomicValue::<AtomicI32>::new(max_buffered_requests),
+            request_drain: Arc::new(RequestDrain::default()),
+            request_metrics: RequestMetrics::default(),
+            shadow_comparison_log,
             update_notifier,
         }
     }
 
-    pub async fn wait_for_available_agent(&self) -> Result<BufferedRequestAgentWaitResult> {
-        if self.buffered_request_counter.get() >= self.max_buffered_requests {
+    /// Balancer is ready when it is not draining, at least one agent serves its desired state,
+    /// and a new request can either get a free slot or wait in the buffer
+    pub fn get_readiness(&self) -> BalancerReadiness {
+        let buffered_requests_current = self.buffered_request_counter.get();
+        let is_draining = self.request_drain.is_draining();
+        let max_buffered_requests = self.max_buffered_requests.get();
+        let serving_capacity = self.agent_controller_pool.serving_capacity();
+
+        BalancerReadiness {
+            agents_serving: serving_capacity.agents_serving,
+            buffered_requests_current,
+            is_draining,
+            is_ready: !is_draining
+                && serving_capacity.agents_serving > 0
+                && (serving_capacity.slots_free > 0
+                    || buffered_requests_current < max_buffered_requests),
+            max_buffered_requests,
+            slots_free: serving_capacity.slots_free,
+        }
+    }
+
+    pub fn set_buffered_request_timeout(&self, buffered_request_timeout: Duration) {
+        *self
+            .buffered_request_timeout
+            .write()
+            .expect("Poisoned lock on buffered request timeout") = buffered_request_timeout;
+    }
+
+    pub fn set_max_buffered_requests(&self, max_buffered_requests: i32) {
+        self.max_buffered_requests.set(max_buffered_requests);
+    }
+
+    pub async fn wait_for_available_agent(
+        &self,
+        request_routing_hints: &RequestRoutingHints,
+    ) -> Result<BufferedRequestAgentWaitResult> {
+        if self.buffered_request_counter.get() >= self.max_buffered_requests.get() {
             return Ok(BufferedRequestAgentWaitResult::BufferOverflow);
         }
 
+        let started_waiting_at = Instant::now();
+
         // Do a quick check before getting into the coroutines
         if let Some(agent_controller) = self
             .agent_controller_pool
-            .take_least_busy_agent_controller()
+            .take_least_busy_agent_controller(request_routing_hints)
         {
+            self.request_metrics
+                .queue_wait_time
+                .observe(started_waiting_at.elapsed());
+
             return Ok(BufferedRequestAgentWaitResult::Found(agent_controller));
         }
 
         let _buffered_request_count_guard = self.buffered_request_counter.increment_with_guard();
         let agent_controller_pool = self.agent_controller_pool.clone();
+        let buffered_request_timeout = *self
+            .buffered_request_timeout
+            .read()
+            .expect("Poisoned lock on buffered request timeout");
 
-        match timeout(self.buffered_request_timeout, async {
+        match timeout(buffered_request_timeout, async {
             loop {
-                match agent_controller_pool.take_least_busy_agent_controller() {
+                match agent_controller_pool.take_least_busy_agent_controller(request_routing_hints)
+                {
                     Some(agent_controller) => {
                         return Ok::<_, anyhow::Error>(BufferedRequestAgentWaitResult::Found(
                             agent_controller,
@@ -68,7 +130,13 @@ impl BufferedRequestManager {
         })
         .await
         {
-            Ok(inner_result) => Ok(inner_result?),
+            Ok(inner_result) => {
+                self.request_metrics
+                    .queue_wait_time
+                    .observe(started_waiting_at.elapsed());
+
+                Ok(inner_result?)
+            }
             Err(timeout_err) => Ok(BufferedRequestAgentWaitResult::Timeout(timeout_err.into())),
         }
     }
@@ -83,3 +151,150 @@ impl ProducesSnapshot for BufferedRequestManager {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::collections::BTreeSet;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::AtomicUsize;
+
+    use tokio::sync::broadcast;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::agent_desired_state::AgentDesiredState;
+    use crate::agent_state_application_status::AgentStateApplicationStatus;
+    use crate::balancer::agent_controller::AgentController;
+    use crate::balancer::deployment::Deployment;
+    use crate::drains::Drains as _;
+
+    fn make_serving_agent_controller(slots_total: i32, slots_processing: i32) -> AgentController {
+        let (agent_message_tx, _) = mpsc::unbounded_channel();
+        let (_, connection_close_rx) = broadcast::channel(1);
+
+        AgentController {
+            agent_message_tx,
+            applied_desired_state_generation: AtomicValue::<AtomicI32>::new(1),
+            chat_template_override_sender_collection: Default::default(),
+            connection_close_rx,
+            deployment: RwLock::new(Deployment::Stable),
+            desired_state: RwLock::new(Some(AgentDesiredState::default())),
+            desired_state_generation: AtomicValue::<AtomicI32>::new(1),
+            desired_slots_total: AtomicValue::<AtomicI32>::new(slots_total),
+            download_current: AtomicValue::<AtomicUsize>::new(0),
+            download_filename: RwLock::new(None),
+            download_total: AtomicValue::<AtomicUsize>::new(0),
+            embedding_sender_collection: Default::default(),
+            generate_tokens_sender_collection: Default::default(),
+            id: "agent".to_string(),
+            is_desired_state_pending: AtomicValue::<AtomicBool>::new(false),
+            is_draining: AtomicValue::<AtomicBool>::new(false),
+            issues: RwLock::new(BTreeSet::new()),
+            labels: BTreeMap::new(),
+            model_metadata_sender_collection: Default::default(),
+            model_path: RwLock::new(None),
+            name: None,
+            newest_update_version: AtomicValue::<AtomicI32>::new(0),
+            slots_processing: AtomicValue::<AtomicI32>::new(slots_processing),
+            slots_total: AtomicValue::<AtomicI32>::new(slots_total),
+            state_application_status_code: AtomicValue::<AtomicI32>::new(
+                AgentStateApplicationStatus::Applied as i32,
+            ),
+            uses_chat_template_override: AtomicValue::<AtomicBool>::new(false),
+        }
+    }
+
+    fn make_buffered_request_manager(
+        agent_controller: Option<AgentController>,
+        max_buffered_requests: i32,
+    ) -> Result<BufferedRequestManager> {
+        let agent_controller_pool = Arc::new(AgentControllerPool::default());
+
+        *agent_controller_pool
+            .desired_state
+            .write()
+            .expect("Poisoned lock on desired state") = Some(AgentDesiredState::default());
+
+        if let Some(agent_controller) = agent_controller {
+            agent_controller_pool.register_agent_controller(
+                agent_controller.id.clone(),
+                Arc::new(agent_controller),
+            )?;
+        }
+
+        Ok(BufferedRequestManager::new(
+            agent_controller_pool,
+            Duration::from_secs(1),
+            max_buffered_requests,
+            None,
+        ))
+    }
+
+    #[test]
+    fn test_ready_with_a_free_slot() -> Result<()> {
+        let buffered_request_manager =
+            make_buffered_request_manager(Some(make_serving_agent_controller(2, 1)), 0)?;
+        let readiness = buffered_request_manager.get_readiness();
+
+        assert!(readiness.is_ready);
+        assert_eq!(readiness.agents_serving, 1);
+        assert_eq!(readiness.slots_free, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_while_draining() -> Result<()> {
+        let buffered_request_manager =
+            make_buffered_request_manager(Some(make_serving_agent_controller(2, 0)), 10)?;
+
+        buffered_request_manager.request_drain.drain().await;
+
+        let readiness = buffered_request_manager.get_readiness();
+
+        assert!(!readiness.is_ready);
+        assert!(readiness.is_draining);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_not_ready_without_serving_agents() -> Result<()> {
+        let buffered_request_manager = make_buffered_request_manager(None, 10)?;
+
+        assert!(!buffered_request_manager.get_readiness().is_ready);
+
+        let pending_agent_controller = make_serving_agent_controller(2, 0);
+
+        pending_agent_controller.is_desired_state_pending.set(true);
+
+        let buffered_request_manager =
+            make_buffered_request_manager(Some(pending_agent_controller), 10)?;
+        let readiness = buffered_request_manager.get_readiness();
+
+        assert!(!readiness.is_ready);
+        assert_eq!(readiness.agents_serving, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_not_ready_with_busy_slots_and_full_buffer() -> Result<()> {
+        let buffered_request_manager =
+            make_buffered_request_manager(Some(make_serving_agent_controller(2, 2)), 1)?;
+
+        assert!(buffered_request_manager.get_readiness().is_ready);
+
+        let _buffered_request_count_guard = buffered_request_manager
+            .buffered_request_counter
+            .increment_with_guard();
+        let readiness = buffered_request_manager.get_readiness();
+
+        assert!(!readiness.is_ready);
+        assert_eq!(readiness.buffered_requests_current, 1);
+        assert_eq!(readiness.slots_free, 0);
+
+        Ok(())
+    }
+}
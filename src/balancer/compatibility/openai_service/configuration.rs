@@ -1,6 +1,13 @@
-use std::net::SocketAddr;
+use std::sync::Arc;
+
+use rustls::ServerConfig;
+
+use crate::listen_addr::ListenAddr;
 
 #[derive(Clone)]
 pub struct Configuration {
-    pub addr: SocketAddr,
+    pub addr: ListenAddr,
+    pub tls_server_config: Option<Arc<ServerConfig>>,
+    /// Applied only when listening on a Unix socket
+    pub unix_socket_mode: Option<u32>,
 }
@@ -1,8 +1,12 @@
+use std::collections::HashMap;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
 use actix_web::Error;
+use actix_web::HttpRequest;
 use actix_web::HttpResponse;
+use actix_web::error::ErrorBadRequest;
+use actix_web::error::ErrorInternalServerError;
 use actix_web::post;
 use actix_web::web;
 use anyhow::anyhow;
@@ -17,11 +21,18 @@ use crate::balancer::compatibility::openai_service::app_data::AppData;
 use crate::balancer::http_stream_from_agent::http_stream_from_agent;
 use crate::balancer::inference_client::Message as OutgoingMessage;
 use crate::balancer::inference_client::Response as OutgoingResponse;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
 use crate::balancer::unbounded_stream_from_agent::unbounded_stream_from_agent;
 use crate::conversation_message::ConversationMessage;
+use crate::generated_token::GeneratedToken;
+use crate::generated_token_logprobs::GeneratedTokenLogprobs;
 use crate::generated_token_result::GeneratedTokenResult;
+use crate::generation_summary::GenerationSummary;
 use crate::jsonrpc::ResponseEnvelope;
+use crate::logit_bias_token::LogitBiasToken;
 use crate::request_params::ContinueFromConversationHistoryParams;
+use crate::token_logit_bias::TokenLogitBias;
+use crate::token_logprob::TokenLogprob;
 
 pub fn register(cfg: &mut web::ServiceConfig) {
     cfg.service(respond);
@@ -34,6 +45,35 @@ fn current_timestamp() -> u64 {
         .as_secs()
 }
 
+fn openai_logprobs_content(
+    GeneratedToken {
+        content, logprobs, ..
+    }: &GeneratedToken,
+) -> Option<serde_json::Value> {
+    logprobs.as_ref().map(
+        |GeneratedTokenLogprobs {
+             bytes,
+             logprob,
+             top_logprobs,
+             ..
+         }| {
+            json!({
+                "token": content,
+                "logprob": logprob,
+                "bytes": bytes,
+                "top_logprobs": top_logprobs
+                    .iter()
+                    .map(|TokenLogprob { bytes, logprob, token, .. }| json!({
+                        "token": token,
+                        "logprob": logprob,
+                        "bytes": bytes,
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        },
+    )
+}
+
 #[derive(Deserialize)]
 /// Although fields are same as in Paddler's conversation message for the moment,
 /// it would be better if this struct stayed independent from ours just in case
@@ -54,16 +94,25 @@ impl OpenAIMessage {
 
 #[derive(Deserialize)]
 struct OpenAICompletionRequestParams {
+    /// Maps token ids (as strings) to a bias added to their logits (-100 bans the token)
+    logit_bias: Option<HashMap<String, f32>>,
+    logprobs: Option<bool>,
     max_completion_tokens: Option<i32>,
     messages: Vec<OpenAIMessage>,
     /// This parameter is ignored here, but is required by the OpenAI API.
     model: String,
+    n: Option<u32>,
+    seed: Option<u32>,
     stream: bool,
+    top_logprobs: Option<usize>,
+    /// Used as the sticky routing key if the request does not set one in headers
+    user: Option<String>,
 }
 
 #[derive(Clone)]
 struct OpenAIStreamingResponseTransformer {
     model: String,
+    n: u32,
     system_fingerprint: String,
 }
 
@@ -78,21 +127,26 @@ impl TransformsOutgoingMessage for OpenAIStreamingResponseTransformer {
         match message {
             OutgoingMessage::Response(ResponseEnvelope {
                 request_id,
-                response: OutgoingResponse::GeneratedToken(GeneratedTokenResult::Done),
+                response:
+                    OutgoingResponse::GeneratedToken(GeneratedTokenResult::Done(GenerationSummary {
+                        seed,
+                        ..
+                    })),
             }) => Ok(json!({
                 "id": request_id,
                 "object": "chat.completion.chunk",
                 "created": current_timestamp(),
                 "model": self.model,
+                "seed": seed,
                 "system_fingerprint": self.system_fingerprint,
-                "choices": [
-                    {
-                        "index": 0,
+                "choices": (0..self.n)
+                    .map(|index| json!({
+                        "index": index,
                         "delta": {},
                         "logprobs": null,
                         "finish_reason": "stop"
-                    }
-                ]
+                    }))
+                    .collect::<Vec<_>>(),
             })),
             OutgoingMessage::Response(ResponseEnvelope {
                 request_id,
@@ -105,12 +159,15 @@ impl TransformsOutgoingMessage for OpenAIStreamingResponseTransformer {
                 "system_fingerprint": self.system_fingerprint,
                 "choices": [
                     {
-                        "index": 0,
+                        "index": token.choice_index,
                         "delta": {
                             "role": "assistant",
-                            "content": token,
+                            "content": token.content,
                         },
-                        "logprobs": null,
+                        "logprobs": openai_logprobs_content(&token).map(|content| json!({
+                            "content": [content],
+                            "refusal": null,
+                        })),
                         "finish_reason": null
                     }
                 ]
@@ -125,25 +182,18 @@ struct OpenAICombinedResponseTransformer {}
 
 #[async_trait]
 impl TransformsOutgoingMessage for OpenAICombinedResponseTransformer {
-    type TransformedMessage = String;
-
-    fn stringify(&self, message: &Self::TransformedMessage) -> anyhow::Result<String> {
-        Ok(message.clone())
-    }
+    type TransformedMessage = GeneratedTokenResult;
 
     async fn transform(
         &self,
         message: OutgoingMessage,
     ) -> anyhow::Result<Self::TransformedMessage> {
         match message {
+            // Errors are passed as well, to be returned instead of an empty completion
             OutgoingMessage::Response(ResponseEnvelope {
-                response: OutgoingResponse::GeneratedToken(GeneratedTokenResult::Done),
-                ..
-            }) => Ok("".to_string()),
-            OutgoingMessage::Response(ResponseEnvelope {
-                response: OutgoingResponse::GeneratedToken(GeneratedTokenResult::Token(token)),
+                response: OutgoingResponse::GeneratedToken(generated_token_result),
                 ..
-            }) => Ok(token),
+            }) => Ok(generated_token_result),
             _ => Err(anyhow!("Unexpected message type: {:?}", message)),
         }
     }
@@ -153,7 +203,28 @@ impl TransformsOutgoingMessage for OpenAICombinedResponseTransformer {
 async fn respond(
     app_data: web::Data<AppData>,
     openai_params: web::Json<OpenAICompletionRequestParams>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
+    let logit_bias = openai_params
+        .logit_bias
+        .iter()
+        .flatten()
+        .map(|(token_id, bias)| {
+            Ok(TokenLogitBias {
+                bias: *bias,
+                token: LogitBiasToken::Id(token_id.parse::<i32>()?),
+            })
+        })
+        .collect::<Result<Vec<_>, std::num::ParseIntError>>()
+        .map_err(ErrorBadRequest)?;
+    let n = openai_params.n.unwrap_or(1);
+    let mut request_routing_hints =
+        RequestRoutingHints::from_http_request(&req).map_err(ErrorBadRequest)?;
+
+    if request_routing_hints.sticky_key.is_none() {
+        request_routing_hints.sticky_key = openai_params.user.clone();
+    }
+
     let paddler_params = ContinueFromConversationHistoryParams {
         add_generation_prompt: true,
         conversation_history: openai_params
@@ -162,8 +233,14 @@ async fn respond(
             .map(|openai_message| openai_message.to_paddler_message())
             .collect(),
         enable_thinking: true,
+        logit_bias,
+        logprobs: openai_params.logprobs.unwrap_or(false),
         max_tokens: openai_params.max_completion_tokens.unwrap_or(2000),
+        n,
+        sampler_chain: None,
+        seed: openai_params.seed,
         tools: vec![],
+        top_logprobs: openai_params.top_logprobs.unwrap_or(0),
     };
 
     if openai_params.stream {
@@ -171,40 +248,90 @@ async fn respond(
             app_data.buffered_request_manager.clone(),
             app_data.inference_service_configuration.clone(),
             paddler_params,
+            request_routing_hints,
             OpenAIStreamingResponseTransformer {
                 model: openai_params.model.clone(),
+                n,
                 system_fingerprint: nanoid!(),
             },
         )
     } else {
-        let combined_response = unbounded_stream_from_agent(
+        let mut generated_tokens: Vec<GeneratedToken> = Vec::new();
+        let mut seed: Option<u32> = None;
+
+        for chunk in unbounded_stream_from_agent(
             app_data.buffered_request_manager.clone(),
             app_data.inference_service_configuration.clone(),
             paddler_params,
+            request_routing_hints,
             OpenAICombinedResponseTransformer {},
         )?
         .collect::<Vec<String>>()
         .await
-        .join("");
+        {
+            match serde_json::from_str::<GeneratedTokenResult>(&chunk)
+                .map_err(ErrorInternalServerError)?
+            {
+                GeneratedTokenResult::Done(generation_summary) => {
+                    seed = Some(generation_summary.seed);
+                }
+                GeneratedTokenResult::Token(generated_token) => {
+                    generated_tokens.push(generated_token);
+                }
+                GeneratedTokenResult::ChatTemplateError(message) => {
+                    return Err(ErrorInternalServerError(message));
+                }
+                // Agent rejects the requests it cannot generate, like too many choices
+                // or a prompt that does not fit in the context
+                GeneratedTokenResult::Error(message) => {
+                    return Err(ErrorBadRequest(message));
+                }
+            }
+        }
+
+        let choices = (0..n)
+            .map(|index| {
+                let choice_tokens = generated_tokens
+                    .iter()
+                    .filter(|generated_token| generated_token.choice_index == index)
+                    .collect::<Vec<_>>();
+                let combined_response = choice_tokens
+                    .iter()
+                    .map(|generated_token| generated_token.content.as_str())
+                    .collect::<String>();
+                let logprobs = if openai_params.logprobs.unwrap_or(false) {
+                    json!({
+                        "content": choice_tokens
+                            .iter()
+                            .filter_map(|generated_token| openai_logprobs_content(generated_token))
+                            .collect::<Vec<_>>(),
+                        "refusal": null,
+                    })
+                } else {
+                    serde_json::Value::Null
+                };
+
+                json!({
+                  "index": index,
+                  "message": {
+                    "role": "assistant",
+                    "content": combined_response,
+                    "refusal": null,
+                    "annotations": []
+                  },
+                  "logprobs": logprobs,
+                  "finish_reason": "stop"
+                })
+            })
+            .collect::<Vec<_>>();
 
         Ok(HttpResponse::Ok().json(json!({
           "id": nanoid!(),
           "object": "chat.completion",
           "created": current_timestamp(),
           "model": openai_params.model,
-          "choices": [
-            {
-              "index": 0,
-              "message": {
-                "role": "assistant",
-                "content": combined_response,
-                "refusal": null,
-                "annotations": []
-              },
-              "logprobs": null,
-              "finish_reason": "stop"
-            }
-          ],
+          "seed": seed,
+          "choices": choices,
           "usage": {
             "prompt_tokens": 0,
             "completion_tokens": 0,
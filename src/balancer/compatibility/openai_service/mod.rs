@@ -10,6 +10,7 @@ use actix_web::web::Data;
 use anyhow::Result;
 use async_trait::async_trait;
 use log::error;
+use rustls::ServerConfig;
 use tokio::sync::broadcast;
 
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
@@ -18,12 +19,16 @@ use crate::balancer::compatibility::openai_service::configuration::Configuration
 use crate::balancer::http_route as common_http_route;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
 use crate::create_cors_middleware::create_cors_middleware;
+use crate::listen_addr::ListenAddr;
 use crate::service::Service;
+use crate::service_health_registry::ServiceHealthRegistry;
+use crate::service_restart_policy::ServiceRestartPolicy;
 
 pub struct OpenAIService {
     pub buffered_request_manager: Arc<BufferedRequestManager>,
     pub inference_service_configuration: InferenceServiceConfiguration,
     pub openai_service_configuration: OpenAIServiceConfiguration,
+    pub service_health_registry: Arc<ServiceHealthRegistry>,
 }
 
 #[async_trait]
@@ -32,34 +37,57 @@ impl Service for OpenAIService {
         "balancer::compatibility::openai_service"
     }
 
+    fn restart_policy(&self) -> ServiceRestartPolicy {
+        ServiceRestartPolicy::Fatal
+    }
+
     async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
         let cors_allowed_hosts = self
             .inference_service_configuration
             .cors_allowed_hosts
             .clone();
-        let cors_allowed_hosts_arc = Arc::new(cors_allowed_hosts);
 
         let app_data = Data::new(AppData {
             buffered_request_manager: self.buffered_request_manager.clone(),
             inference_service_configuration: self.inference_service_configuration.clone(),
         });
 
-        HttpServer::new(move || {
+        let buffered_request_manager = Data::from(self.buffered_request_manager.clone());
+        let service_health_registry = Data::from(self.service_health_registry.clone());
+
+        let http_server = HttpServer::new(move || {
             App::new()
-                .wrap(create_cors_middleware(cors_allowed_hosts_arc.clone()))
+                .wrap(create_cors_middleware(cors_allowed_hosts.clone()))
                 .app_data(app_data.clone())
-                .configure(common_http_route::get_health::register)
+                .app_data(buffered_request_manager.clone())
+                .app_data(service_health_registry.clone())
+                .configure(common_http_route::get_health_live::register)
+                .configure(common_http_route::get_health_ready::register)
                 .configure(http_route::post_chat_completions::register)
         })
         .shutdown_signal(async move {
             if let Err(err) = shutdown.recv().await {
                 error!("Failed to receive shutdown signal: {err}");
             }
-        })
-        .bind(self.openai_service_configuration.addr)
-        .expect("Unable to bind server to address")
-        .run()
-        .await?;
+        });
+
+        let http_server = match (
+            &self.openai_service_configuration.addr,
+            &self.openai_service_configuration.tls_server_config,
+        ) {
+            (ListenAddr::Tcp(addr), Some(tls_server_config)) => {
+                http_server.bind_rustls_0_23(addr, ServerConfig::clone(tls_server_config))
+            }
+            (ListenAddr::Tcp(addr), None) => http_server.bind(addr),
+            (ListenAddr::Unix(path), _) => http_server.bind_uds(path),
+        }
+        .expect("Unable to bind server to address");
+
+        self.openai_service_configuration
+            .addr
+            .set_unix_socket_mode(self.openai_service_configuration.unix_socket_mode)?;
+
+        http_server.run().await?;
 
         Ok(())
     }
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Deployment {
+    Canary,
+    /// Receives only mirrored copies of requests, see `ShadowDeployment`
+    Shadow,
+    Stable,
+}
+
+impl Deployment {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Deployment::Canary => "canary",
+            Deployment::Shadow => "shadow",
+            Deployment::Stable => "stable",
+        }
+    }
+}
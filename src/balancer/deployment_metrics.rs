@@ -0,0 +1,50 @@
+use std::sync::atomic::AtomicUsize;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::atomic_value::AtomicValue;
+use crate::balancer::deployment_metrics_snapshot::DeploymentMetricsSnapshot;
+use crate::produces_snapshot::ProducesSnapshot;
+
+/// Counters used to compare the canary deployment against the stable one
+pub struct DeploymentMetrics {
+    pub requests_failed: AtomicValue<AtomicUsize>,
+    pub requests_succeeded: AtomicValue<AtomicUsize>,
+    pub response_time_ms_total: AtomicValue<AtomicUsize>,
+}
+
+impl DeploymentMetrics {
+    pub fn record_request(&self, is_successful: bool, response_time: Duration) {
+        if is_successful {
+            self.requests_succeeded.increment_by(1);
+        } else {
+            self.requests_failed.increment_by(1);
+        }
+
+        self.response_time_ms_total
+            .increment_by(response_time.as_millis() as usize);
+    }
+}
+
+impl Default for DeploymentMetrics {
+    fn default() -> Self {
+        Self {
+            requests_failed: AtomicValue::<AtomicUsize>::new(0),
+            requests_succeeded: AtomicValue::<AtomicUsize>::new(0),
+            response_time_ms_total: AtomicValue::<AtomicUsize>::new(0),
+        }
+    }
+}
+
+impl ProducesSnapshot for DeploymentMetrics {
+    type Snapshot = DeploymentMetricsSnapshot;
+
+    fn make_snapshot(&self) -> Result<Self::Snapshot> {
+        Ok(DeploymentMetricsSnapshot {
+            requests_failed: self.requests_failed.get(),
+            requests_succeeded: self.requests_succeeded.get(),
+            response_time_ms_total: self.response_time_ms_total.get(),
+        })
+    }
+}
@@ -0,0 +1,8 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct DeploymentMetricsSnapshot {
+    pub requests_failed: usize,
+    pub requests_succeeded: usize,
+    pub response_time_ms_total: usize,
+}
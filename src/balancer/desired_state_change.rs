@@ -0,0 +1,99 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// Single difference between two desired states
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DesiredStateChange {
+    /// Value in the newer state, `None` if it was removed
+    pub after: Option<Value>,
+    /// Value in the older state, `None` if it was added
+    pub before: Option<Value>,
+    /// JSON pointer to the changed value
+    pub path: String,
+}
+
+impl DesiredStateChange {
+    pub fn diff(before: &Value, after: &Value) -> Vec<Self> {
+        let mut changes = Vec::new();
+
+        collect_changes(String::new(), Some(before), Some(after), &mut changes);
+
+        changes
+    }
+}
+
+fn collect_changes(
+    path: String,
+    before: Option<&Value>,
+    after: Option<&Value>,
+    changes: &mut Vec<DesiredStateChange>,
+) {
+    match (before, after) {
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                collect_changes(
+                    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1")),
+                    before.get(key),
+                    after.get(key),
+                    changes,
+                );
+            }
+        }
+        (before, after) if before != after => changes.push(DesiredStateChange {
+            after: after.cloned(),
+            before: before.cloned(),
+            path,
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_diff_reports_nested_changes() {
+        let changes = DesiredStateChange::diff(
+            &json!({
+                "inference_parameters": { "temperature": 0.6, "top_k": 40 },
+                "model": "None",
+            }),
+            &json!({
+                "canary": null,
+                "inference_parameters": { "temperature": 0.8, "top_k": 40 },
+                "model": "None",
+            }),
+        );
+
+        assert_eq!(
+            changes,
+            vec![
+                DesiredStateChange {
+                    after: Some(json!(null)),
+                    before: None,
+                    path: "/canary".to_string(),
+                },
+                DesiredStateChange {
+                    after: Some(json!(0.8)),
+                    before: Some(json!(0.6)),
+                    path: "/inference_parameters/temperature".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_of_equal_states_is_empty() {
+        let state = json!({ "model": { "LocalToAgent": "/model.gguf" } });
+
+        assert!(DesiredStateChange::diff(&state, &state).is_empty());
+    }
+}
@@ -0,0 +1,9 @@
+use serde::Serialize;
+
+/// Reason why a desired state would not work if it was applied
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DesiredStateProblem {
+    pub message: String,
+    /// JSON pointer to the value that causes the problem
+    pub path: String,
+}
@@ -0,0 +1,277 @@
+use std::time::Duration;
+
+use hf_hub::Cache;
+use hf_hub::Repo;
+use hf_hub::RepoType;
+use hf_hub::api::tokio::ApiBuilder;
+use hf_hub::api::tokio::ApiError;
+use tokio::time::timeout;
+
+use crate::agent_desired_model::AgentDesiredModel;
+use crate::balancer::desired_state_problem::DesiredStateProblem;
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::chat_template_renderer::ChatTemplateRenderer;
+use crate::huggingface_model_reference::HuggingFaceModelReference;
+use crate::inference_parameters::InferenceParameters;
+use crate::validates::Validates as _;
+
+/// Slow Hub responses should not hold the dry run endpoint
+const HUGGINGFACE_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Checks everything the agents would check when applying the state,
+/// without pushing it to the agents or downloading the model
+pub async fn dry_run_desired_state(
+    balancer_desired_state: &BalancerDesiredState,
+) -> Vec<DesiredStateProblem> {
+    let mut problems = Vec::new();
+
+    check_deployment(String::new(), balancer_desired_state, &mut problems).await;
+
+    if let Some(canary) = &balancer_desired_state.canary {
+        check_deployment(
+            "/canary/desired_state".to_string(),
+            &canary.desired_state,
+            &mut problems,
+        )
+        .await;
+    }
+
+    if let Some(shadow) = &balancer_desired_state.shadow {
+        check_deployment(
+            "/shadow/desired_state".to_string(),
+            &shadow.desired_state,
+            &mut problems,
+        )
+        .await;
+    }
+
+    // Inference parameters are already reported field by field, and validation would stop
+    // at the first of them before reaching the rest of the state
+    if let Err(err) = without_inference_parameters(balancer_desired_state).validate() {
+        problems.insert(
+            0,
+            DesiredStateProblem {
+                message: format!("{err:#}"),
+                path: String::new(),
+            },
+        );
+    }
+
+    problems
+}
+
+async fn check_deployment(
+    path: String,
+    balancer_desired_state: &BalancerDesiredState,
+    problems: &mut Vec<DesiredStateProblem>,
+) {
+    if balancer_desired_state.use_chat_template_override {
+        match &balancer_desired_state.chat_template_override {
+            Some(chat_template) => {
+                if let Err(err) = ChatTemplateRenderer::new(chat_template.clone()) {
+                    problems.push(DesiredStateProblem {
+                        message: format!("Chat template does not compile: {err:#}"),
+                        path: format!("{path}/chat_template_override"),
+                    });
+                }
+            }
+            None => problems.push(DesiredStateProblem {
+                message: "Chat template override is enabled, but no template is given".to_string(),
+                path: format!("{path}/chat_template_override"),
+            }),
+        }
+    }
+
+    for (name, message) in balancer_desired_state
+        .inference_parameters
+        .find_out_of_range_parameters()
+    {
+        problems.push(DesiredStateProblem {
+            message: message.to_string(),
+            path: format!("{path}/inference_parameters/{name}"),
+        });
+    }
+
+    // Grammars are compiled here as well, agents would reject them only on the first request
+    for (index, sampler_stage) in balancer_desired_state
+        .inference_parameters
+        .sampler_chain
+        .iter()
+        .enumerate()
+    {
+        if let Err(err) = sampler_stage.clone().validate() {
+            problems.push(DesiredStateProblem {
+                message: format!("{err:#}"),
+                path: format!("{path}/inference_parameters/sampler_chain/{index}"),
+            });
+        }
+    }
+
+    let model_problem = match &balancer_desired_state.model {
+        AgentDesiredModel::HuggingFace(huggingface_model_reference) => {
+            find_huggingface_model_problem(huggingface_model_reference).await
+        }
+        _ => None,
+    };
+
+    if let Some(message) = model_problem {
+        problems.push(DesiredStateProblem {
+            message,
+            path: format!("{path}/model"),
+        });
+    }
+}
+
+fn without_inference_parameters(
+    balancer_desired_state: &BalancerDesiredState,
+) -> BalancerDesiredState {
+    let mut balancer_desired_state = balancer_desired_state.clone();
+
+    balancer_desired_state.inference_parameters = InferenceParameters::default();
+
+    if let Some(canary) = balancer_desired_state.canary.as_mut() {
+        canary.desired_state.inference_parameters = InferenceParameters::default();
+    }
+
+    if let Some(shadow) = balancer_desired_state.shadow.as_mut() {
+        shadow.desired_state.inference_parameters = InferenceParameters::default();
+    }
+
+    balancer_desired_state
+}
+
+/// Only fetches the repository file list, the model itself is not downloaded
+async fn find_huggingface_model_problem(
+    HuggingFaceModelReference {
+        filename,
+        repo_id,
+        revision,
+    }: &HuggingFaceModelReference,
+) -> Option<String> {
+    let hf_api = match ApiBuilder::from_cache(Cache::default()).build() {
+        Ok(hf_api) => hf_api,
+        Err(err) => return Some(format!("Unable to connect to Hugging Face: {err}")),
+    };
+    let hf_repo = hf_api.repo(Repo::with_revision(
+        repo_id.to_owned(),
+        RepoType::Model,
+        revision.to_owned(),
+    ));
+
+    let repo_info = match timeout(HUGGINGFACE_CHECK_TIMEOUT, hf_repo.info()).await {
+        Ok(repo_info) => repo_info,
+        Err(_) => {
+            return Some(format!(
+                "Hugging Face did not respond within {} seconds",
+                HUGGINGFACE_CHECK_TIMEOUT.as_secs()
+            ));
+        }
+    };
+
+    match repo_info {
+        Ok(repo_info) => {
+            if repo_info
+                .siblings
+                .iter()
+                .any(|sibling| sibling.rfilename == *filename)
+            {
+                None
+            } else {
+                Some(format!(
+                    "File '{filename}' does not exist in '{repo_id}' at revision '{revision}'"
+                ))
+            }
+        }
+        Err(ApiError::RequestError(reqwest_error))
+            if reqwest_error.status() == Some(reqwest::StatusCode::NOT_FOUND) =>
+        {
+            Some(format!(
+                "Repository '{repo_id}' or its revision '{revision}' does not exist on Hugging Face"
+            ))
+        }
+        Err(err) => Some(format!("Unable to check the model on Hugging Face: {err}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canary_deployment::CanaryDeployment;
+    use crate::chat_template::ChatTemplate;
+    use crate::sampler_stage::SamplerStage;
+
+    #[tokio::test]
+    async fn test_reports_broken_chat_template_and_parameters() {
+        let problems = dry_run_desired_state(&BalancerDesiredState {
+            chat_template_override: Some(ChatTemplate {
+                content: "{% for message in messages %}".to_string(),
+            }),
+            inference_parameters: InferenceParameters {
+                temperature: -1.0,
+                top_p: 1.5,
+                sampler_chain: vec![
+                    SamplerStage::Temperature,
+                    SamplerStage::Grammar {
+                        grammar: "root ::= answer".to_string(),
+                        root: "root".to_string(),
+                    },
+                ],
+                ..InferenceParameters::default()
+            },
+            use_chat_template_override: true,
+            ..BalancerDesiredState::default()
+        })
+        .await;
+
+        let paths: Vec<&str> = problems
+            .iter()
+            .map(|problem| problem.path.as_str())
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "/chat_template_override",
+                "/inference_parameters/temperature",
+                "/inference_parameters/top_p",
+                "/inference_parameters/sampler_chain/1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reports_parameters_together_with_the_rest_of_the_state() {
+        let problems = dry_run_desired_state(&BalancerDesiredState {
+            canary: Some(CanaryDeployment {
+                agent_selector: Default::default(),
+                agents: 1,
+                desired_state: Box::new(BalancerDesiredState::default()),
+                sticky: false,
+                traffic_percentage: 150,
+            }),
+            inference_parameters: InferenceParameters {
+                temperature: -1.0,
+                ..InferenceParameters::default()
+            },
+            ..BalancerDesiredState::default()
+        })
+        .await;
+
+        let paths: Vec<&str> = problems
+            .iter()
+            .map(|problem| problem.path.as_str())
+            .collect();
+
+        assert_eq!(paths, vec!["", "/inference_parameters/temperature"]);
+        assert!(problems[0].message.contains("traffic_percentage"));
+    }
+
+    #[tokio::test]
+    async fn test_default_state_has_no_problems() {
+        assert!(
+            dry_run_desired_state(&BalancerDesiredState::default())
+                .await
+                .is_empty()
+        );
+    }
+}
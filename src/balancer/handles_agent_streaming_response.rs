@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use crate::agent::jsonrpc::Request as AgentJsonRpcRequest;
 use crate::balancer::manages_senders::ManagesSenders;
 use crate::balancer::manages_senders_controller::ManagesSendersController;
+use crate::balancer::request_endpoint::RequestEndpoint;
 
 #[async_trait]
 pub trait HandlesAgentStreamingResponse<TParams>
@@ -12,6 +13,8 @@ where
 {
     type SenderCollection: ManagesSenders + Send + Sync;
 
+    const REQUEST_ENDPOINT: RequestEndpoint;
+
     async fn handle_streaming_response(
         &self,
         request_id: String,
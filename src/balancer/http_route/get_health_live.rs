@@ -0,0 +1,33 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::routes;
+use actix_web::web;
+
+use crate::produces_snapshot::ProducesSnapshot as _;
+use crate::service_health_registry::ServiceHealthRegistry;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Reports unhealthy once any of the supervised services failed and won't be restarted.
+/// `/health` is kept for the existing probes.
+#[routes]
+#[get("/health")]
+#[get("/health/live")]
+async fn respond(
+    service_health_registry: web::Data<ServiceHealthRegistry>,
+) -> Result<impl Responder, Error> {
+    let snapshot = service_health_registry
+        .make_snapshot()
+        .map_err(ErrorInternalServerError)?;
+
+    if snapshot.is_healthy {
+        Ok(HttpResponse::Ok().json(snapshot))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(snapshot))
+    }
+}
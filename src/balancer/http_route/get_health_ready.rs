@@ -0,0 +1,23 @@
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::get;
+use actix_web::web;
+
+use crate::balancer::buffered_request_manager::BufferedRequestManager;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Load balancers should only send traffic once an agent can take it,
+/// and stop once the balancer starts draining
+#[get("/health/ready")]
+async fn respond(buffered_request_manager: web::Data<BufferedRequestManager>) -> impl Responder {
+    let readiness = buffered_request_manager.get_readiness();
+
+    if readiness.is_ready {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
+}
@@ -6,6 +6,7 @@ use actix_web::HttpResponse;
 use actix_web::http::header;
 use bytes::Bytes;
 use futures::stream::StreamExt;
+use serde::Serialize;
 
 use crate::agent::jsonrpc::Request as AgentJsonRpcRequest;
 use crate::balancer::agent_controller::AgentController;
@@ -15,6 +16,7 @@ use crate::balancer::handles_agent_streaming_response::HandlesAgentStreamingResp
 use crate::balancer::inference_client::Response as OutgoingResponse;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
 use crate::balancer::manages_senders::ManagesSenders;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
 use crate::balancer::unbounded_stream_from_agent::unbounded_stream_from_agent;
 use crate::streamable_result::StreamableResult;
 
@@ -22,10 +24,11 @@ pub fn http_stream_from_agent<TParams, TTransformsOutgoingMessage>(
     buffered_request_manager: Arc<BufferedRequestManager>,
     inference_service_configuration: InferenceServiceConfiguration,
     params: TParams,
+    request_routing_hints: RequestRoutingHints,
     transformer: TTransformsOutgoingMessage,
 ) -> Result<HttpResponse, Error>
 where
-    TParams: Debug + Into<AgentJsonRpcRequest> + Send + 'static,
+    TParams: Clone + Debug + Into<AgentJsonRpcRequest> + Send + Serialize + 'static,
     AgentController: HandlesAgentStreamingResponse<TParams>,
     <<AgentController as HandlesAgentStreamingResponse<TParams>>::SenderCollection as ManagesSenders>::Value: Debug + Into<OutgoingResponse> + StreamableResult,
     TTransformsOutgoingMessage: Clone + TransformsOutgoingMessage + Send + Sync + 'static,
@@ -34,6 +37,7 @@ where
         buffered_request_manager,
         inference_service_configuration,
         params,
+        request_routing_hints,
         transformer,
     )?
     .map(|chunk: String| Ok::<_, Error>(Bytes::from(format!("{chunk}\n"))));
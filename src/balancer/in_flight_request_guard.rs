@@ -0,0 +1,19 @@
+use std::sync::Arc;
+
+use crate::balancer::request_drain::RequestDrain;
+
+pub struct InFlightRequestGuard {
+    request_drain: Arc<RequestDrain>,
+}
+
+impl InFlightRequestGuard {
+    pub fn new(request_drain: Arc<RequestDrain>) -> Self {
+        InFlightRequestGuard { request_drain }
+    }
+}
+
+impl Drop for InFlightRequestGuard {
+    fn drop(&mut self) {
+        self.request_drain.finish_request();
+    }
+}
@@ -1,9 +1,35 @@
-use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::RwLock;
 use std::time::Duration;
 
+use rustls::ServerConfig;
+
+use crate::cors_allowed_hosts::CorsAllowedHosts;
+use crate::listen_addr::ListenAddr;
+
+/// Clones share the reloadable settings
 #[derive(Clone)]
 pub struct Configuration {
-    pub addr: SocketAddr,
-    pub cors_allowed_hosts: Vec<String>,
-    pub inference_item_timeout: Duration,
+    pub addr: ListenAddr,
+    pub cors_allowed_hosts: Arc<CorsAllowedHosts>,
+    pub inference_item_timeout: Arc<RwLock<Duration>>,
+    pub tls_server_config: Option<Arc<ServerConfig>>,
+    /// Applied only when listening on a Unix socket
+    pub unix_socket_mode: Option<u32>,
+}
+
+impl Configuration {
+    pub fn get_inference_item_timeout(&self) -> Duration {
+        *self
+            .inference_item_timeout
+            .read()
+            .expect("Poisoned lock on inference item timeout")
+    }
+
+    pub fn set_inference_item_timeout(&self, inference_item_timeout: Duration) {
+        *self
+            .inference_item_timeout
+            .write()
+            .expect("Poisoned lock on inference item timeout") = inference_item_timeout;
+    }
 }
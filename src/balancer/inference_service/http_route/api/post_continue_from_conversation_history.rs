@@ -2,6 +2,7 @@ use actix_web::post;
 use actix_web::error::ErrorBadRequest;
 use actix_web::web;
 use actix_web::Error;
+use actix_web::HttpRequest;
 use actix_web::Responder;
 
 use crate::validates::Validates as _;
@@ -10,6 +11,7 @@ use crate::request_params::ContinueFromConversationHistoryParams;
 use crate::request_params::continue_from_conversation_history_params::tool::tool_params::function_call::parameters_schema::raw_parameters_schema::RawParametersSchema;
 use crate::balancer::http_stream_from_agent::http_stream_from_agent;
 use crate::balancer::chunk_forwarding_session_controller::identity_transformer::IdentityTransformer;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
 
 pub fn register(cfg: &mut web::ServiceConfig) {
     cfg.service(respond);
@@ -19,6 +21,7 @@ pub fn register(cfg: &mut web::ServiceConfig) {
 async fn respond(
     app_data: web::Data<AppData>,
     params: web::Json<ContinueFromConversationHistoryParams<RawParametersSchema>>,
+    req: HttpRequest,
 ) -> Result<impl Responder, Error> {
     http_stream_from_agent(
         app_data.buffered_request_manager.clone(),
@@ -31,6 +34,7 @@ async fn respond(
                 )));
             }
         },
+        RequestRoutingHints::from_http_request(&req).map_err(ErrorBadRequest)?,
         IdentityTransformer::new(),
     )
 }
@@ -1,6 +1,8 @@
 use actix_web::Error;
+use actix_web::HttpRequest;
 use actix_web::HttpResponse;
 use actix_web::Responder;
+use actix_web::error::ErrorBadRequest;
 use actix_web::error::ErrorNotImplemented;
 use actix_web::error::ErrorServiceUnavailable;
 use actix_web::http::header;
@@ -20,6 +22,7 @@ use crate::balancer::chunk_forwarding_session_controller::identity_transformer::
 use crate::balancer::inference_client::Message as OutgoingMessage;
 use crate::balancer::inference_service::app_data::AppData;
 use crate::balancer::request_from_agent::request_from_agent;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
 use crate::controls_session::ControlsSession as _;
 use crate::jsonrpc::Error as JsonRpcError;
 use crate::jsonrpc::ErrorEnvelope;
@@ -35,6 +38,7 @@ pub fn register(cfg: &mut web::ServiceConfig) {
 async fn respond(
     app_data: web::Data<AppData>,
     params: web::Json<GenerateEmbeddingBatchParams>,
+    req: HttpRequest,
 ) -> Result<impl Responder, Error> {
     let balancer_applicable_state_holder = app_data.balancer_applicable_state_holder.clone();
     let agent_desired_state = match balancer_applicable_state_holder.get_agent_desired_state() {
@@ -53,6 +57,8 @@ async fn respond(
     }
 
     let (connection_close_tx, _connection_close_rx) = broadcast::channel::<()>(1);
+    let request_routing_hints =
+        RequestRoutingHints::from_http_request(&req).map_err(ErrorBadRequest)?;
     let (chunk_tx, chunk_rx) = mpsc::unbounded_channel();
 
     // Distribute the embeddings evenly across the available agents
@@ -65,6 +71,7 @@ async fn respond(
         let connection_close_tx_clone = connection_close_tx.clone();
         let inference_service_configuration_clone =
             app_data.inference_service_configuration.clone();
+        let request_routing_hints_clone = request_routing_hints.clone();
 
         rt::spawn(async move {
             let request_id: String = nanoid!();
@@ -77,6 +84,7 @@ async fn respond(
                 inference_service_configuration_clone,
                 batch,
                 request_id.clone(),
+                request_routing_hints_clone,
                 session_controller.clone(),
             )
             .await
@@ -2,8 +2,10 @@ use std::sync::Arc;
 
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
 
 pub struct InferenceSocketControllerContext {
     pub buffered_request_manager: Arc<BufferedRequestManager>,
     pub inference_service_configuration: InferenceServiceConfiguration,
+    pub request_routing_hints: RequestRoutingHints,
 }
@@ -6,6 +6,7 @@ use std::sync::Arc;
 use actix_web::Error;
 use actix_web::HttpRequest;
 use actix_web::HttpResponse;
+use actix_web::error::ErrorBadRequest;
 use actix_web::get;
 use actix_web::web::Data;
 use actix_web::web::Payload;
@@ -23,6 +24,7 @@ use crate::balancer::inference_client::Message as OutgoingMessage;
 use crate::balancer::inference_service::app_data::AppData;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
 use crate::balancer::request_from_agent::request_from_agent;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
 use crate::controls_websocket_endpoint::ContinuationDecision;
 use crate::controls_websocket_endpoint::ControlsWebSocketEndpoint;
 use crate::jsonrpc::Error as JsonRpcError;
@@ -38,6 +40,7 @@ pub fn register(cfg: &mut ServiceConfig) {
 struct InferenceSocketController {
     buffered_request_manager: Arc<BufferedRequestManager>,
     inference_service_configuration: InferenceServiceConfiguration,
+    request_routing_hints: RequestRoutingHints,
 }
 
 #[async_trait]
@@ -50,6 +53,7 @@ impl ControlsWebSocketEndpoint for InferenceSocketController {
         InferenceSocketControllerContext {
             buffered_request_manager: self.buffered_request_manager.clone(),
             inference_service_configuration: self.inference_service_configuration.clone(),
+            request_routing_hints: self.request_routing_hints.clone(),
         }
     }
 
@@ -80,6 +84,7 @@ impl ControlsWebSocketEndpoint for InferenceSocketController {
                     context.inference_service_configuration.clone(),
                     params.validate()?,
                     id,
+                    context.request_routing_hints.clone(),
                     websocket_session_controller,
                 )
                 .await?;
@@ -96,6 +101,7 @@ impl ControlsWebSocketEndpoint for InferenceSocketController {
                     context.inference_service_configuration.clone(),
                     params,
                     id,
+                    context.request_routing_hints.clone(),
                     websocket_session_controller,
                 )
                 .await?;
@@ -115,6 +121,8 @@ async fn respond(
     let inference_socket_controller = InferenceSocketController {
         buffered_request_manager: app_data.buffered_request_manager.clone(),
         inference_service_configuration: app_data.inference_service_configuration.clone(),
+        request_routing_hints: RequestRoutingHints::from_http_request(&req)
+            .map_err(ErrorBadRequest)?,
     };
 
     inference_socket_controller.respond(payload, req)
@@ -10,24 +10,25 @@ use actix_web::web::Data;
 use anyhow::Result;
 use async_trait::async_trait;
 use log::error;
+use rustls::ServerConfig;
 use tokio::sync::broadcast;
 
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
 use crate::balancer::http_route as common_http_route;
 use crate::balancer::inference_service::app_data::AppData;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
-#[cfg(feature = "web_admin_panel")]
-use crate::balancer::web_admin_panel_service::configuration::Configuration as WebAdminPanelServiceConfiguration;
 use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
 use crate::create_cors_middleware::create_cors_middleware;
+use crate::listen_addr::ListenAddr;
 use crate::service::Service;
+use crate::service_health_registry::ServiceHealthRegistry;
+use crate::service_restart_policy::ServiceRestartPolicy;
 
 pub struct InferenceService {
     pub balancer_applicable_state_holder: Arc<BalancerApplicableStateHolder>,
     pub buffered_request_manager: Arc<BufferedRequestManager>,
     pub configuration: InferenceServiceConfiguration,
-    #[cfg(feature = "web_admin_panel")]
-    pub web_admin_panel_service_configuration: Option<WebAdminPanelServiceConfiguration>,
+    pub service_health_registry: Arc<ServiceHealthRegistry>,
 }
 
 #[async_trait]
@@ -36,16 +37,12 @@ impl Service for InferenceService {
         "balancer::inference_service"
     }
 
-    async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        #[allow(unused_mut)]
-        let mut cors_allowed_hosts = self.configuration.cors_allowed_hosts.clone();
-
-        #[cfg(feature = "web_admin_panel")]
-        if let Some(web_admin_panel_config) = &self.web_admin_panel_service_configuration {
-            cors_allowed_hosts.push(format!("http://{}", web_admin_panel_config.addr));
-        }
+    fn restart_policy(&self) -> ServiceRestartPolicy {
+        ServiceRestartPolicy::Fatal
+    }
 
-        let cors_allowed_hosts_arc = Arc::new(cors_allowed_hosts);
+    async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let cors_allowed_hosts = self.configuration.cors_allowed_hosts.clone();
 
         let app_data = Data::new(AppData {
             balancer_applicable_state_holder: self.balancer_applicable_state_holder.clone(),
@@ -53,11 +50,17 @@ impl Service for InferenceService {
             inference_service_configuration: self.configuration.clone(),
         });
 
-        HttpServer::new(move || {
+        let buffered_request_manager = Data::from(self.buffered_request_manager.clone());
+        let service_health_registry = Data::from(self.service_health_registry.clone());
+
+        let http_server = HttpServer::new(move || {
             App::new()
-                .wrap(create_cors_middleware(cors_allowed_hosts_arc.clone()))
+                .wrap(create_cors_middleware(cors_allowed_hosts.clone()))
                 .app_data(app_data.clone())
-                .configure(common_http_route::get_health::register)
+                .app_data(buffered_request_manager.clone())
+                .app_data(service_health_registry.clone())
+                .configure(common_http_route::get_health_live::register)
+                .configure(common_http_route::get_health_ready::register)
                 .configure(http_route::api::post_continue_from_conversation_history::register)
                 .configure(http_route::api::post_continue_from_raw_prompt::register)
                 .configure(http_route::api::post_generate_embedding_batch::register)
@@ -67,11 +70,25 @@ impl Service for InferenceService {
             if let Err(err) = shutdown.recv().await {
                 error!("Failed to receive shutdown signal: {err}");
             }
-        })
-        .bind(self.configuration.addr)
-        .expect("Unable to bind server to address")
-        .run()
-        .await?;
+        });
+
+        let http_server = match (
+            &self.configuration.addr,
+            &self.configuration.tls_server_config,
+        ) {
+            (ListenAddr::Tcp(addr), Some(tls_server_config)) => {
+                http_server.bind_rustls_0_23(addr, ServerConfig::clone(tls_server_config))
+            }
+            (ListenAddr::Tcp(addr), None) => http_server.bind(addr),
+            (ListenAddr::Unix(path), _) => http_server.bind_uds(path),
+        }
+        .expect("Unable to bind server to address");
+
+        self.configuration
+            .addr
+            .set_unix_socket_mode(self.configuration.unix_socket_mode)?;
+
+        http_server.run().await?;
 
         Ok(())
     }
@@ -0,0 +1,94 @@
+use std::sync::atomic::AtomicUsize;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::atomic_value::AtomicValue;
+use crate::balancer::latency_histogram_snapshot::LatencyHistogramSnapshot;
+use crate::produces_snapshot::ProducesSnapshot;
+
+/// Upper bounds of the histogram buckets, in milliseconds
+pub const LATENCY_HISTOGRAM_BUCKETS_MS: [u64; 14] = [
+    5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000, 120_000,
+];
+
+pub struct LatencyHistogram {
+    /// Not cumulative, the last one counts the observations above the largest bound
+    bucket_counts: [AtomicValue<AtomicUsize>; LATENCY_HISTOGRAM_BUCKETS_MS.len() + 1],
+    sum_us: AtomicValue<AtomicUsize>,
+}
+
+impl LatencyHistogram {
+    pub fn observe(&self, duration: Duration) {
+        let bucket_index = LATENCY_HISTOGRAM_BUCKETS_MS
+            .iter()
+            .position(|bound_ms| duration <= Duration::from_millis(*bound_ms))
+            .unwrap_or(LATENCY_HISTOGRAM_BUCKETS_MS.len());
+
+        self.bucket_counts[bucket_index].increment_by(1);
+        self.sum_us.increment_by(duration.as_micros() as usize);
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            bucket_counts: std::array::from_fn(|_| AtomicValue::<AtomicUsize>::new(0)),
+            sum_us: AtomicValue::<AtomicUsize>::new(0),
+        }
+    }
+}
+
+impl ProducesSnapshot for LatencyHistogram {
+    type Snapshot = LatencyHistogramSnapshot;
+
+    fn make_snapshot(&self) -> Result<Self::Snapshot> {
+        let mut count = 0;
+        let mut cumulative_counts = Vec::with_capacity(LATENCY_HISTOGRAM_BUCKETS_MS.len());
+
+        for (bucket_index, bucket_count) in self.bucket_counts.iter().enumerate() {
+            count += bucket_count.get();
+
+            if bucket_index < LATENCY_HISTOGRAM_BUCKETS_MS.len() {
+                cumulative_counts.push(count);
+            }
+        }
+
+        Ok(LatencyHistogramSnapshot {
+            count,
+            cumulative_counts,
+            sum_us: self.sum_us.get(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observations_are_counted_in_cumulative_buckets() -> Result<()> {
+        let latency_histogram = LatencyHistogram::default();
+
+        latency_histogram.observe(Duration::from_millis(3));
+        latency_histogram.observe(Duration::from_millis(10));
+        latency_histogram.observe(Duration::from_millis(700));
+        latency_histogram.observe(Duration::from_secs(600));
+
+        let LatencyHistogramSnapshot {
+            count,
+            cumulative_counts,
+            sum_us,
+        } = latency_histogram.make_snapshot()?;
+
+        assert_eq!(count, 4);
+        assert_eq!(cumulative_counts[0], 1);
+        assert_eq!(cumulative_counts[1], 2);
+        assert_eq!(cumulative_counts[6], 2);
+        assert_eq!(cumulative_counts[7], 3);
+        assert_eq!(cumulative_counts.last(), Some(&3));
+        assert_eq!(sum_us, 600_713_000);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+#[derive(Clone, Default)]
+pub struct LatencyHistogramSnapshot {
+    pub count: usize,
+    /// Aligned with `LATENCY_HISTOGRAM_BUCKETS_MS`
+    pub cumulative_counts: Vec<usize>,
+    pub sum_us: usize,
+}
@@ -0,0 +1,8 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct ManagementReadiness {
+    pub is_draining: bool,
+    pub is_healthy: bool,
+    pub is_ready: bool,
+}
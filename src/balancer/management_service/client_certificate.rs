@@ -0,0 +1,27 @@
+use std::any::Any;
+
+use actix_tls::accept::rustls_0_23::TlsStream;
+use actix_web::dev::Extensions;
+use actix_web::rt::net::TcpStream;
+use rustls::pki_types::CertificateDer;
+
+/// Certificate that the client presented and the server verified during the TLS handshake
+pub struct ClientCertificate {
+    pub certificate: CertificateDer<'static>,
+}
+
+impl ClientCertificate {
+    /// Stores the certificate in the connection data, so the handlers can read it
+    pub fn store_in_connection_data(connection: &dyn Any, extensions: &mut Extensions) {
+        let certificate = connection
+            .downcast_ref::<TlsStream<TcpStream>>()
+            .and_then(|tls_stream| tls_stream.get_ref().1.peer_certificates())
+            .and_then(|certificates| certificates.first());
+
+        if let Some(certificate) = certificate {
+            extensions.insert(ClientCertificate {
+                certificate: certificate.clone().into_owned(),
+            });
+        }
+    }
+}
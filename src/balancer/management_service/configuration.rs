@@ -1,7 +1,17 @@
-use std::net::SocketAddr;
+use std::sync::Arc;
+
+use rustls::ServerConfig;
+
+use crate::cors_allowed_hosts::CorsAllowedHosts;
+use crate::listen_addr::ListenAddr;
 
 #[derive(Clone)]
 pub struct Configuration {
-    pub addr: SocketAddr,
-    pub cors_allowed_hosts: Vec<String>,
+    pub addr: ListenAddr,
+    pub cors_allowed_hosts: Arc<CorsAllowedHosts>,
+    /// Agents have to present a client certificate to connect to the agent socket
+    pub requires_agent_certificate: bool,
+    pub tls_server_config: Option<Arc<ServerConfig>>,
+    /// Applied only when listening on a Unix socket
+    pub unix_socket_mode: Option<u32>,
 }
@@ -3,9 +3,11 @@ use actix_web::HttpResponse;
 use actix_web::Responder;
 use actix_web::error::ErrorInternalServerError;
 use actix_web::get;
+use actix_web::http::header::ETag;
 use actix_web::web;
 
 use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::revision_precondition::revision_etag;
 
 pub fn register(cfg: &mut web::ServiceConfig) {
     cfg.service(respond);
@@ -13,11 +15,13 @@ pub fn register(cfg: &mut web::ServiceConfig) {
 
 #[get("/api/v1/balancer_desired_state")]
 async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
-    let desired_state = app_data
+    let revision = app_data
         .state_database
-        .read_balancer_desired_state()
+        .read_current_balancer_desired_state_revision()
         .await
         .map_err(ErrorInternalServerError)?;
 
-    Ok(HttpResponse::Ok().json(desired_state))
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(revision_etag(revision.id)))
+        .json(revision.balancer_desired_state))
 }
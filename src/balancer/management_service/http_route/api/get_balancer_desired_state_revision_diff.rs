@@ -0,0 +1,60 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::error::ErrorNotFound;
+use actix_web::get;
+use actix_web::web;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::balancer_desired_state_revision::BalancerDesiredStateRevision;
+use crate::balancer::desired_state_change::DesiredStateChange;
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    from_id: u64,
+    to_id: u64,
+}
+
+#[derive(Serialize)]
+struct RevisionDiff {
+    changes: Vec<DesiredStateChange>,
+    from_id: u64,
+    to_id: u64,
+}
+
+async fn read_revision(app_data: &AppData, id: u64) -> Result<BalancerDesiredStateRevision, Error> {
+    app_data
+        .state_database
+        .read_balancer_desired_state_revision(id)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound(format!("Revision {id} does not exist")))
+}
+
+#[get("/api/v1/balancer_desired_state/revisions/{from_id}/diff/{to_id}")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Path<PathParams>,
+) -> Result<impl Responder, Error> {
+    let from_revision = read_revision(&app_data, params.from_id).await?;
+    let to_revision = read_revision(&app_data, params.to_id).await?;
+
+    Ok(HttpResponse::Ok().json(RevisionDiff {
+        changes: DesiredStateChange::diff(
+            &serde_json::to_value(&from_revision.balancer_desired_state)
+                .map_err(ErrorInternalServerError)?,
+            &serde_json::to_value(&to_revision.balancer_desired_state)
+                .map_err(ErrorInternalServerError)?,
+        ),
+        from_id: from_revision.id,
+        to_id: to_revision.id,
+    }))
+}
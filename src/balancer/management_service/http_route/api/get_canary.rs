@@ -0,0 +1,53 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+use serde::Serialize;
+
+use crate::balancer::deployment::Deployment;
+use crate::balancer::deployment_metrics_snapshot::DeploymentMetricsSnapshot;
+use crate::balancer::management_service::app_data::AppData;
+use crate::canary_deployment::CanaryDeployment;
+use crate::produces_snapshot::ProducesSnapshot as _;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Serialize)]
+struct CanaryStatus {
+    canary: Option<CanaryDeployment>,
+    canary_agents: Vec<String>,
+    canary_metrics: DeploymentMetricsSnapshot,
+    stable_metrics: DeploymentMetricsSnapshot,
+}
+
+#[get("/api/v1/canary")]
+async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
+    let desired_state = app_data
+        .state_database
+        .read_balancer_desired_state()
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let agent_controller_pool = &app_data.agent_controller_pool;
+
+    Ok(HttpResponse::Ok().json(CanaryStatus {
+        canary: desired_state.canary,
+        canary_agents: agent_controller_pool
+            .agents
+            .iter()
+            .filter(|entry| entry.value().get_deployment() == Deployment::Canary)
+            .map(|entry| entry.key().clone())
+            .collect(),
+        canary_metrics: agent_controller_pool
+            .canary_metrics
+            .make_snapshot()
+            .map_err(ErrorInternalServerError)?,
+        stable_metrics: agent_controller_pool
+            .stable_metrics
+            .make_snapshot()
+            .map_err(ErrorInternalServerError)?,
+    }))
+}
@@ -0,0 +1,24 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+
+use crate::produces_snapshot::ProducesSnapshot as _;
+use crate::service_health_registry::ServiceHealthRegistry;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[get("/api/v1/services")]
+async fn respond(
+    service_health_registry: web::Data<ServiceHealthRegistry>,
+) -> Result<impl Responder, Error> {
+    Ok(HttpResponse::Ok().json(
+        service_health_registry
+            .make_snapshot()
+            .map_err(ErrorInternalServerError)?,
+    ))
+}
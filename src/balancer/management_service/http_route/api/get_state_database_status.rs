@@ -0,0 +1,24 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::get;
+use actix_web::web;
+use serde::Serialize;
+
+use crate::balancer::management_service::app_data::AppData;
+
+#[derive(Serialize)]
+struct StateDatabaseStatus {
+    external_change_error: Option<String>,
+}
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[get("/api/v1/state_database/status")]
+async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
+    Ok(HttpResponse::Ok().json(StateDatabaseStatus {
+        external_change_error: app_data.state_database.external_change_error(),
+    }))
+}
@@ -1,9 +1,21 @@
+pub mod get_agent_desired_state_overrides;
 pub mod get_agents;
 pub mod get_agents_stream;
 pub mod get_balancer_desired_state;
+pub mod get_balancer_desired_state_revision_diff;
+pub mod get_balancer_desired_state_revisions;
 pub mod get_buffered_requests;
 pub mod get_buffered_requests_stream;
+pub mod get_canary;
 pub mod get_chat_template_override;
 pub mod get_model_metadata;
+pub mod get_services;
+pub mod get_state_database_status;
+pub mod patch_balancer_desired_state;
+pub mod post_balancer_desired_state_revision_rollback;
+pub mod post_balancer_desired_state_validate;
+pub mod post_canary_promote;
+pub mod post_canary_rollback;
+pub mod put_agent_desired_state_overrides;
 pub mod put_balancer_desired_state;
 pub mod ws_agent_socket;
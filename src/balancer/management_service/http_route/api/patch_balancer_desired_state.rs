@@ -0,0 +1,68 @@
+use actix_web::Error;
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorBadRequest;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::ETag;
+use actix_web::patch;
+use actix_web::web;
+use serde_json::Value;
+
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::revision_author::revision_author_from_http_request;
+use crate::balancer::management_service::revision_precondition::expected_revision_id_from_http_request;
+use crate::balancer::management_service::revision_precondition::revision_etag;
+use crate::balancer::management_service::revision_precondition::stored_revision_or_conflict;
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::json_merge_patch::apply_json_merge_patch;
+use crate::validates::Validates as _;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Applies a JSON merge patch (application/merge-patch+json) to the current desired state
+#[patch("/api/v1/balancer_desired_state")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    body: web::Bytes,
+    req: HttpRequest,
+) -> Result<impl Responder, Error> {
+    let expected_revision_id =
+        expected_revision_id_from_http_request(&req).map_err(ErrorBadRequest)?;
+    let patch: Value = serde_json::from_slice(&body).map_err(ErrorBadRequest)?;
+    let current_revision = app_data
+        .state_database
+        .read_current_balancer_desired_state_revision()
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    let mut balancer_desired_state = serde_json::to_value(&current_revision.balancer_desired_state)
+        .map_err(ErrorInternalServerError)?;
+
+    apply_json_merge_patch(&mut balancer_desired_state, &patch);
+
+    let patched_balancer_desired_state =
+        serde_json::from_value::<BalancerDesiredState>(balancer_desired_state)
+            .map_err(ErrorBadRequest)?
+            .validate()
+            .map_err(ErrorBadRequest)?;
+
+    // Patch is based on the revision read above, even if the client did not ask for a specific one
+    let revision = stored_revision_or_conflict(
+        app_data
+            .state_database
+            .store_balancer_desired_state(
+                &patched_balancer_desired_state,
+                revision_author_from_http_request(&req),
+                Some(expected_revision_id.unwrap_or(current_revision.id)),
+            )
+            .await
+            .map_err(ErrorInternalServerError)?,
+    )?;
+
+    Ok(HttpResponse::NoContent()
+        .insert_header(ETag(revision_etag(revision.id)))
+        .finish())
+}
@@ -0,0 +1,62 @@
+use actix_web::Error;
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorBadRequest;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::error::ErrorNotFound;
+use actix_web::post;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::revision_author::revision_author_from_http_request;
+use crate::balancer::management_service::revision_precondition::expected_revision_id_from_http_request;
+use crate::balancer::management_service::revision_precondition::stored_revision_or_conflict;
+use crate::validates::Validates as _;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    id: u64,
+}
+
+/// Stores the desired state of an older revision as the newest one, so the history is kept intact
+#[post("/api/v1/balancer_desired_state/revisions/{id}/rollback")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Path<PathParams>,
+    req: HttpRequest,
+) -> Result<impl Responder, Error> {
+    let expected_revision_id =
+        expected_revision_id_from_http_request(&req).map_err(ErrorBadRequest)?;
+    let revision = app_data
+        .state_database
+        .read_balancer_desired_state_revision(params.id)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound(format!("Revision {} does not exist", params.id)))?;
+
+    let balancer_desired_state = revision
+        .balancer_desired_state
+        .validate()
+        .map_err(ErrorBadRequest)?;
+
+    let new_revision = stored_revision_or_conflict(
+        app_data
+            .state_database
+            .store_balancer_desired_state(
+                &balancer_desired_state,
+                revision_author_from_http_request(&req),
+                expected_revision_id,
+            )
+            .await
+            .map_err(ErrorInternalServerError)?,
+    )?;
+
+    Ok(HttpResponse::Ok().json(new_revision))
+}
@@ -0,0 +1,32 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::post;
+use actix_web::web;
+use serde::Serialize;
+
+use crate::balancer::desired_state_problem::DesiredStateProblem;
+use crate::balancer::dry_run_desired_state::dry_run_desired_state;
+use crate::balancer_desired_state::BalancerDesiredState;
+
+#[derive(Serialize)]
+struct ValidationResult {
+    is_valid: bool,
+    problems: Vec<DesiredStateProblem>,
+}
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[post("/api/v1/balancer_desired_state/validate")]
+async fn respond(
+    balancer_desired_state: web::Json<BalancerDesiredState>,
+) -> Result<impl Responder, Error> {
+    let problems = dry_run_desired_state(&balancer_desired_state).await;
+
+    Ok(HttpResponse::Ok().json(ValidationResult {
+        is_valid: problems.is_empty(),
+        problems,
+    }))
+}
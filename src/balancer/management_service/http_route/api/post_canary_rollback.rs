@@ -0,0 +1,49 @@
+use actix_web::Error;
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorConflict;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::post;
+use actix_web::web;
+
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::revision_author::revision_author_from_http_request;
+use crate::balancer::management_service::revision_precondition::stored_revision_or_conflict;
+use crate::balancer_desired_state::BalancerDesiredState;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Drops the canary deployment, canary agents go back to the stable desired state
+#[post("/api/v1/canary/rollback")]
+async fn respond(app_data: web::Data<AppData>, req: HttpRequest) -> Result<impl Responder, Error> {
+    let current_revision = app_data
+        .state_database
+        .read_current_balancer_desired_state_revision()
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let desired_state = current_revision.balancer_desired_state;
+
+    if desired_state.canary.is_none() {
+        return Err(ErrorConflict("There is no canary deployment to roll back"));
+    }
+
+    stored_revision_or_conflict(
+        app_data
+            .state_database
+            .store_balancer_desired_state(
+                &BalancerDesiredState {
+                    canary: None,
+                    ..desired_state
+                },
+                revision_author_from_http_request(&req),
+                Some(current_revision.id),
+            )
+            .await
+            .map_err(ErrorInternalServerError)?,
+    )?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
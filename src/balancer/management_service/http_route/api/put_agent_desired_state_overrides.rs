@@ -0,0 +1,41 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorBadRequest;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::put;
+use actix_web::web;
+
+use crate::agent_desired_state_override::AgentDesiredStateOverride;
+use crate::balancer::management_service::app_data::AppData;
+use crate::validates::Validates as _;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[put("/api/v1/agent_desired_state_overrides")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    overrides: web::Json<Vec<AgentDesiredStateOverride>>,
+) -> Result<impl Responder, Error> {
+    let validated_overrides = overrides
+        .into_inner()
+        .into_iter()
+        .map(|desired_state_override| desired_state_override.validate())
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(ErrorBadRequest)?;
+
+    app_data
+        .state_database
+        .store_agent_desired_state_overrides(&validated_overrides)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    // Agents pick up the new overrides with the next rollout tick
+    app_data
+        .agent_controller_pool
+        .set_desired_state_overrides(validated_overrides);
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,12 +1,20 @@
 use actix_web::Error;
+use actix_web::HttpRequest;
 use actix_web::HttpResponse;
 use actix_web::Responder;
+use actix_web::error::ErrorBadRequest;
 use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::ETag;
 use actix_web::put;
 use actix_web::web;
 
 use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::revision_author::revision_author_from_http_request;
+use crate::balancer::management_service::revision_precondition::expected_revision_id_from_http_request;
+use crate::balancer::management_service::revision_precondition::revision_etag;
+use crate::balancer::management_service::revision_precondition::stored_revision_or_conflict;
 use crate::balancer_desired_state::BalancerDesiredState;
+use crate::validates::Validates as _;
 
 pub fn register(cfg: &mut web::ServiceConfig) {
     cfg.service(respond);
@@ -16,14 +24,28 @@ pub fn register(cfg: &mut web::ServiceConfig) {
 async fn respond(
     app_data: web::Data<AppData>,
     balancer_desired_state: web::Json<BalancerDesiredState>,
+    req: HttpRequest,
 ) -> Result<impl Responder, Error> {
-    let balancer_desired_state_inner = balancer_desired_state.into_inner();
+    let expected_revision_id =
+        expected_revision_id_from_http_request(&req).map_err(ErrorBadRequest)?;
+    let balancer_desired_state_inner = balancer_desired_state
+        .into_inner()
+        .validate()
+        .map_err(ErrorBadRequest)?;
 
-    app_data
-        .state_database
-        .store_balancer_desired_state(&balancer_desired_state_inner)
-        .await
-        .map_err(ErrorInternalServerError)?;
+    let revision = stored_revision_or_conflict(
+        app_data
+            .state_database
+            .store_balancer_desired_state(
+                &balancer_desired_state_inner,
+                revision_author_from_http_request(&req),
+                expected_revision_id,
+            )
+            .await
+            .map_err(ErrorInternalServerError)?,
+    )?;
 
-    Ok(HttpResponse::NoContent().finish())
+    Ok(HttpResponse::NoContent()
+        .insert_header(ETag(revision_etag(revision.id)))
+        .finish())
 }
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -6,6 +8,8 @@ use crate::slot_aggregated_status_snapshot::SlotAggregatedStatusSnapshot;
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct RegisterAgentParams {
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
     pub name: Option<String>,
     pub slot_aggregated_status_snapshot: SlotAggregatedStatusSnapshot,
 }
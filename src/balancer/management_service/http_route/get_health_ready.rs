@@ -0,0 +1,34 @@
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::get;
+use actix_web::web;
+
+use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::management_readiness::ManagementReadiness;
+use crate::service_health_registry::ServiceHealthRegistry;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Agents register through the management service, so it has to be ready
+/// before any agent serves requests. It only stops being ready while draining.
+#[get("/health/ready")]
+async fn respond(
+    buffered_request_manager: web::Data<BufferedRequestManager>,
+    service_health_registry: web::Data<ServiceHealthRegistry>,
+) -> impl Responder {
+    let is_draining = buffered_request_manager.request_drain.is_draining();
+    let is_healthy = service_health_registry.is_healthy();
+    let readiness = ManagementReadiness {
+        is_draining,
+        is_healthy,
+        is_ready: is_healthy && !is_draining,
+    };
+
+    if readiness.is_ready {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
+}
@@ -1,4 +1,7 @@
 use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Write as _;
 
 use actix_web::HttpResponse;
 use actix_web::Responder;
@@ -7,8 +10,15 @@ use actix_web::web::Data;
 use actix_web::web::ServiceConfig;
 use indoc::formatdoc;
 
+use crate::balancer::agent_controller_pool_snapshot::AgentControllerPoolSnapshot;
 use crate::balancer::agent_controller_pool_total_slots::AgentControllerPoolTotalSlots;
+use crate::balancer::agent_controller_snapshot::AgentControllerSnapshot;
+use crate::balancer::deployment_metrics_snapshot::DeploymentMetricsSnapshot;
+use crate::balancer::latency_histogram::LATENCY_HISTOGRAM_BUCKETS_MS;
+use crate::balancer::latency_histogram_snapshot::LatencyHistogramSnapshot;
 use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::request_metrics_snapshot::RequestMetricsSnapshot;
+use crate::produces_snapshot::ProducesSnapshot as _;
 
 pub fn register(cfg: &mut ServiceConfig) {
     cfg.service(respond);
@@ -24,9 +34,47 @@ async fn respond(app_data: Data<AppData>) -> Result<impl Responder, Box<dyn Erro
         .buffered_request_manager
         .buffered_request_counter
         .get();
+    let DeploymentMetricsSnapshot {
+        requests_failed: canary_requests_failed,
+        requests_succeeded: canary_requests_succeeded,
+        response_time_ms_total: canary_response_time_ms_total,
+    } = app_data
+        .agent_controller_pool
+        .canary_metrics
+        .make_snapshot()?;
+    let DeploymentMetricsSnapshot {
+        requests_failed: shadow_requests_failed,
+        requests_succeeded: shadow_requests_succeeded,
+        response_time_ms_total: shadow_response_time_ms_total,
+    } = app_data
+        .agent_controller_pool
+        .shadow_metrics
+        .make_snapshot()?;
+    let DeploymentMetricsSnapshot {
+        requests_failed: stable_requests_failed,
+        requests_succeeded: stable_requests_succeeded,
+        response_time_ms_total: stable_response_time_ms_total,
+    } = app_data
+        .agent_controller_pool
+        .stable_metrics
+        .make_snapshot()?;
+    let RequestMetricsSnapshot {
+        embeddings_generated,
+        inter_token_latency,
+        queue_wait_time,
+        request_duration,
+        requests,
+        time_to_first_token,
+        tokens_in,
+        tokens_out,
+    } = app_data
+        .buffered_request_manager
+        .request_metrics
+        .make_snapshot()?;
+    let AgentControllerPoolSnapshot { agents } = app_data.agent_controller_pool.make_snapshot()?;
     let statsd_prefix = app_data.statsd_prefix.clone();
 
-    let metrics_response = formatdoc! {"
+    let mut metrics_response = formatdoc! {"
         # HELP {statsd_prefix}slots_processing Number of processing slots
         # TYPE {statsd_prefix}slots_processing gauge
         {statsd_prefix}slots_processing {slots_processing}
@@ -38,9 +86,169 @@ async fn respond(app_data: Data<AppData>) -> Result<impl Responder, Box<dyn Erro
         # HELP {statsd_prefix}requests_buffered Number of buffered requests
         # TYPE {statsd_prefix}requests_buffered gauge
         {statsd_prefix}requests_buffered {buffered_requests_count}
+
+        # HELP {statsd_prefix}deployment_requests_succeeded Number of requests completed by the deployment agents
+        # TYPE {statsd_prefix}deployment_requests_succeeded counter
+        {statsd_prefix}deployment_requests_succeeded{{deployment=\"canary\"}} {canary_requests_succeeded}
+        {statsd_prefix}deployment_requests_succeeded{{deployment=\"shadow\"}} {shadow_requests_succeeded}
+        {statsd_prefix}deployment_requests_succeeded{{deployment=\"stable\"}} {stable_requests_succeeded}
+
+        # HELP {statsd_prefix}deployment_requests_failed Number of requests that timed out or lost their agent
+        # TYPE {statsd_prefix}deployment_requests_failed counter
+        {statsd_prefix}deployment_requests_failed{{deployment=\"canary\"}} {canary_requests_failed}
+        {statsd_prefix}deployment_requests_failed{{deployment=\"shadow\"}} {shadow_requests_failed}
+        {statsd_prefix}deployment_requests_failed{{deployment=\"stable\"}} {stable_requests_failed}
+
+        # HELP {statsd_prefix}deployment_response_time_ms_total Total time (in milliseconds) spent serving requests
+        # TYPE {statsd_prefix}deployment_response_time_ms_total counter
+        {statsd_prefix}deployment_response_time_ms_total{{deployment=\"canary\"}} {canary_response_time_ms_total}
+        {statsd_prefix}deployment_response_time_ms_total{{deployment=\"shadow\"}} {shadow_response_time_ms_total}
+        {statsd_prefix}deployment_response_time_ms_total{{deployment=\"stable\"}} {stable_response_time_ms_total}
+
+        # HELP {statsd_prefix}tokens_in Number of prompt tokens evaluated by the agents
+        # TYPE {statsd_prefix}tokens_in counter
+        {statsd_prefix}tokens_in {tokens_in}
+
+        # HELP {statsd_prefix}tokens_out Number of tokens generated by the agents
+        # TYPE {statsd_prefix}tokens_out counter
+        {statsd_prefix}tokens_out {tokens_out}
+
+        # HELP {statsd_prefix}embeddings_generated Number of embeddings generated by the agents
+        # TYPE {statsd_prefix}embeddings_generated counter
+        {statsd_prefix}embeddings_generated {embeddings_generated}
     "};
 
+    writeln!(metrics_response)?;
+    writeln!(
+        metrics_response,
+        "# HELP {statsd_prefix}requests Number of finished requests by endpoint and outcome"
+    )?;
+    writeln!(metrics_response, "# TYPE {statsd_prefix}requests counter")?;
+
+    for ((request_endpoint, request_outcome), requests_count) in requests {
+        writeln!(
+            metrics_response,
+            "{statsd_prefix}requests{{endpoint=\"{}\",outcome=\"{}\"}} {requests_count}",
+            request_endpoint.label(),
+            request_outcome.label()
+        )?;
+    }
+
+    for (name, help, latency_histogram_snapshot) in [
+        (
+            "queue_wait_time_seconds",
+            "Time requests spent waiting for a free slot",
+            queue_wait_time,
+        ),
+        (
+            "time_to_first_token_seconds",
+            "Time from receiving a request until its first generated token",
+            time_to_first_token,
+        ),
+        (
+            "inter_token_latency_seconds",
+            "Time between the consecutive generated tokens of a request",
+            inter_token_latency,
+        ),
+        (
+            "request_duration_seconds",
+            "Time from receiving a request until its last response",
+            request_duration,
+        ),
+    ] {
+        write_histogram(
+            &mut metrics_response,
+            &format!("{statsd_prefix}{name}"),
+            help,
+            &latency_histogram_snapshot,
+        )?;
+    }
+
+    write_agent_gauge(
+        &mut metrics_response,
+        &format!("{statsd_prefix}agent_slots_processing"),
+        "Number of processing slots of the agent",
+        &agents,
+        |agent| agent.slots_processing,
+    )?;
+    write_agent_gauge(
+        &mut metrics_response,
+        &format!("{statsd_prefix}agent_slots_total"),
+        "Number of total slots of the agent",
+        &agents,
+        |agent| agent.slots_total,
+    )?;
+    write_agent_gauge(
+        &mut metrics_response,
+        &format!("{statsd_prefix}agent_issues"),
+        "Number of issues reported by the agent",
+        &agents,
+        |agent| agent.issues.len(),
+    )?;
+
     Ok(HttpResponse::Ok()
         .content_type("text/plain; version=0.0.4; charset=utf-8; escaping=values")
         .body(metrics_response))
 }
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_agent_gauge<TValue: Display>(
+    metrics_response: &mut String,
+    name: &str,
+    help: &str,
+    agents: &[AgentControllerSnapshot],
+    agent_value: impl Fn(&AgentControllerSnapshot) -> TValue,
+) -> fmt::Result {
+    writeln!(metrics_response)?;
+    writeln!(metrics_response, "# HELP {name} {help}")?;
+    writeln!(metrics_response, "# TYPE {name} gauge")?;
+
+    for agent in agents {
+        writeln!(
+            metrics_response,
+            "{name}{{agent_id=\"{}\",agent_name=\"{}\"}} {}",
+            escape_label_value(&agent.id),
+            escape_label_value(agent.name.as_deref().unwrap_or_default()),
+            agent_value(agent)
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_histogram(
+    metrics_response: &mut String,
+    name: &str,
+    help: &str,
+    LatencyHistogramSnapshot {
+        count,
+        cumulative_counts,
+        sum_us,
+    }: &LatencyHistogramSnapshot,
+) -> fmt::Result {
+    writeln!(metrics_response)?;
+    writeln!(metrics_response, "# HELP {name} {help}")?;
+    writeln!(metrics_response, "# TYPE {name} histogram")?;
+
+    for (bound_ms, cumulative_count) in LATENCY_HISTOGRAM_BUCKETS_MS.iter().zip(cumulative_counts) {
+        writeln!(
+            metrics_response,
+            "{name}_bucket{{le=\"{}\"}} {cumulative_count}",
+            *bound_ms as f64 / 1_000.0
+        )?;
+    }
+
+    writeln!(metrics_response, "{name}_bucket{{le=\"+Inf\"}} {count}")?;
+    writeln!(
+        metrics_response,
+        "{name}_sum {}",
+        *sum_us as f64 / 1_000_000.0
+    )?;
+    writeln!(metrics_response, "{name}_count {count}")
+}
@@ -1,2 +1,3 @@
 pub mod api;
+pub mod get_health_ready;
 pub mod get_metrics;
@@ -1,6 +1,9 @@
 pub mod app_data;
+pub mod client_certificate;
 pub mod configuration;
 pub mod http_route;
+mod revision_author;
+mod revision_precondition;
 
 use std::sync::Arc;
 
@@ -10,37 +13,33 @@ use actix_web::web::Data;
 use anyhow::Result;
 use async_trait::async_trait;
 use log::error;
+use rustls::ServerConfig;
 use tokio::sync::broadcast;
 
 use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::agent_message_handler::AgentMessageHandler;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
-use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
-use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
-use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
 use crate::balancer::http_route as common_http_route;
 use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::client_certificate::ClientCertificate;
 use crate::balancer::management_service::configuration::Configuration as ManagementServiceConfiguration;
-use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
 use crate::balancer::state_database::StateDatabase;
-#[cfg(feature = "web_admin_panel")]
-use crate::balancer::web_admin_panel_service::configuration::Configuration as WebAdminPanelServiceConfiguration;
 use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
 use crate::create_cors_middleware::create_cors_middleware;
+use crate::listen_addr::ListenAddr;
 use crate::service::Service;
+use crate::service_health_registry::ServiceHealthRegistry;
+use crate::service_restart_policy::ServiceRestartPolicy;
 
 pub struct ManagementService {
     pub agent_controller_pool: Arc<AgentControllerPool>,
+    pub agent_message_handler: AgentMessageHandler,
     pub balancer_applicable_state_holder: Arc<BalancerApplicableStateHolder>,
     pub buffered_request_manager: Arc<BufferedRequestManager>,
-    pub chat_template_override_sender_collection: Arc<ChatTemplateOverrideSenderCollection>,
     pub configuration: ManagementServiceConfiguration,
-    pub embedding_sender_collection: Arc<EmbeddingSenderCollection>,
-    pub generate_tokens_sender_collection: Arc<GenerateTokensSenderCollection>,
-    pub model_metadata_sender_collection: Arc<ModelMetadataSenderCollection>,
+    pub service_health_registry: Arc<ServiceHealthRegistry>,
     pub state_database: Arc<dyn StateDatabase>,
     pub statsd_prefix: String,
-    #[cfg(feature = "web_admin_panel")]
-    pub web_admin_panel_service_configuration: Option<WebAdminPanelServiceConfiguration>,
 }
 
 #[async_trait]
@@ -49,56 +48,89 @@ impl Service for ManagementService {
         "balancer::management_service"
     }
 
-    async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        #[allow(unused_mut)]
-        let mut cors_allowed_hosts = self.configuration.cors_allowed_hosts.clone();
-
-        #[cfg(feature = "web_admin_panel")]
-        if let Some(web_admin_panel_config) = &self.web_admin_panel_service_configuration {
-            cors_allowed_hosts.push(format!("http://{}", web_admin_panel_config.addr));
-        }
+    fn restart_policy(&self) -> ServiceRestartPolicy {
+        ServiceRestartPolicy::Fatal
+    }
 
-        let cors_allowed_hosts_arc = Arc::new(cors_allowed_hosts);
+    async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let cors_allowed_hosts = self.configuration.cors_allowed_hosts.clone();
 
         let app_data = Data::new(AppData {
             agent_controller_pool: self.agent_controller_pool.clone(),
+            agent_message_handler: self.agent_message_handler.clone(),
             balancer_applicable_state_holder: self.balancer_applicable_state_holder.clone(),
             buffered_request_manager: self.buffered_request_manager.clone(),
             chat_template_override_sender_collection: self
+                .agent_message_handler
                 .chat_template_override_sender_collection
                 .clone(),
-            embedding_sender_collection: self.embedding_sender_collection.clone(),
-            generate_tokens_sender_collection: self.generate_tokens_sender_collection.clone(),
-            model_metadata_sender_collection: self.model_metadata_sender_collection.clone(),
+            model_metadata_sender_collection: self
+                .agent_message_handler
+                .model_metadata_sender_collection
+                .clone(),
+            requires_agent_certificate: self.configuration.requires_agent_certificate,
             state_database: self.state_database.clone(),
             statsd_prefix: self.statsd_prefix.clone(),
         });
 
-        HttpServer::new(move || {
+        let buffered_request_manager = Data::from(self.buffered_request_manager.clone());
+        let service_health_registry = Data::from(self.service_health_registry.clone());
+
+        let http_server = HttpServer::new(move || {
             App::new()
-                .wrap(create_cors_middleware(cors_allowed_hosts_arc.clone()))
+                .wrap(create_cors_middleware(cors_allowed_hosts.clone()))
                 .app_data(app_data.clone())
-                .configure(common_http_route::get_health::register)
+                .app_data(buffered_request_manager.clone())
+                .app_data(service_health_registry.clone())
+                .configure(common_http_route::get_health_live::register)
+                .configure(http_route::get_health_ready::register)
+                .configure(http_route::api::get_agent_desired_state_overrides::register)
                 .configure(http_route::api::get_agents::register)
                 .configure(http_route::api::get_agents_stream::register)
                 .configure(http_route::api::get_balancer_desired_state::register)
+                .configure(http_route::api::get_balancer_desired_state_revision_diff::register)
+                .configure(http_route::api::get_balancer_desired_state_revisions::register)
                 .configure(http_route::api::get_buffered_requests::register)
                 .configure(http_route::api::get_buffered_requests_stream::register)
+                .configure(http_route::api::get_canary::register)
                 .configure(http_route::api::get_chat_template_override::register)
                 .configure(http_route::api::get_model_metadata::register)
+                .configure(http_route::api::get_services::register)
+                .configure(http_route::api::get_state_database_status::register)
+                .configure(http_route::api::patch_balancer_desired_state::register)
+                .configure(http_route::api::post_balancer_desired_state_revision_rollback::register)
+                .configure(http_route::api::post_balancer_desired_state_validate::register)
+                .configure(http_route::api::post_canary_promote::register)
+                .configure(http_route::api::post_canary_rollback::register)
+                .configure(http_route::api::put_agent_desired_state_overrides::register)
                 .configure(http_route::api::put_balancer_desired_state::register)
                 .configure(http_route::api::ws_agent_socket::register)
                 .configure(http_route::get_metrics::register)
         })
+        .on_connect(ClientCertificate::store_in_connection_data)
         .shutdown_signal(async move {
             if let Err(err) = shutdown.recv().await {
                 error!("Failed to receive shutdown signal: {err}");
             }
-        })
-        .bind(self.configuration.addr)
-        .expect("Unable to bind server to address")
-        .run()
-        .await?;
+        });
+
+        let http_server = match (
+            &self.configuration.addr,
+            &self.configuration.tls_server_config,
+        ) {
+            (ListenAddr::Tcp(addr), Some(tls_server_config)) => {
+                http_server.bind_rustls_0_23(addr, ServerConfig::clone(tls_server_config))
+            }
+            (ListenAddr::Tcp(addr), None) => http_server.bind(addr),
+            (ListenAddr::Unix(path), _) => http_server.bind_uds(path),
+        }
+        .expect("Unable to bind server to address");
+
+        self.configuration
+            .addr
+            .set_unix_socket_mode(self.configuration.unix_socket_mode)?;
+
+        http_server.run().await?;
 
         Ok(())
     }
@@ -0,0 +1,40 @@
+use actix_web::HttpRequest;
+use actix_web::http::header;
+
+pub const AUTHOR_HEADER: &str = "X-Paddler-Author";
+
+/// Only the end of the API key is stored, enough to tell the keys apart
+const API_KEY_VISIBLE_SUFFIX_LENGTH: usize = 4;
+
+/// Identifies who changes the desired state, either by the explicit author header,
+/// or by the API key used to authorize the request
+pub fn revision_author_from_http_request(req: &HttpRequest) -> Option<String> {
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|header_value| header_value.to_str().ok())
+            .map(str::trim)
+            .filter(|header_value| !header_value.is_empty())
+    };
+
+    if let Some(author) = header(AUTHOR_HEADER) {
+        return Some(author.to_string());
+    }
+
+    header(header::AUTHORIZATION.as_str())
+        .and_then(|authorization| authorization.strip_prefix("Bearer "))
+        .map(|api_key| {
+            let api_key = api_key.trim();
+            let visible_suffix: String = api_key
+                .chars()
+                .skip(
+                    api_key
+                        .chars()
+                        .count()
+                        .saturating_sub(API_KEY_VISIBLE_SUFFIX_LENGTH),
+                )
+                .collect();
+
+            format!("api_key:...{visible_suffix}")
+        })
+}
@@ -0,0 +1,48 @@
+use actix_web::Error;
+use actix_web::HttpRequest;
+use actix_web::error::ErrorConflict;
+use actix_web::http::header;
+use actix_web::http::header::EntityTag;
+use actix_web::http::header::Header as _;
+use actix_web::http::header::IfMatch;
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+
+use crate::balancer::balancer_desired_state_revision::BalancerDesiredStateRevision;
+use crate::balancer::balancer_desired_state_store_result::BalancerDesiredStateStoreResult;
+
+pub fn revision_etag(revision_id: u64) -> EntityTag {
+    EntityTag::new_strong(revision_id.to_string())
+}
+
+/// Revision the client based its changes on, `None` if it does not care about concurrent changes
+pub fn expected_revision_id_from_http_request(req: &HttpRequest) -> Result<Option<u64>> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return Ok(None);
+    }
+
+    match IfMatch::parse(req)? {
+        IfMatch::Any => Ok(None),
+        IfMatch::Items(entity_tags) => match entity_tags.as_slice() {
+            [entity_tag] => Ok(Some(entity_tag.tag().parse().context(format!(
+                "If-Match does not contain a valid revision: {:?}",
+                entity_tag.tag()
+            ))?)),
+            _ => Err(anyhow!("If-Match has to contain exactly one revision")),
+        },
+    }
+}
+
+pub fn stored_revision_or_conflict(
+    store_result: BalancerDesiredStateStoreResult,
+) -> Result<BalancerDesiredStateRevision, Error> {
+    match store_result {
+        BalancerDesiredStateStoreResult::Conflict {
+            current_revision_id,
+        } => Err(ErrorConflict(format!(
+            "Balancer desired state was changed in the meantime, current revision is {current_revision_id}"
+        ))),
+        BalancerDesiredStateStoreResult::Stored(revision) => Ok(revision),
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use log::error;
+use log::warn;
+use nanoid::nanoid;
+use tokio::time::timeout;
+
+use crate::balancer::agent_controller::AgentController;
+use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::deployment::Deployment;
+use crate::balancer::handles_agent_streaming_response::HandlesAgentStreamingResponse;
+use crate::balancer::inference_client::Response as OutgoingResponse;
+use crate::balancer::manages_senders::ManagesSenders;
+use crate::balancer::request_outcome::RequestOutcome;
+use crate::balancer::shadow_comparison_output::ShadowComparisonOutput;
+use crate::streamable_result::StreamableResult;
+
+/// Sends a copy of the request to a shadow agent and collects its responses
+/// instead of forwarding them to the client.
+pub async fn mirror_request_to_shadow<TParams>(
+    agent_controller: Arc<AgentController>,
+    agent_controller_pool: Arc<AgentControllerPool>,
+    inference_item_timeout: Duration,
+    params: TParams,
+) -> ShadowComparisonOutput
+where
+    TParams: Send,
+    AgentController: HandlesAgentStreamingResponse<TParams>,
+    <<AgentController as HandlesAgentStreamingResponse<TParams>>::SenderCollection as ManagesSenders>::Value: Into<OutgoingResponse> + StreamableResult,
+{
+    let request_id: String = nanoid!();
+    let started_at = Instant::now();
+    let mut responses: Vec<serde_json::Value> = Vec::new();
+
+    let request_outcome = match agent_controller
+        .handle_streaming_response(request_id.clone(), params)
+        .await
+    {
+        Ok(mut receive_response_controller) => loop {
+            match timeout(
+                inference_item_timeout,
+                receive_response_controller.response_rx.recv(),
+            )
+            .await
+            {
+                Ok(Some(response)) => {
+                    let final_outcome = response
+                        .is_done()
+                        .then(|| RequestOutcome::from_final_response(&response));
+                    let response: OutgoingResponse = response.into();
+
+                    match serde_json::to_value(&response) {
+                        Ok(response) => responses.push(response),
+                        Err(err) => error!(
+                            "Failed to serialize shadow response for request {request_id:?}: {err}"
+                        ),
+                    }
+
+                    if let Some(final_outcome) = final_outcome {
+                        break final_outcome;
+                    }
+                }
+                Ok(None) => break RequestOutcome::Failed,
+                Err(_) => {
+                    warn!("Timed out waiting for shadow response for request {request_id:?}");
+
+                    agent_controller
+                        .stop_responding_to(request_id.clone())
+                        .await
+                        .unwrap_or_else(|err| {
+                            error!("Failed to stop responding to request {request_id:?}: {err}");
+                        });
+
+                    break RequestOutcome::TimedOut;
+                }
+            }
+        },
+        Err(err) => {
+            error!("Failed to mirror request {request_id:?} to shadow agent: {err}");
+
+            RequestOutcome::AgentDisconnected
+        }
+    };
+
+    let response_time = started_at.elapsed();
+
+    agent_controller_pool
+        .get_deployment_metrics(Deployment::Shadow)
+        .record_request(request_outcome == RequestOutcome::Completed, response_time);
+
+    ShadowComparisonOutput {
+        agent_id: agent_controller.id.clone(),
+        duration_ms: response_time.as_millis(),
+        outcome: request_outcome,
+        responses,
+    }
+}
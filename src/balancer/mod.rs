@@ -1,9 +1,14 @@
 mod agent_controller;
 pub mod agent_controller_pool;
+mod agent_controller_pool_serving_capacity;
 mod agent_controller_pool_snapshot;
 mod agent_controller_pool_total_slots;
 mod agent_controller_snapshot;
 mod agent_controller_update_result;
+pub mod agent_message_handler;
+pub mod balancer_desired_state_revision;
+pub mod balancer_desired_state_store_result;
+mod balancer_readiness;
 mod buffered_request_agent_wait_result;
 mod buffered_request_count_guard;
 mod buffered_request_counter;
@@ -13,24 +18,46 @@ pub mod chat_template_override_sender_collection;
 mod chunk_forwarding_session_controller;
 pub mod compatibility;
 mod controls_manages_senders_endpoint;
+mod deployment;
+mod deployment_metrics;
+mod deployment_metrics_snapshot;
+mod desired_state_change;
+mod desired_state_problem;
+mod dry_run_desired_state;
 pub mod embedding_sender_collection;
 pub mod generate_tokens_sender_collection;
 mod handles_agent_streaming_response;
 mod http_route;
 mod http_stream_from_agent;
+mod in_flight_request_guard;
 mod inference_client;
 pub mod inference_service;
+mod latency_histogram;
+mod latency_histogram_snapshot;
+mod management_readiness;
 pub mod management_service;
 mod manages_senders;
 mod manages_senders_controller;
+mod mirror_request_to_shadow;
 pub mod model_metadata_sender_collection;
 pub mod reconciliation_service;
+pub mod request_drain;
+mod request_endpoint;
 mod request_from_agent;
+mod request_metrics;
+mod request_metrics_snapshot;
+mod request_outcome;
+mod request_routing_hints;
 #[cfg(feature = "web_admin_panel")]
 mod response;
+mod shadow_comparison_entry;
+pub mod shadow_comparison_log;
+mod shadow_comparison_output;
 pub mod state_database;
+pub mod state_database_file_watch_service;
 pub mod state_database_type;
 pub mod statsd_service;
+mod sticky_key_roll;
 mod unbounded_stream_from_agent;
 #[cfg(feature = "web_admin_panel")]
 pub mod web_admin_panel_service;
@@ -21,6 +21,7 @@ pub struct ReconciliationService {
     pub balancer_desired_state: BalancerDesiredState,
     pub balancer_desired_state_rx: broadcast::Receiver<BalancerDesiredState>,
     pub is_converted_to_applicable_state: bool,
+    pub max_unavailable_agents: usize,
 }
 
 impl ReconciliationService {
@@ -31,13 +32,19 @@ impl ReconciliationService {
             self.agent_controller_pool
                 .set_desired_state(balancer_applicable_state.agent_desired_state.clone())
                 .await?;
+            self.agent_controller_pool
+                .set_canary(balancer_applicable_state.canary.clone());
+            self.agent_controller_pool
+                .set_shadow(balancer_applicable_state.shadow.clone());
             self.balancer_applicable_state_holder
                 .set_balancer_applicable_state(Some(balancer_applicable_state));
         }
 
         self.is_converted_to_applicable_state = true;
 
-        Ok(())
+        self.agent_controller_pool
+            .roll_out_desired_state(self.max_unavailable_agents)
+            .await
     }
 
     pub async fn try_convert_to_applicable_state(&mut self) {
@@ -64,6 +71,12 @@ impl Service for ReconciliationService {
                 _ = ticker.tick() => {
                     if !self.is_converted_to_applicable_state {
                         self.try_convert_to_applicable_state().await;
+                    } else if let Err(err) = self
+                        .agent_controller_pool
+                        .roll_out_desired_state(self.max_unavailable_agents)
+                        .await
+                    {
+                        error!("Failed to roll out desired state: {err}");
                     }
                 },
                 balancer_desired_state = self.balancer_desired_state_rx.recv() => {
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicI32;
+
+use async_trait::async_trait;
+use tokio::sync::Notify;
+
+use crate::atomic_value::AtomicValue;
+use crate::balancer::in_flight_request_guard::InFlightRequestGuard;
+use crate::drains::Drains;
+
+/// Keeps track of requests in progress, so the balancer can finish them before shutting down
+pub struct RequestDrain {
+    in_flight_requests: AtomicValue<AtomicI32>,
+    is_draining: AtomicValue<AtomicBool>,
+    update_notifier: Notify,
+}
+
+impl RequestDrain {
+    pub fn finish_request(&self) {
+        self.in_flight_requests.decrement();
+        self.update_notifier.notify_waiters();
+    }
+
+    pub fn get_in_flight_requests(&self) -> i32 {
+        self.in_flight_requests.get()
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.is_draining.get()
+    }
+
+    /// Returns None if the balancer is draining and should not take new requests
+    pub fn start_request(self: &Arc<Self>) -> Option<InFlightRequestGuard> {
+        if self.is_draining() {
+            return None;
+        }
+
+        self.in_flight_requests.increment();
+
+        Some(InFlightRequestGuard::new(self.clone()))
+    }
+}
+
+impl Default for RequestDrain {
+    fn default() -> Self {
+        Self {
+            in_flight_requests: AtomicValue::<AtomicI32>::new(0),
+            is_draining: AtomicValue::<AtomicBool>::new(false),
+            update_notifier: Notify::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Drains for RequestDrain {
+    async fn drain(&self) {
+        self.is_draining.set(true);
+
+        loop {
+            let notified = self.update_notifier.notified();
+
+            if self.in_flight_requests.get() < 1 {
+                break;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight_requests() {
+        let request_drain = Arc::new(RequestDrain::default());
+        let in_flight_request_guard = request_drain.start_request();
+
+        assert!(in_flight_request_guard.is_some());
+
+        let draining = {
+            let request_drain = request_drain.clone();
+
+            tokio::spawn(async move { request_drain.drain().await })
+        };
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert!(request_drain.is_draining());
+        assert!(request_drain.start_request().is_none());
+        assert!(!draining.is_finished());
+
+        drop(in_flight_request_guard);
+
+        assert!(
+            timeout(Duration::from_secs(1), draining).await.is_ok(),
+            "Drain should finish once the last request is done"
+        );
+    }
+}
@@ -0,0 +1,24 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RequestEndpoint {
+    ContinueFromConversationHistory,
+    ContinueFromRawPrompt,
+    GenerateEmbeddingBatch,
+}
+
+impl RequestEndpoint {
+    pub const ALL: [RequestEndpoint; 3] = [
+        RequestEndpoint::ContinueFromConversationHistory,
+        RequestEndpoint::ContinueFromRawPrompt,
+        RequestEndpoint::GenerateEmbeddingBatch,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RequestEndpoint::ContinueFromConversationHistory => {
+                "continue_from_conversation_history"
+            }
+            RequestEndpoint::ContinueFromRawPrompt => "continue_from_raw_prompt",
+            RequestEndpoint::GenerateEmbeddingBatch => "generate_embedding_batch",
+        }
+    }
+}
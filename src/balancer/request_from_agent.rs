@@ -1,10 +1,15 @@
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
+use actix_web::rt;
 use anyhow::Result;
 use log::debug;
 use log::error;
 use log::warn;
+use serde::Serialize;
 use tokio::sync::broadcast;
 use tokio::time::sleep;
 
@@ -18,7 +23,17 @@ use crate::balancer::inference_client::Response as OutgoingResponse;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
 use crate::balancer::manages_senders::ManagesSenders;
 use crate::balancer::manages_senders_controller::ManagesSendersController;
+use crate::balancer::mirror_request_to_shadow::mirror_request_to_shadow;
+use crate::balancer::request_endpoint::RequestEndpoint;
+use crate::balancer::request_metrics::RequestMetrics;
+use crate::balancer::request_outcome::RequestOutcome;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
+use crate::balancer::shadow_comparison_entry::ShadowComparisonEntry;
+use crate::balancer::shadow_comparison_output::ShadowComparisonOutput;
 use crate::controls_session::ControlsSession;
+use crate::embedding_result::EmbeddingResult;
+use crate::generated_token_result::GeneratedTokenResult;
+use crate::generation_summary::GenerationSummary;
 use crate::jsonrpc::Error as JsonRpcError;
 use crate::jsonrpc::ErrorEnvelope;
 use crate::jsonrpc::ResponseEnvelope;
@@ -30,23 +45,82 @@ pub async fn request_from_agent<TControlsSession, TParams>(
     inference_service_configuration: InferenceServiceConfiguration,
     params: TParams,
     request_id: String,
+    request_routing_hints: RequestRoutingHints,
     mut session_controller: TControlsSession,
 ) -> Result<()>
 where
     TControlsSession: ControlsSession<OutgoingMessage>,
-    TParams: Debug + Into<AgentJsonRpcRequest> + Send,
+    TParams: Clone + Debug + Into<AgentJsonRpcRequest> + Send + Serialize + 'static,
     AgentController: HandlesAgentStreamingResponse<TParams>,
     <<AgentController as HandlesAgentStreamingResponse<TParams>>::SenderCollection as ManagesSenders>::Value: Debug + Into<OutgoingResponse> + StreamableResult,
 {
+    let received_at = Instant::now();
+    let request_endpoint =
+        <AgentController as HandlesAgentStreamingResponse<TParams>>::REQUEST_ENDPOINT;
+    let _in_flight_request_guard = match buffered_request_manager.request_drain.start_request() {
+        Some(in_flight_request_guard) => in_flight_request_guard,
+        None => {
+            warn!("Balancer is shutting down, rejecting request: {request_id:?}");
+
+            buffered_request_manager
+                .request_metrics
+                .record_request(request_endpoint, RequestOutcome::ShuttingDown);
+
+            respond_with_error(
+                JsonRpcError {
+                    code: 503,
+                    description: "Balancer is shutting down".to_string(),
+                },
+                request_id,
+                &mut session_controller,
+            )
+            .await;
+
+            return Ok(());
+        }
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs();
+
     match wait_for_agent_controller(
         buffered_request_manager.clone(),
         connection_close_tx.subscribe(),
+        request_endpoint,
         request_id.clone(),
+        &request_routing_hints,
         &mut session_controller,
     )
     .await?
     {
         Some(agent_controller) => {
+            let agent_controller_pool = buffered_request_manager.agent_controller_pool.clone();
+            let deployment_metrics =
+                agent_controller_pool.get_deployment_metrics(agent_controller.get_deployment());
+            // Without the comparison log there is nothing to do with the shadow responses
+            let shadow_request = buffered_request_manager
+                .shadow_comparison_log
+                .clone()
+                .and_then(|shadow_comparison_log| {
+                    agent_controller_pool.take_shadow_agent_controller().map(
+                        |shadow_agent_controller| {
+                            (
+                                shadow_comparison_log,
+                                serde_json::to_value(&params).unwrap_or_default(),
+                                rt::spawn(mirror_request_to_shadow(
+                                    shadow_agent_controller,
+                                    agent_controller_pool.clone(),
+                                    inference_service_configuration.get_inference_item_timeout(),
+                                    params.clone(),
+                                )),
+                            )
+                        },
+                    )
+                });
+            let primary_agent_id = agent_controller.id.clone();
+            let mut primary_responses: Vec<serde_json::Value> = Vec::new();
+            let started_at = Instant::now();
             let receive_response_controller = match agent_controller
                 .handle_streaming_response(request_id.clone(), params)
                 .await
@@ -55,6 +129,11 @@ where
                 Err(err) => {
                     error!("Failed to handle request {request_id:?}: {err}");
 
+                    deployment_metrics.record_request(false, started_at.elapsed());
+                    buffered_request_manager
+                        .request_metrics
+                        .record_request(request_endpoint, RequestOutcome::Failed);
+
                     respond_with_error(
                         JsonRpcError {
                             code: 500,
@@ -69,15 +148,68 @@ where
                 }
             };
 
-            forward_responses_stream(
+            let request_outcome = forward_responses_stream(
                 agent_controller,
                 connection_close_tx.subscribe(),
                 inference_service_configuration,
+                received_at,
                 receive_response_controller,
-                request_id,
+                request_id.clone(),
+                &buffered_request_manager.request_metrics,
+                shadow_request.is_some().then_some(&mut primary_responses),
                 session_controller,
             )
             .await?;
+            let response_time = started_at.elapsed();
+
+            if request_outcome != RequestOutcome::ClientDisconnected {
+                deployment_metrics
+                    .record_request(request_outcome == RequestOutcome::Completed, response_time);
+            }
+
+            buffered_request_manager
+                .request_metrics
+                .record_request(request_endpoint, request_outcome);
+            buffered_request_manager
+                .request_metrics
+                .request_duration
+                .observe(received_at.elapsed());
+
+            if let Some((shadow_comparison_log, serialized_params, shadow_request_handle)) =
+                shadow_request
+            {
+                let primary = ShadowComparisonOutput {
+                    agent_id: primary_agent_id,
+                    duration_ms: response_time.as_millis(),
+                    outcome: request_outcome,
+                    responses: primary_responses,
+                };
+
+                // Shadow agent can still be generating, do not hold the client back
+                rt::spawn(async move {
+                    let shadow = match shadow_request_handle.await {
+                        Ok(shadow) => shadow,
+                        Err(err) => {
+                            error!("Shadow request for {request_id:?} failed: {err}");
+
+                            return;
+                        }
+                    };
+
+                    if let Err(err) = shadow_comparison_log
+                        .append(&ShadowComparisonEntry {
+                            params: serialized_params,
+                            primary,
+                            request_id: request_id.clone(),
+                            shadow,
+                            timestamp,
+                        })
+                        .await
+                    {
+                        error!("Failed to write shadow comparison for {request_id:?}: {err}");
+                    }
+                });
+            }
 
             Ok(())
         }
@@ -89,10 +221,13 @@ async fn forward_responses_stream<TControlsSession, TManagesSenders>(
     agent_controller: Arc<AgentController>,
     mut connection_close_rx: broadcast::Receiver<()>,
     inference_service_configuration: InferenceServiceConfiguration,
+    received_at: Instant,
     mut receive_response_controller: ManagesSendersController<TManagesSenders>,
     request_id: String,
+    request_metrics: &RequestMetrics,
+    mut recorded_responses: Option<&mut Vec<serde_json::Value>>,
     mut session_controller: TControlsSession,
-) -> Result<()>
+) -> Result<RequestOutcome>
 where
     TControlsSession: ControlsSession<OutgoingMessage>,
     TManagesSenders: ManagesSenders + Send + Sync,
@@ -102,8 +237,9 @@ where
 
     let mut agent_controller_connection_close_resubscribed =
         agent_controller.connection_close_rx.resubscribe();
+    let mut last_token_at: Option<Instant> = None;
 
-    loop {
+    let request_outcome = loop {
         tokio::select! {
             _ = agent_controller_connection_close_resubscribed.recv() => {
                 error!("Agent controller connection closed");
@@ -117,16 +253,16 @@ where
                     &mut session_controller,
                 ).await;
 
-                break;
+                break RequestOutcome::AgentDisconnected;
             }
             _ = connection_close_rx.recv() => {
                 agent_controller.stop_responding_to(request_id.clone()).await.unwrap_or_else(|err| {
                     error!("Failed to stop request {request_id:?}: {err}");
                 });
 
-                break;
+                break RequestOutcome::ClientDisconnected;
             }
-            _ = sleep(inference_service_configuration.inference_item_timeout) => {
+            _ = sleep(inference_service_configuration.get_inference_item_timeout()) => {
                 warn!("Timed out waiting for response for request {request_id:?}");
 
                 respond_with_error(
@@ -142,12 +278,26 @@ where
                     error!("Failed to stop responding to request {request_id:?}: {err}");
                 });
 
-                break;
+                break RequestOutcome::TimedOut;
             }
             response = receive_response_controller.response_rx.recv() => {
                 match response {
                     Some(response) => {
-                        let is_done = response.is_done();
+                        let final_outcome = response
+                            .is_done()
+                            .then(|| RequestOutcome::from_final_response(&response));
+                        let response: OutgoingResponse = response.into();
+
+                        record_response_metrics(
+                            &mut last_token_at,
+                            received_at,
+                            request_metrics,
+                            &response,
+                        );
+
+                        if let Some(recorded_responses) = recorded_responses.as_mut() {
+                            recorded_responses.push(serde_json::to_value(&response)?);
+                        }
 
                         send_response_to_client(
                             agent_controller.clone(),
@@ -156,17 +306,65 @@ where
                             &mut session_controller,
                         ).await;
 
-                        if is_done {
-                            break;
+                        if let Some(final_outcome) = final_outcome {
+                            break final_outcome;
                         }
                     }
-                    None => break,
+                    None => {
+                        error!(
+                            "Agent closed the response stream of request {request_id:?} before it was done"
+                        );
+
+                        respond_with_error(
+                            JsonRpcError {
+                                code: 502,
+                                description: "Agent closed the response stream".to_string(),
+                            },
+                            request_id,
+                            &mut session_controller,
+                        ).await;
+
+                        break RequestOutcome::Failed;
+                    }
                 }
             }
         }
-    }
+    };
 
-    Ok(())
+    Ok(request_outcome)
+}
+
+fn record_response_metrics(
+    last_token_at: &mut Option<Instant>,
+    received_at: Instant,
+    request_metrics: &RequestMetrics,
+    response: &OutgoingResponse,
+) {
+    match response {
+        OutgoingResponse::Embedding(EmbeddingResult::Embedding(_)) => {
+            request_metrics.embeddings_generated.increment_by(1);
+        }
+        OutgoingResponse::GeneratedToken(GeneratedTokenResult::Done(GenerationSummary {
+            prompt_tokens,
+            ..
+        })) => {
+            request_metrics.tokens_in.increment_by(*prompt_tokens);
+        }
+        OutgoingResponse::GeneratedToken(GeneratedTokenResult::Token(_)) => {
+            match last_token_at {
+                Some(last_token_at) => request_metrics
+                    .inter_token_latency
+                    .observe(last_token_at.elapsed()),
+                None => request_metrics
+                    .time_to_first_token
+                    .observe(received_at.elapsed()),
+            }
+
+            *last_token_at = Some(Instant::now());
+            request_metrics.tokens_out.increment_by(1);
+        }
+        _ => {}
+    }
 }
 
 async fn respond_with_error<TControlsSession>(
@@ -217,26 +415,33 @@ async fn send_response_to_client<TControlsSession, TResponse>(
 async fn wait_for_agent_controller<TControlsSession>(
     buffered_request_manager: Arc<BufferedRequestManager>,
     mut connection_close_rx: broadcast::Receiver<()>,
+    request_endpoint: RequestEndpoint,
     request_id: String,
+    request_routing_hints: &RequestRoutingHints,
     session_controller: &mut TControlsSession,
 ) -> Result<Option<Arc<AgentController>>>
 where
     TControlsSession: ControlsSession<OutgoingMessage>,
 {
-    let buffered_request_manager = buffered_request_manager.clone();
+    let request_metrics = &buffered_request_manager.request_metrics;
 
     tokio::select! {
         _ = connection_close_rx.recv() => {
             debug!("Connection close signal received, stopping GenerateTokens loop.");
 
+            request_metrics.record_request(request_endpoint, RequestOutcome::ClientDisconnected);
+
             Ok(None)
         },
-        buffered_request_agent_wait_result = buffered_request_manager.wait_for_available_agent() => {
+        buffered_request_agent_wait_result = buffered_request_manager.wait_for_available_agent(request_routing_hints) => {
             match buffered_request_agent_wait_result {
                 Ok(BufferedRequestAgentWaitResult::Found(agent_controller)) => Ok(Some(agent_controller)),
                 Ok(BufferedRequestAgentWaitResult::BufferOverflow) => {
                     warn!("Too many buffered requests, dropping request: {request_id:?}");
 
+                    request_metrics
+                        .record_request(request_endpoint, RequestOutcome::BufferOverflow);
+
                     respond_with_error(
                         JsonRpcError {
                             code: 503,
@@ -251,6 +456,8 @@ where
                 Ok(BufferedRequestAgentWaitResult::Timeout(err)) => {
                     warn!("Buffered request {request_id:?} timed out: {err:?}");
 
+                    request_metrics.record_request(request_endpoint, RequestOutcome::TimedOut);
+
                     respond_with_error(
                         JsonRpcError {
                             code: 504,
@@ -265,6 +472,8 @@ where
                 Err(err) => {
                     error!("Error while waiting for available agent controller for GenerateTokens request: {err}");
 
+                    request_metrics.record_request(request_endpoint, RequestOutcome::Failed);
+
                     respond_with_error(
                         JsonRpcError {
                             code: 500,
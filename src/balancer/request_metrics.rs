@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicUsize;
+
+use anyhow::Result;
+use dashmap::DashMap;
+
+use crate::atomic_value::AtomicValue;
+use crate::balancer::latency_histogram::LatencyHistogram;
+use crate::balancer::request_endpoint::RequestEndpoint;
+use crate::balancer::request_metrics_snapshot::RequestMetricsSnapshot;
+use crate::balancer::request_outcome::RequestOutcome;
+use crate::produces_snapshot::ProducesSnapshot;
+
+/// Latencies and throughput of the requests forwarded to the agents
+pub struct RequestMetrics {
+    pub embeddings_generated: AtomicValue<AtomicUsize>,
+    pub inter_token_latency: LatencyHistogram,
+    pub queue_wait_time: LatencyHistogram,
+    /// From receiving the request until its last response, for the requests that reached an agent
+    pub request_duration: LatencyHistogram,
+    requests: DashMap<(RequestEndpoint, RequestOutcome), AtomicValue<AtomicUsize>>,
+    /// From receiving the request, so it includes the queue wait time
+    pub time_to_first_token: LatencyHistogram,
+    pub tokens_in: AtomicValue<AtomicUsize>,
+    pub tokens_out: AtomicValue<AtomicUsize>,
+}
+
+impl RequestMetrics {
+    pub fn record_request(
+        &self,
+        request_endpoint: RequestEndpoint,
+        request_outcome: RequestOutcome,
+    ) {
+        if let Some(requests) = self.requests.get(&(request_endpoint, request_outcome)) {
+            requests.increment_by(1);
+        }
+    }
+}
+
+impl Default for RequestMetrics {
+    fn default() -> Self {
+        let requests = DashMap::new();
+
+        // Every series is exported from the start, even before the first request
+        for request_endpoint in RequestEndpoint::ALL {
+            for request_outcome in RequestOutcome::ALL {
+                requests.insert(
+                    (request_endpoint, request_outcome),
+                    AtomicValue::<AtomicUsize>::new(0),
+                );
+            }
+        }
+
+        Self {
+            embeddings_generated: AtomicValue::<AtomicUsize>::new(0),
+            inter_token_latency: LatencyHistogram::default(),
+            queue_wait_time: LatencyHistogram::default(),
+            request_duration: LatencyHistogram::default(),
+            requests,
+            time_to_first_token: LatencyHistogram::default(),
+            tokens_in: AtomicValue::<AtomicUsize>::new(0),
+            tokens_out: AtomicValue::<AtomicUsize>::new(0),
+        }
+    }
+}
+
+impl ProducesSnapshot for RequestMetrics {
+    type Snapshot = RequestMetricsSnapshot;
+
+    fn make_snapshot(&self) -> Result<Self::Snapshot> {
+        Ok(RequestMetricsSnapshot {
+            embeddings_generated: self.embeddings_generated.get(),
+            inter_token_latency: self.inter_token_latency.make_snapshot()?,
+            queue_wait_time: self.queue_wait_time.make_snapshot()?,
+            request_duration: self.request_duration.make_snapshot()?,
+            requests: self
+                .requests
+                .iter()
+                .map(|entry| (*entry.key(), entry.value().get()))
+                .collect::<BTreeMap<_, _>>(),
+            time_to_first_token: self.time_to_first_token.make_snapshot()?,
+            tokens_in: self.tokens_in.get(),
+            tokens_out: self.tokens_out.get(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated_token_result::GeneratedTokenResult;
+
+    #[test]
+    fn test_agent_error_is_counted_as_error_outcome() -> Result<()> {
+        let request_metrics = RequestMetrics::default();
+        let request_outcome = RequestOutcome::from_final_response(&GeneratedTokenResult::Error(
+            "Prompt does not fit in the context".to_string(),
+        ));
+
+        request_metrics.record_request(RequestEndpoint::ContinueFromRawPrompt, request_outcome);
+
+        let RequestMetricsSnapshot { requests, .. } = request_metrics.make_snapshot()?;
+
+        assert_eq!(request_outcome.label(), "error");
+        assert_eq!(
+            requests.get(&(
+                RequestEndpoint::ContinueFromRawPrompt,
+                RequestOutcome::Failed
+            )),
+            Some(&1)
+        );
+        assert_eq!(
+            requests.get(&(
+                RequestEndpoint::ContinueFromRawPrompt,
+                RequestOutcome::Completed
+            )),
+            Some(&0)
+        );
+
+        Ok(())
+    }
+}
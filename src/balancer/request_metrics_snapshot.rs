@@ -0,0 +1,17 @@
+use std::collections::BTreeMap;
+
+use crate::balancer::latency_histogram_snapshot::LatencyHistogramSnapshot;
+use crate::balancer::request_endpoint::RequestEndpoint;
+use crate::balancer::request_outcome::RequestOutcome;
+
+#[derive(Clone, Default)]
+pub struct RequestMetricsSnapshot {
+    pub embeddings_generated: usize,
+    pub inter_token_latency: LatencyHistogramSnapshot,
+    pub queue_wait_time: LatencyHistogramSnapshot,
+    pub request_duration: LatencyHistogramSnapshot,
+    pub requests: BTreeMap<(RequestEndpoint, RequestOutcome), usize>,
+    pub time_to_first_token: LatencyHistogramSnapshot,
+    pub tokens_in: usize,
+    pub tokens_out: usize,
+}
@@ -0,0 +1,48 @@
+use serde::Serialize;
+
+use crate::streamable_result::StreamableResult;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum RequestOutcome {
+    AgentDisconnected,
+    BufferOverflow,
+    ClientDisconnected,
+    Completed,
+    /// Balancer or agent failed to handle the request
+    Failed,
+    ShuttingDown,
+    TimedOut,
+}
+
+impl RequestOutcome {
+    pub const ALL: [RequestOutcome; 7] = [
+        RequestOutcome::AgentDisconnected,
+        RequestOutcome::BufferOverflow,
+        RequestOutcome::ClientDisconnected,
+        RequestOutcome::Completed,
+        RequestOutcome::Failed,
+        RequestOutcome::ShuttingDown,
+        RequestOutcome::TimedOut,
+    ];
+
+    /// Agent errors end the response stream as well, but they are not successes
+    pub fn from_final_response<TResponse: StreamableResult>(response: &TResponse) -> Self {
+        if response.is_error() {
+            RequestOutcome::Failed
+        } else {
+            RequestOutcome::Completed
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RequestOutcome::AgentDisconnected => "agent_disconnected",
+            RequestOutcome::BufferOverflow => "overflow",
+            RequestOutcome::ClientDisconnected => "client_disconnected",
+            RequestOutcome::Completed => "ok",
+            RequestOutcome::Failed => "error",
+            RequestOutcome::ShuttingDown => "shutting_down",
+            RequestOutcome::TimedOut => "timeout",
+        }
+    }
+}
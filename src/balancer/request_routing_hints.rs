@@ -0,0 +1,34 @@
+use actix_web::HttpRequest;
+use anyhow::Result;
+
+use crate::label_selector::LabelSelector;
+
+pub const AGENT_SELECTOR_HEADER: &str = "X-Paddler-Agent-Selector";
+pub const STICKY_KEY_HEADER: &str = "X-Paddler-Sticky-Key";
+
+/// Request details the agent controller pool can use to pick an agent
+#[derive(Clone, Debug, Default)]
+pub struct RequestRoutingHints {
+    /// Only agents with matching labels can handle the request
+    pub agent_selector: LabelSelector,
+    /// Requests with the same key stay on the same deployment if the canary is sticky
+    pub sticky_key: Option<String>,
+}
+
+impl RequestRoutingHints {
+    pub fn from_http_request(req: &HttpRequest) -> Result<Self> {
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|header_value| header_value.to_str().ok())
+        };
+
+        Ok(Self {
+            agent_selector: match header(AGENT_SELECTOR_HEADER) {
+                Some(agent_selector) => agent_selector.parse()?,
+                None => LabelSelector::default(),
+            },
+            sticky_key: header(STICKY_KEY_HEADER).map(str::to_string),
+        })
+    }
+}
@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+use crate::balancer::shadow_comparison_output::ShadowComparisonOutput;
+
+/// Single line of the shadow comparison log
+#[derive(Serialize)]
+pub struct ShadowComparisonEntry {
+    pub params: serde_json::Value,
+    pub primary: ShadowComparisonOutput,
+    pub request_id: String,
+    pub shadow: ShadowComparisonOutput,
+    /// Unix timestamp (in seconds) of when the request was received
+    pub timestamp: u64,
+}
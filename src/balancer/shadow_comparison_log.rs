@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt as _;
+use tokio::sync::Mutex;
+
+use crate::balancer::shadow_comparison_entry::ShadowComparisonEntry;
+
+/// Appends primary and shadow outputs of mirrored requests to a JSONL file
+pub struct ShadowComparisonLog {
+    path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl ShadowComparisonLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub async fn append(&self, entry: &ShadowComparisonEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+
+        line.push('\n');
+
+        let _lock = self.write_lock.lock().await;
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)
+            .await
+            .context(format!(
+                "Unable to open shadow comparison log: '{}'",
+                self.path.display()
+            ))?;
+
+        file.write_all(line.as_bytes()).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+use crate::balancer::request_outcome::RequestOutcome;
+
+#[derive(Serialize)]
+pub struct ShadowComparisonOutput {
+    pub agent_id: String,
+    pub duration_ms: u128,
+    pub outcome: RequestOutcome,
+    pub responses: Vec<serde_json::Value>,
+}
@@ -0,0 +1,8 @@
+use crate::agent_desired_state_override::AgentDesiredStateOverride;
+use crate::balancer_desired_state::BalancerDesiredState;
+
+/// Validated contents of a state file that was edited outside of the balancer
+pub struct ExternalChange {
+    pub agent_desired_state_overrides: Vec<AgentDesiredStateOverride>,
+    pub balancer_desired_state: BalancerDesiredState,
+}
@@ -1,6 +1,8 @@
+mod external_change;
 mod schema;
 
 use std::path::PathBuf;
+use std::sync::RwLock as SyncRwLock;
 
 use anyhow::Context;
 use anyhow::Result;
@@ -11,12 +13,26 @@ use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
 use tokio::sync::broadcast;
 
+pub use self::external_change::ExternalChange;
 use self::schema::Schema;
 use super::StateDatabase;
+use crate::agent_desired_state_override::AgentDesiredStateOverride;
+use crate::balancer::balancer_desired_state_revision::BalancerDesiredStateRevision;
+use crate::balancer::balancer_desired_state_store_result::BalancerDesiredStateStoreResult;
 use crate::balancer_desired_state::BalancerDesiredState;
+use crate::validates::Validates as _;
+
+/// Recorded as the author of the revisions that come from external edits of the file
+const EXTERNAL_CHANGE_AUTHOR: &str = "external change";
 
 pub struct File {
+    /// Last state written by the balancer or accepted from an external edit. Served and
+    /// updated instead of the file, so a rejected edit does not break the API.
+    accepted_schema: SyncRwLock<Option<Schema>>,
     balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+    external_change_error: SyncRwLock<Option<String>>,
+    /// Contents last written or loaded by the balancer, used to tell apart external edits
+    known_content: SyncRwLock<Option<String>>,
     path: PathBuf,
     write_lock: RwLock<()>,
 }
@@ -27,12 +43,144 @@ impl File {
         path: PathBuf,
     ) -> Self {
         File {
+            accepted_schema: SyncRwLock::new(None),
             balancer_desired_state_notify_tx,
+            external_change_error: SyncRwLock::new(None),
+            known_content: SyncRwLock::new(None),
             path,
             write_lock: RwLock::new(()),
         }
     }
 
+    /// Checks if the file was edited outside of the balancer (for example, deployed from a git
+    /// repository). Valid changes become a new revision and are broadcast as the new desired
+    /// state, invalid ones are kept as the external change error until the file changes again.
+    /// The last accepted state stays in use until then.
+    pub async fn load_external_change(&self) -> Result<Option<ExternalChange>> {
+        let _lock = self.write_lock.write().await;
+        let content = match fs::read_to_string(&self.path).await {
+            Ok(content) => content,
+            // Editors and deployment tools often replace the file, so it can be briefly missing
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        if content.is_empty() {
+            return Ok(None);
+        }
+
+        {
+            let mut known_content = self
+                .known_content
+                .write()
+                .expect("Poisoned lock on known state file content");
+
+            match known_content.as_ref() {
+                Some(known_content) if *known_content == content => return Ok(None),
+                Some(_) => {}
+                None => {
+                    // Initial contents were already loaded during the startup
+                    *known_content = Some(content);
+
+                    return Ok(None);
+                }
+            }
+
+            *known_content = Some(content.clone());
+        }
+
+        let known_revision_id = self
+            .accepted_schema
+            .read()
+            .expect("Poisoned lock on accepted state file schema")
+            .as_ref()
+            .map(|accepted_schema| accepted_schema.balancer_desired_state_revision)
+            .unwrap_or_default();
+
+        let mut schema = match self.validate_external_content(&content) {
+            Ok(schema) => schema,
+            Err(err) => {
+                self.set_external_change_error(Some(format!("{err:#}")));
+
+                return Err(err);
+            }
+        };
+
+        // Otherwise a client holding the previous revision could overwrite the external change
+        let revision = BalancerDesiredStateRevision::new(
+            Some(EXTERNAL_CHANGE_AUTHOR.to_string()),
+            schema.balancer_desired_state.clone(),
+            known_revision_id.max(schema.balancer_desired_state_revision) + 1,
+        );
+
+        schema.balancer_desired_state_author = revision.author;
+        schema.balancer_desired_state_created_at = revision.created_at;
+        schema.balancer_desired_state_revision = revision.id;
+
+        // Revision is kept only in memory, rewriting the file would leave the checkout it
+        // is deployed from dirty. The balancer writes it the next time the state is stored.
+        *self
+            .accepted_schema
+            .write()
+            .expect("Poisoned lock on accepted state file schema") = Some(schema.clone());
+        self.set_external_change_error(None);
+        self.balancer_desired_state_notify_tx
+            .send(schema.balancer_desired_state.clone())?;
+
+        Ok(Some(ExternalChange {
+            agent_desired_state_overrides: schema.agent_desired_state_overrides,
+            balancer_desired_state: schema.balancer_desired_state,
+        }))
+    }
+
+    fn set_external_change_error(&self, external_change_error: Option<String>) {
+        *self
+            .external_change_error
+            .write()
+            .expect("Poisoned lock on state file external change error") = external_change_error;
+    }
+
+    fn validate_external_content(&self, content: &str) -> Result<Schema> {
+        let schema: Schema = serde_json::from_str(content).context(format!(
+            "Unable to parse externally changed database file: '{}'",
+            self.path.display()
+        ))?;
+
+        Ok(Schema {
+            agent_desired_state_overrides: schema
+                .agent_desired_state_overrides
+                .into_iter()
+                .map(|desired_state_override| desired_state_override.validate())
+                .collect::<Result<Vec<_>>>()
+                .context("Invalid agent desired state override")?,
+            balancer_desired_state: schema
+                .balancer_desired_state
+                .validate()
+                .context("Invalid balancer desired state")?,
+            ..schema
+        })
+    }
+
+    async fn read_accepted_schema(&self) -> Result<Schema> {
+        if let Some(accepted_schema) = self
+            .accepted_schema
+            .read()
+            .expect("Poisoned lock on accepted state file schema")
+            .clone()
+        {
+            return Ok(accepted_schema);
+        }
+
+        let schema = self.read_schema_from_file().await?;
+
+        *self
+            .accepted_schema
+            .write()
+            .expect("Poisoned lock on accepted state file schema") = Some(schema.clone());
+
+        Ok(schema)
+    }
+
     async fn read_schema_from_file(&self) -> Result<Schema> {
         match fs::read_to_string(&self.path).await {
             Ok(content) => {
@@ -56,28 +204,42 @@ impl File {
         }
     }
 
+    /// Default state is not broadcast, since nothing was set for the agents yet
     async fn store_default_schema(&self) -> Result<Schema> {
         let schema = Schema::default();
 
-        self.store_schema(&schema)
+        self.write_schema(&schema)
             .await
             .context("Failed to store default state")?;
 
         Ok(schema)
     }
 
+    /// Callers that read the schema before storing it should hold the write lock
     async fn store_schema(&self, schema: &Schema) -> Result<()> {
-        let balancer_desired_state = schema.balancer_desired_state.clone();
-        let _lock = self.write_lock.write().await;
+        self.write_schema(schema).await?;
+        self.balancer_desired_state_notify_tx
+            .send(schema.balancer_desired_state.clone())?;
+
+        Ok(())
+    }
 
+    async fn write_schema(&self, schema: &Schema) -> Result<()> {
         let serialized_schema = serde_json::to_string_pretty(schema)?;
         let mut file = fs::File::create(&self.path).await?;
 
         file.write_all(serialized_schema.as_bytes()).await?;
         file.sync_all().await?;
 
-        self.balancer_desired_state_notify_tx
-            .send(balancer_desired_state)?;
+        *self
+            .accepted_schema
+            .write()
+            .expect("Poisoned lock on accepted state file schema") = Some(schema.clone());
+        *self
+            .known_content
+            .write()
+            .expect("Poisoned lock on known state file content") = Some(serialized_schema);
+        self.set_external_change_error(None);
 
         Ok(())
     }
@@ -86,8 +248,9 @@ impl File {
     where
         TModifier: FnOnce(&mut Schema),
     {
+        let _lock = self.write_lock.write().await;
         let mut schema = self
-            .read_schema_from_file()
+            .read_accepted_schema()
             .await
             .context("Unable to read current state from file")?;
 
@@ -97,24 +260,113 @@ impl File {
     }
 }
 
+fn current_revision(schema: Schema) -> BalancerDesiredStateRevision {
+    BalancerDesiredStateRevision {
+        author: schema.balancer_desired_state_author,
+        balancer_desired_state: schema.balancer_desired_state,
+        created_at: schema.balancer_desired_state_created_at,
+        id: schema.balancer_desired_state_revision,
+    }
+}
+
 #[async_trait]
 impl StateDatabase for File {
-    async fn read_balancer_desired_state(&self) -> Result<BalancerDesiredState> {
+    fn external_change_error(&self) -> Option<String> {
+        self.external_change_error
+            .read()
+            .expect("Poisoned lock on state file external change error")
+            .clone()
+    }
+
+    async fn list_balancer_desired_state_revisions(
+        &self,
+    ) -> Result<Vec<BalancerDesiredStateRevision>> {
+        let revision = current_revision(
+            self.read_accepted_schema()
+                .await
+                .context("Unable to read state from file")?,
+        );
+
+        Ok(if revision.id > 0 {
+            vec![revision]
+        } else {
+            Vec::new()
+        })
+    }
+
+    async fn read_agent_desired_state_overrides(&self) -> Result<Vec<AgentDesiredStateOverride>> {
         Ok(self
-            .read_schema_from_file()
+            .read_accepted_schema()
             .await
             .context("Unable to read state from file")?
-            .balancer_desired_state
-            .clone())
+            .agent_desired_state_overrides)
     }
 
-    async fn store_balancer_desired_state(
+    async fn read_balancer_desired_state_revision(
         &self,
-        balancer_desired_state: &BalancerDesiredState,
+        id: u64,
+    ) -> Result<Option<BalancerDesiredStateRevision>> {
+        let revision = current_revision(
+            self.read_accepted_schema()
+                .await
+                .context("Unable to read state from file")?,
+        );
+
+        Ok((revision.id > 0 && revision.id == id).then_some(revision))
+    }
+
+    async fn read_current_balancer_desired_state_revision(
+        &self,
+    ) -> Result<BalancerDesiredStateRevision> {
+        Ok(current_revision(
+            self.read_accepted_schema()
+                .await
+                .context("Unable to read state from file")?,
+        ))
+    }
+
+    async fn store_agent_desired_state_overrides(
+        &self,
+        overrides: &[AgentDesiredStateOverride],
     ) -> Result<()> {
         self.update_schema(|schema| {
-            schema.balancer_desired_state = balancer_desired_state.clone();
+            schema.agent_desired_state_overrides = overrides.to_vec();
         })
         .await
     }
+
+    async fn store_balancer_desired_state(
+        &self,
+        balancer_desired_state: &BalancerDesiredState,
+        author: Option<String>,
+        expected_revision_id: Option<u64>,
+    ) -> Result<BalancerDesiredStateStoreResult> {
+        let _lock = self.write_lock.write().await;
+        let mut schema = self
+            .read_accepted_schema()
+            .await
+            .context("Unable to read current state from file")?;
+        let current_revision_id = schema.balancer_desired_state_revision;
+
+        if expected_revision_id.is_some_and(|expected| expected != current_revision_id) {
+            return Ok(BalancerDesiredStateStoreResult::Conflict {
+                current_revision_id,
+            });
+        }
+
+        let revision = BalancerDesiredStateRevision::new(
+            author,
+            balancer_desired_state.clone(),
+            current_revision_id + 1,
+        );
+
+        schema.balancer_desired_state = revision.balancer_desired_state.clone();
+        schema.balancer_desired_state_author = revision.author.clone();
+        schema.balancer_desired_state_created_at = revision.created_at;
+        schema.balancer_desired_state_revision = revision.id;
+
+        self.store_schema(&schema).await?;
+
+        Ok(BalancerDesiredStateStoreResult::Stored(revision))
+    }
 }
@@ -1,16 +1,26 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::agent_desired_state_override::AgentDesiredStateOverride;
 use crate::balancer_desired_state::BalancerDesiredState;
 
 fn default_version() -> String {
     "1".into()
 }
 
-#[derive(Default, Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Schema {
+    #[serde(default)]
+    pub agent_desired_state_overrides: Vec<AgentDesiredStateOverride>,
     pub balancer_desired_state: BalancerDesiredState,
+    #[serde(default)]
+    pub balancer_desired_state_author: Option<String>,
+    #[serde(default)]
+    pub balancer_desired_state_created_at: u64,
+    /// File only keeps the latest revision, 0 means it was never stored
+    #[serde(default)]
+    pub balancer_desired_state_revision: u64,
     #[serde(default = "default_version")]
     pub version: String,
 }
@@ -5,44 +5,115 @@ use async_trait::async_trait;
 use tokio::sync::broadcast;
 
 use super::StateDatabase;
+use crate::agent_desired_state_override::AgentDesiredStateOverride;
+use crate::balancer::balancer_desired_state_revision::BalancerDesiredStateRevision;
+use crate::balancer::balancer_desired_state_store_result::BalancerDesiredStateStoreResult;
 use crate::balancer_desired_state::BalancerDesiredState;
 
 pub struct Memory {
-    balancer_desired_state: RwLock<BalancerDesiredState>,
+    agent_desired_state_overrides: RwLock<Vec<AgentDesiredStateOverride>>,
     balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+    balancer_desired_state_revisions: RwLock<Vec<BalancerDesiredStateRevision>>,
 }
 
 impl Memory {
     pub fn new(balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>) -> Self {
         Memory {
-            balancer_desired_state: RwLock::new(BalancerDesiredState::default()),
+            agent_desired_state_overrides: RwLock::new(Vec::new()),
             balancer_desired_state_notify_tx,
+            balancer_desired_state_revisions: RwLock::new(Vec::new()),
         }
     }
 }
 
 #[async_trait]
 impl StateDatabase for Memory {
-    async fn read_balancer_desired_state(&self) -> Result<BalancerDesiredState> {
+    async fn list_balancer_desired_state_revisions(
+        &self,
+    ) -> Result<Vec<BalancerDesiredStateRevision>> {
         Ok(self
-            .balancer_desired_state
+            .balancer_desired_state_revisions
             .read()
             .expect("Failed to acquire read lock")
             .clone())
     }
 
-    async fn store_balancer_desired_state(&self, state: &BalancerDesiredState) -> Result<()> {
-        {
-            let mut balancer_desired_state = self
-                .balancer_desired_state
+    async fn read_agent_desired_state_overrides(&self) -> Result<Vec<AgentDesiredStateOverride>> {
+        Ok(self
+            .agent_desired_state_overrides
+            .read()
+            .expect("Failed to acquire read lock")
+            .clone())
+    }
+
+    async fn read_balancer_desired_state_revision(
+        &self,
+        id: u64,
+    ) -> Result<Option<BalancerDesiredStateRevision>> {
+        Ok(self
+            .balancer_desired_state_revisions
+            .read()
+            .expect("Failed to acquire read lock")
+            .iter()
+            .find(|revision| revision.id == id)
+            .cloned())
+    }
+
+    async fn read_current_balancer_desired_state_revision(
+        &self,
+    ) -> Result<BalancerDesiredStateRevision> {
+        Ok(self
+            .balancer_desired_state_revisions
+            .read()
+            .expect("Failed to acquire read lock")
+            .last()
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn store_agent_desired_state_overrides(
+        &self,
+        overrides: &[AgentDesiredStateOverride],
+    ) -> Result<()> {
+        let mut agent_desired_state_overrides = self
+            .agent_desired_state_overrides
+            .write()
+            .expect("Failed to acquire write lock");
+
+        *agent_desired_state_overrides = overrides.to_vec();
+
+        Ok(())
+    }
+
+    async fn store_balancer_desired_state(
+        &self,
+        state: &BalancerDesiredState,
+        author: Option<String>,
+        expected_revision_id: Option<u64>,
+    ) -> Result<BalancerDesiredStateStoreResult> {
+        let revision = {
+            let mut balancer_desired_state_revisions = self
+                .balancer_desired_state_revisions
                 .write()
                 .expect("Failed to acquire write lock");
+            let current_revision_id = balancer_desired_state_revisions.len() as u64;
 
-            *balancer_desired_state = state.clone();
-        }
+            if expected_revision_id.is_some_and(|expected| expected != current_revision_id) {
+                return Ok(BalancerDesiredStateStoreResult::Conflict {
+                    current_revision_id,
+                });
+            }
+
+            let revision =
+                BalancerDesiredStateRevision::new(author, state.clone(), current_revision_id + 1);
+
+            balancer_desired_state_revisions.push(revision.clone());
+
+            revision
+        };
 
         self.balancer_desired_state_notify_tx.send(state.clone())?;
 
-        Ok(())
+        Ok(BalancerDesiredStateStoreResult::Stored(revision))
     }
 }
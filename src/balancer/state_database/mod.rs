@@ -1,18 +1,62 @@
 mod file;
 mod memory;
+mod sqlite;
 
 use anyhow::Result;
 use async_trait::async_trait;
 
+pub use self::file::ExternalChange;
 pub use self::file::File;
 pub use self::memory::Memory;
+pub use self::sqlite::Sqlite;
+use crate::agent_desired_state_override::AgentDesiredStateOverride;
+use crate::balancer::balancer_desired_state_revision::BalancerDesiredStateRevision;
+use crate::balancer::balancer_desired_state_store_result::BalancerDesiredStateStoreResult;
 use crate::balancer_desired_state::BalancerDesiredState;
 
 #[async_trait]
 pub trait StateDatabase: Send + Sync {
-    async fn read_balancer_desired_state(&self) -> Result<BalancerDesiredState>;
+    /// Why the last change made outside of the balancer could not be applied, if any
+    fn external_change_error(&self) -> Option<String> {
+        None
+    }
+
+    /// Stored revisions, oldest first. Databases without a history only return the current one.
+    async fn list_balancer_desired_state_revisions(
+        &self,
+    ) -> Result<Vec<BalancerDesiredStateRevision>>;
+
+    async fn read_agent_desired_state_overrides(&self) -> Result<Vec<AgentDesiredStateOverride>>;
+
+    async fn read_balancer_desired_state(&self) -> Result<BalancerDesiredState> {
+        Ok(self
+            .read_current_balancer_desired_state_revision()
+            .await?
+            .balancer_desired_state)
+    }
+
+    async fn read_balancer_desired_state_revision(
+        &self,
+        id: u64,
+    ) -> Result<Option<BalancerDesiredStateRevision>>;
+
+    async fn read_current_balancer_desired_state_revision(
+        &self,
+    ) -> Result<BalancerDesiredStateRevision>;
+
+    async fn store_agent_desired_state_overrides(
+        &self,
+        overrides: &[AgentDesiredStateOverride],
+    ) -> Result<()>;
 
-    async fn store_balancer_desired_state(&self, state: &BalancerDesiredState) -> Result<()>;
+    /// Stores the state only if the current revision matches the expected one (if given),
+    /// so concurrent writers do not overwrite each other
+    async fn store_balancer_desired_state(
+        &self,
+        state: &BalancerDesiredState,
+        author: Option<String>,
+        expected_revision_id: Option<u64>,
+    ) -> Result<BalancerDesiredStateStoreResult>;
 }
 
 #[cfg(test)]
@@ -23,17 +67,37 @@ mod tests {
 
     use super::*;
     use crate::agent_desired_model::AgentDesiredModel;
+    use crate::agent_override_target::AgentOverrideTarget;
     use crate::inference_parameters::InferenceParameters;
 
+    async fn store_unconditionally<TDatabase: StateDatabase>(
+        db: &TDatabase,
+        desired_state: &BalancerDesiredState,
+        author: Option<String>,
+    ) -> Result<BalancerDesiredStateRevision> {
+        match db
+            .store_balancer_desired_state(desired_state, author, None)
+            .await?
+        {
+            BalancerDesiredStateStoreResult::Conflict { .. } => {
+                panic!("Unconditional store should never conflict")
+            }
+            BalancerDesiredStateStoreResult::Stored(revision) => Ok(revision),
+        }
+    }
+
     async fn subtest_store_desired_state<TDatabase: StateDatabase>(db: &TDatabase) -> Result<()> {
         let desired_state = BalancerDesiredState {
+            canary: None,
             chat_template_override: None,
             inference_parameters: InferenceParameters::default(),
             model: AgentDesiredModel::LocalToAgent("test_model_path".to_string()),
+            shadow: None,
+            slots: None,
             use_chat_template_override: false,
         };
 
-        db.store_balancer_desired_state(&desired_state).await?;
+        store_unconditionally(db, &desired_state, None).await?;
 
         let read_state = db.read_balancer_desired_state().await?;
 
@@ -42,6 +106,217 @@ mod tests {
         Ok(())
     }
 
+    async fn subtest_balancer_desired_state_history<TDatabase: StateDatabase>(
+        db: &TDatabase,
+    ) -> Result<()> {
+        let first_revision = store_unconditionally(
+            db,
+            &BalancerDesiredState {
+                model: AgentDesiredModel::LocalToAgent("first_model_path".to_string()),
+                ..BalancerDesiredState::default()
+            },
+            Some("first_author".to_string()),
+        )
+        .await?;
+        let second_revision = store_unconditionally(
+            db,
+            &BalancerDesiredState {
+                model: AgentDesiredModel::LocalToAgent("second_model_path".to_string()),
+                ..BalancerDesiredState::default()
+            },
+            Some("second_author".to_string()),
+        )
+        .await?;
+
+        assert!(second_revision.id > first_revision.id);
+
+        let revisions = db.list_balancer_desired_state_revisions().await?;
+        let last_revision = revisions.last().expect("There should be stored revisions");
+
+        assert_eq!(last_revision.id, second_revision.id);
+        assert_eq!(last_revision.author, Some("second_author".to_string()));
+
+        let read_first_revision = db
+            .read_balancer_desired_state_revision(first_revision.id)
+            .await?
+            .expect("First revision should be kept");
+
+        assert_eq!(read_first_revision.author, Some("first_author".to_string()));
+        assert_eq!(
+            read_first_revision.balancer_desired_state.model,
+            AgentDesiredModel::LocalToAgent("first_model_path".to_string())
+        );
+        assert!(
+            db.read_balancer_desired_state_revision(second_revision.id + 1)
+                .await?
+                .is_none()
+        );
+
+        Ok(())
+    }
+
+    async fn subtest_store_desired_state_with_expected_revision<TDatabase: StateDatabase>(
+        db: &TDatabase,
+    ) -> Result<()> {
+        let current_revision = db.read_current_balancer_desired_state_revision().await?;
+        let stored_revision = match db
+            .store_balancer_desired_state(
+                &BalancerDesiredState::default(),
+                None,
+                Some(current_revision.id),
+            )
+            .await?
+        {
+            BalancerDesiredStateStoreResult::Conflict { .. } => {
+                panic!("Current revision should be accepted")
+            }
+            BalancerDesiredStateStoreResult::Stored(revision) => revision,
+        };
+
+        assert!(matches!(
+            db.store_balancer_desired_state(
+                &BalancerDesiredState::default(),
+                None,
+                Some(current_revision.id),
+            )
+            .await?,
+            BalancerDesiredStateStoreResult::Conflict {
+                current_revision_id
+            } if current_revision_id == stored_revision.id
+        ));
+        assert_eq!(
+            db.read_current_balancer_desired_state_revision().await?.id,
+            stored_revision.id
+        );
+
+        Ok(())
+    }
+
+    async fn subtest_store_agent_desired_state_overrides<TDatabase: StateDatabase>(
+        db: &TDatabase,
+    ) -> Result<()> {
+        assert!(db.read_agent_desired_state_overrides().await?.is_empty());
+
+        let overrides = vec![AgentDesiredStateOverride {
+            inference_parameters: serde_json::Map::from_iter([(
+                "context_size".to_string(),
+                serde_json::json!(2048),
+            )]),
+            model: None,
+            slots: None,
+            target: AgentOverrideTarget::Name("small_agent".to_string()),
+        }];
+
+        db.store_agent_desired_state_overrides(&overrides).await?;
+
+        let read_overrides = db.read_agent_desired_state_overrides().await?;
+
+        assert_eq!(read_overrides.len(), 1);
+        assert_eq!(read_overrides[0].target, overrides[0].target);
+        assert_eq!(
+            read_overrides[0].inference_parameters,
+            overrides[0].inference_parameters
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_database_external_change() -> Result<()> {
+        let (balancer_desired_state_tx, mut balancer_desired_state_rx) = broadcast::channel(100);
+        let tempfile = NamedTempFile::new()?;
+        let db = File::new(balancer_desired_state_tx, tempfile.path().to_path_buf());
+
+        store_unconditionally(&db, &BalancerDesiredState::default(), None).await?;
+        balancer_desired_state_rx.recv().await?;
+
+        assert!(db.load_external_change().await?.is_none());
+
+        let external_content = serde_json::to_string(&serde_json::json!({
+            "balancer_desired_state": {
+                "chat_template_override": null,
+                "inference_parameters": InferenceParameters::default(),
+                "model": {"LocalToAgent": "external_model_path"},
+                "use_chat_template_override": false,
+            },
+        }))?;
+
+        std::fs::write(tempfile.path(), &external_content)?;
+
+        let external_change = db
+            .load_external_change()
+            .await?
+            .expect("External change should be detected");
+
+        assert_eq!(
+            external_change.balancer_desired_state.model,
+            AgentDesiredModel::LocalToAgent("external_model_path".to_string())
+        );
+        assert_eq!(
+            balancer_desired_state_rx.recv().await?.model,
+            external_change.balancer_desired_state.model
+        );
+        assert!(db.load_external_change().await?.is_none());
+
+        let external_revision = db.read_current_balancer_desired_state_revision().await?;
+
+        assert_eq!(external_revision.id, 2);
+        assert_eq!(external_revision.author.as_deref(), Some("external change"));
+        assert_eq!(std::fs::read_to_string(tempfile.path())?, external_content);
+        assert!(matches!(
+            db.store_balancer_desired_state(&BalancerDesiredState::default(), None, Some(1))
+                .await?,
+            BalancerDesiredStateStoreResult::Conflict {
+                current_revision_id: 2
+            }
+        ));
+
+        std::fs::write(tempfile.path(), "{ not json")?;
+
+        assert!(db.load_external_change().await.is_err());
+        assert!(db.external_change_error().is_some());
+        assert!(db.load_external_change().await?.is_none());
+        assert_eq!(
+            db.read_current_balancer_desired_state_revision().await?.id,
+            2
+        );
+
+        std::fs::write(
+            tempfile.path(),
+            serde_json::to_string(&serde_json::json!({
+                "balancer_desired_state": {
+                    "chat_template_override": null,
+                    "inference_parameters": InferenceParameters {
+                        top_p: 1.5,
+                        ..InferenceParameters::default()
+                    },
+                    "model": {"LocalToAgent": "rejected_model_path"},
+                    "use_chat_template_override": false,
+                },
+            }))?,
+        )?;
+
+        assert!(db.load_external_change().await.is_err());
+        assert_eq!(
+            db.read_balancer_desired_state().await?.model,
+            AgentDesiredModel::LocalToAgent("external_model_path".to_string())
+        );
+
+        // Storing a state through the balancer repairs the file
+        assert!(matches!(
+            db.store_balancer_desired_state(&BalancerDesiredState::default(), None, Some(2))
+                .await?,
+            BalancerDesiredStateStoreResult::Stored(_)
+        ));
+        assert!(db.external_change_error().is_none());
+        assert!(
+            serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(tempfile.path())?)
+                .is_ok()
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_file_database() -> Result<()> {
         let (balancer_desired_state_tx, _balancer_desired_state_rx) = broadcast::channel(100);
@@ -49,6 +324,8 @@ mod tests {
         let db = File::new(balancer_desired_state_tx, tempfile.path().to_path_buf());
 
         subtest_store_desired_state(&db).await?;
+        subtest_store_agent_desired_state_overrides(&db).await?;
+        subtest_store_desired_state_with_expected_revision(&db).await?;
 
         Ok(())
     }
@@ -59,6 +336,23 @@ mod tests {
         let db = Memory::new(balancer_desired_state_tx);
 
         subtest_store_desired_state(&db).await?;
+        subtest_store_agent_desired_state_overrides(&db).await?;
+        subtest_store_desired_state_with_expected_revision(&db).await?;
+        subtest_balancer_desired_state_history(&db).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_database() -> Result<()> {
+        let (balancer_desired_state_tx, _balancer_desired_state_rx) = broadcast::channel(100);
+        let tempfile = NamedTempFile::new()?;
+        let db = Sqlite::new(balancer_desired_state_tx, tempfile.path().to_path_buf())?;
+
+        subtest_store_desired_state(&db).await?;
+        subtest_store_agent_desired_state_overrides(&db).await?;
+        subtest_store_desired_state_with_expected_revision(&db).await?;
+        subtest_balancer_desired_state_history(&db).await?;
 
         Ok(())
     }
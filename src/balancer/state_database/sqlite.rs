@@ -0,0 +1,250 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use async_trait::async_trait;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension as _;
+use rusqlite::TransactionBehavior;
+use rusqlite::params;
+use tokio::sync::broadcast;
+use tokio::task::spawn_blocking;
+
+use super::StateDatabase;
+use crate::agent_desired_state_override::AgentDesiredStateOverride;
+use crate::balancer::balancer_desired_state_revision::BalancerDesiredStateRevision;
+use crate::balancer::balancer_desired_state_store_result::BalancerDesiredStateStoreResult;
+use crate::balancer_desired_state::BalancerDesiredState;
+
+/// Other balancers can share the same database file, so writers wait for each other
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS agent_desired_state_overrides (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        overrides TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS balancer_desired_state_revisions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        author TEXT,
+        balancer_desired_state TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+";
+
+const SELECT_REVISIONS: &str = "
+    SELECT author, balancer_desired_state, created_at, id
+    FROM balancer_desired_state_revisions
+";
+
+type RevisionRow = (Option<String>, String, i64, i64);
+
+fn read_revision_row(row: &rusqlite::Row) -> rusqlite::Result<RevisionRow> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+}
+
+fn revision_from_row(
+    (author, balancer_desired_state, created_at, id): RevisionRow,
+) -> Result<BalancerDesiredStateRevision> {
+    Ok(BalancerDesiredStateRevision {
+        author,
+        balancer_desired_state: serde_json::from_str(&balancer_desired_state).context(format!(
+            "Unable to parse balancer desired state of revision {id}"
+        ))?,
+        created_at: created_at as u64,
+        id: id as u64,
+    })
+}
+
+pub struct Sqlite {
+    balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl Sqlite {
+    pub fn new(
+        balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+        path: PathBuf,
+    ) -> Result<Self> {
+        let connection = Connection::open(&path).context(format!(
+            "Unable to open SQLite state database: '{}'",
+            path.display()
+        ))?;
+
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection
+            .execute_batch(SCHEMA)
+            .context("Unable to create SQLite state database schema")?;
+
+        Ok(Sqlite {
+            balancer_desired_state_notify_tx,
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    async fn with_connection<TResult, TFunction>(&self, function: TFunction) -> Result<TResult>
+    where
+        TFunction: FnOnce(&mut Connection) -> Result<TResult> + Send + 'static,
+        TResult: Send + 'static,
+    {
+        let connection = self.connection.clone();
+
+        spawn_blocking(move || {
+            let mut connection = connection
+                .lock()
+                .expect("Poisoned lock on SQLite connection");
+
+            function(&mut connection)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl StateDatabase for Sqlite {
+    async fn list_balancer_desired_state_revisions(
+        &self,
+    ) -> Result<Vec<BalancerDesiredStateRevision>> {
+        self.with_connection(|connection| {
+            connection
+                .prepare(&format!("{SELECT_REVISIONS} ORDER BY id ASC"))?
+                .query_map([], read_revision_row)?
+                .map(|row| revision_from_row(row?))
+                .collect()
+        })
+        .await
+    }
+
+    async fn read_agent_desired_state_overrides(&self) -> Result<Vec<AgentDesiredStateOverride>> {
+        self.with_connection(|connection| {
+            match connection
+                .query_row(
+                    "SELECT overrides FROM agent_desired_state_overrides WHERE id = 1",
+                    [],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?
+            {
+                Some(overrides) => Ok(serde_json::from_str(&overrides)
+                    .context("Unable to parse agent desired state overrides")?),
+                None => Ok(Vec::new()),
+            }
+        })
+        .await
+    }
+
+    async fn read_balancer_desired_state_revision(
+        &self,
+        id: u64,
+    ) -> Result<Option<BalancerDesiredStateRevision>> {
+        self.with_connection(move |connection| {
+            connection
+                .query_row(
+                    &format!("{SELECT_REVISIONS} WHERE id = ?1"),
+                    params![id as i64],
+                    read_revision_row,
+                )
+                .optional()?
+                .map(revision_from_row)
+                .transpose()
+        })
+        .await
+    }
+
+    async fn read_current_balancer_desired_state_revision(
+        &self,
+    ) -> Result<BalancerDesiredStateRevision> {
+        self.with_connection(|connection| {
+            match connection
+                .query_row(
+                    &format!("{SELECT_REVISIONS} ORDER BY id DESC LIMIT 1"),
+                    [],
+                    read_revision_row,
+                )
+                .optional()?
+            {
+                Some(row) => revision_from_row(row),
+                None => Ok(BalancerDesiredStateRevision::default()),
+            }
+        })
+        .await
+    }
+
+    async fn store_agent_desired_state_overrides(
+        &self,
+        overrides: &[AgentDesiredStateOverride],
+    ) -> Result<()> {
+        let serialized_overrides = serde_json::to_string(overrides)?;
+
+        self.with_connection(move |connection| {
+            connection.execute(
+                "
+                    INSERT INTO agent_desired_state_overrides (id, overrides)
+                    VALUES (1, ?1)
+                    ON CONFLICT (id) DO UPDATE SET overrides = excluded.overrides
+                ",
+                params![serialized_overrides],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn store_balancer_desired_state(
+        &self,
+        state: &BalancerDesiredState,
+        author: Option<String>,
+        expected_revision_id: Option<u64>,
+    ) -> Result<BalancerDesiredStateStoreResult> {
+        let serialized_state = serde_json::to_string(state)?;
+        // Database assigns the id when the revision is inserted
+        let mut revision = BalancerDesiredStateRevision::new(author, state.clone(), 0);
+
+        let store_result = self
+            .with_connection(move |connection| {
+                let transaction =
+                    connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+                let current_revision_id = transaction.query_row(
+                    "SELECT COALESCE(MAX(id), 0) FROM balancer_desired_state_revisions",
+                    [],
+                    |row| row.get::<_, i64>(0),
+                )? as u64;
+
+                if expected_revision_id.is_some_and(|expected| expected != current_revision_id) {
+                    return Ok(BalancerDesiredStateStoreResult::Conflict {
+                        current_revision_id,
+                    });
+                }
+
+                transaction.execute(
+                    "
+                        INSERT INTO balancer_desired_state_revisions
+                            (author, balancer_desired_state, created_at)
+                        VALUES (?1, ?2, ?3)
+                    ",
+                    params![
+                        revision.author,
+                        serialized_state,
+                        revision.created_at as i64
+                    ],
+                )?;
+
+                revision.id = transaction.last_insert_rowid() as u64;
+
+                transaction.commit()?;
+
+                Ok(BalancerDesiredStateStoreResult::Stored(revision))
+            })
+            .await?;
+
+        if matches!(store_result, BalancerDesiredStateStoreResult::Stored(_)) {
+            self.balancer_desired_state_notify_tx.send(state.clone())?;
+        }
+
+        Ok(store_result)
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use log::error;
+use log::info;
+use tokio::sync::broadcast;
+use tokio::time::MissedTickBehavior;
+use tokio::time::interval;
+
+use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::state_database::ExternalChange;
+use crate::balancer::state_database::File;
+use crate::service::Service;
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Desired state itself is broadcast by the file, overrides have to be passed to the agents
+pub async fn apply_external_change(agent_controller_pool: &AgentControllerPool, file: &File) {
+    match file.load_external_change().await {
+        Ok(Some(ExternalChange {
+            agent_desired_state_overrides,
+            balancer_desired_state: _,
+        })) => {
+            info!("Applied desired state changed in the state database file");
+
+            agent_controller_pool.set_desired_state_overrides(agent_desired_state_overrides);
+        }
+        Ok(None) => {}
+        Err(err) => error!("Ignoring invalid state database file change: {err:#}"),
+    }
+}
+
+/// Picks up changes made to the state database file outside of the balancer,
+/// so it can be managed with tools like git without restarting the balancer
+pub struct StateDatabaseFileWatchService {
+    pub agent_controller_pool: Arc<AgentControllerPool>,
+    pub file: Arc<File>,
+}
+
+#[async_trait]
+impl Service for StateDatabaseFileWatchService {
+    fn name(&self) -> &'static str {
+        "balancer::state_database_file_watch_service"
+    }
+
+    async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let mut ticker = interval(WATCH_INTERVAL);
+
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break Ok(()),
+                _ = ticker.tick() => apply_external_change(&self.agent_controller_pool, &self.file).await,
+            }
+        }
+    }
+}
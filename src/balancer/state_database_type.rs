@@ -1,3 +1,4 @@
+use std::fmt;
 use std::path::Path;
 use std::path::PathBuf;
 use std::path::absolute;
@@ -13,6 +14,40 @@ use url::Url;
 pub enum StateDatabaseType {
     File(PathBuf),
     Memory,
+    Sqlite(PathBuf),
+}
+
+fn absolute_path_from_url(input: &str, scheme: &str) -> Result<PathBuf> {
+    let path = input
+        .strip_prefix(&format!("{scheme}://"))
+        .ok_or_else(|| anyhow!("Invalid {scheme} URL: {input}"))?
+        .trim();
+
+    if path.is_empty() {
+        return Err(anyhow!("File path cannot be empty"));
+    }
+
+    if !Path::new(path).is_absolute() {
+        let absolute_path = absolute(shellexpand::tilde(path).to_string())?;
+        let expanded_path = absolute_path.display();
+
+        return Err(anyhow!(formatdoc! {"
+            To avoid ambiguity, needing to guess the full file path (and to stay safe overall), Paddler requires absolute paths.
+            The path you wanted is *probably* '{expanded_path}'. If that is so, pass it as '--state-database {scheme}://{expanded_path}'.
+        "}));
+    }
+
+    Ok(PathBuf::from(path))
+}
+
+impl fmt::Display for StateDatabaseType {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateDatabaseType::File(path) => write!(formatter, "file://{}", path.display()),
+            StateDatabaseType::Memory => write!(formatter, "memory://"),
+            StateDatabaseType::Sqlite(path) => write!(formatter, "sqlite://{}", path.display()),
+        }
+    }
 }
 
 impl FromStr for StateDatabaseType {
@@ -22,29 +57,13 @@ impl FromStr for StateDatabaseType {
         let url = Url::parse(input)?;
 
         match url.scheme() {
-            "file" => {
-                let path = input
-                    .strip_prefix("file://")
-                    .ok_or_else(|| anyhow!("Invalid file URL: {input}"))?
-                    .trim();
-
-                if path.is_empty() {
-                    return Err(anyhow!("File path cannot be empty"));
-                }
-
-                if !Path::new(path).is_absolute() {
-                    let absolute_path = absolute(shellexpand::tilde(path).to_string())?;
-                    let expanded_path = absolute_path.display();
-
-                    return Err(anyhow!(formatdoc! {"
-                        To avoid ambiguity, needing to guess the full file path (and to stay safe overall), Paddler requires absolute paths.
-                        The path you wanted is *probably* '{expanded_path}'. If that is so, pass it as '--state-database file://{expanded_path}'.
-                    "}));
-                }
-
-                Ok(StateDatabaseType::File(PathBuf::from(path)))
-            }
+            "file" => Ok(StateDatabaseType::File(absolute_path_from_url(
+                input, "file",
+            )?)),
             "memory" => Ok(StateDatabaseType::Memory),
+            "sqlite" => Ok(StateDatabaseType::Sqlite(absolute_path_from_url(
+                input, "sqlite",
+            )?)),
             scheme => Err(anyhow!("Unsupported scheme '{scheme}'")),
         }
     }
@@ -87,6 +106,24 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_sqlite_absolute_path() {
+        let result = StateDatabaseType::from_str("sqlite:///absolute/paddler.db").unwrap();
+        match result {
+            StateDatabaseType::Sqlite(path) => {
+                assert_eq!(path, PathBuf::from("/absolute/paddler.db"));
+            }
+            _ => panic!("Expected Sqlite variant"),
+        }
+    }
+
+    #[test]
+    fn test_sqlite_relative_path() {
+        let result = StateDatabaseType::from_str("sqlite://paddler.db");
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_unsupported_scheme() {
         let result = StateDatabaseType::from_str("mysql://localhost/db");
@@ -1,7 +1,7 @@
 use std::net::SocketAddr;
 use std::time::Duration;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Configuration {
     pub statsd_addr: SocketAddr,
     pub statsd_prefix: String,
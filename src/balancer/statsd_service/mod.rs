@@ -5,39 +5,167 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use cadence::Counted;
 use cadence::Gauged;
 use cadence::StatsdClient;
 use cadence::UdpMetricSink;
 use log::error;
 use tokio::sync::broadcast;
+use tokio::sync::watch;
 use tokio::time::MissedTickBehavior;
 use tokio::time::interval;
 
 use crate::balancer::agent_controller_pool::AgentControllerPool;
 use crate::balancer::agent_controller_pool_total_slots::AgentControllerPoolTotalSlots;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::latency_histogram::LATENCY_HISTOGRAM_BUCKETS_MS;
+use crate::balancer::latency_histogram_snapshot::LatencyHistogramSnapshot;
+use crate::balancer::request_metrics_snapshot::RequestMetricsSnapshot;
 use crate::balancer::statsd_service::configuration::Configuration as StatsdServiceConfiguration;
+use crate::produces_snapshot::ProducesSnapshot as _;
 use crate::service::Service;
 
 pub struct StatsdService {
     pub agent_controller_pool: Arc<AgentControllerPool>,
     pub buffered_request_manager: Arc<BufferedRequestManager>,
-    pub configuration: StatsdServiceConfiguration,
+    /// Reporting is disabled while there is no configuration
+    pub configuration_rx: watch::Receiver<Option<StatsdServiceConfiguration>>,
 }
 
 impl StatsdService {
-    async fn report_metrics(&self, client: &StatsdClient) -> Result<()> {
+    /// Returns false if the service should stop
+    async fn report_until_changed(
+        &mut self,
+        configuration: StatsdServiceConfiguration,
+        reported_request_metrics: &mut RequestMetricsSnapshot,
+        shutdown: &mut broadcast::Receiver<()>,
+    ) -> Result<bool> {
+        let statsd_sink_socket = UdpSocket::bind("0.0.0.0:0")?;
+        let statsd_sink = UdpMetricSink::from(configuration.statsd_addr, statsd_sink_socket)?;
+
+        let client = StatsdClient::builder(&configuration.statsd_prefix, statsd_sink)
+            .with_error_handler(|err| error!("Statsd error: {err}"))
+            .build();
+
+        let mut ticker = interval(configuration.statsd_reporting_interval);
+
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break Ok(false),
+                changed = self.configuration_rx.changed() => break Ok(changed.is_ok()),
+                _ = ticker.tick() => {
+                    if let Err(err) = self.report_metrics(&client, reported_request_metrics).await {
+                        error!("Failed to report metrics: {err}");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Counters are sent as the increments since `reported_request_metrics`
+    async fn report_metrics(
+        &self,
+        client: &StatsdClient,
+        reported_request_metrics: &mut RequestMetricsSnapshot,
+    ) -> Result<()> {
         let AgentControllerPoolTotalSlots {
             slots_processing,
             slots_total,
         } = self.agent_controller_pool.total_slots();
         let requests_buffered = self.buffered_request_manager.buffered_request_counter.get();
+        let request_metrics = self
+            .buffered_request_manager
+            .request_metrics
+            .make_snapshot()?;
 
         client.gauge("slots_processing", slots_processing as u64)?;
         client.gauge("slots_total", slots_total as u64)?;
         client.gauge("requests_buffered", requests_buffered as u64)?;
+
+        for agent in self.agent_controller_pool.make_snapshot()?.agents {
+            let agent_name = agent.name.unwrap_or_default();
+
+            for (name, value) in [
+                ("agent_slots_processing", agent.slots_processing as u64),
+                ("agent_slots_total", agent.slots_total as u64),
+                ("agent_issues", agent.issues.len() as u64),
+            ] {
+                client
+                    .gauge_with_tags(name, value)
+                    .with_tag("agent_id", &agent.id)
+                    .with_tag("agent_name", &agent_name)
+                    .try_send()?;
+            }
+        }
+
+        for (name, current, reported) in [
+            (
+                "embeddings_generated",
+                request_metrics.embeddings_generated,
+                reported_request_metrics.embeddings_generated,
+            ),
+            (
+                "tokens_in",
+                request_metrics.tokens_in,
+                reported_request_metrics.tokens_in,
+            ),
+            (
+                "tokens_out",
+                request_metrics.tokens_out,
+                reported_request_metrics.tokens_out,
+            ),
+        ] {
+            client.count(name, counter_increment(current, reported))?;
+        }
+
+        for ((request_endpoint, request_outcome), requests_count) in &request_metrics.requests {
+            let reported_requests_count = reported_request_metrics
+                .requests
+                .get(&(*request_endpoint, *request_outcome))
+                .copied()
+                .unwrap_or_default();
+
+            client
+                .count_with_tags(
+                    "requests",
+                    counter_increment(*requests_count, reported_requests_count),
+                )
+                .with_tag("endpoint", request_endpoint.label())
+                .with_tag("outcome", request_outcome.label())
+                .try_send()?;
+        }
+
+        for (name, current, reported) in [
+            (
+                "queue_wait_time_ms",
+                &request_metrics.queue_wait_time,
+                &reported_request_metrics.queue_wait_time,
+            ),
+            (
+                "time_to_first_token_ms",
+                &request_metrics.time_to_first_token,
+                &reported_request_metrics.time_to_first_token,
+            ),
+            (
+                "inter_token_latency_ms",
+                &request_metrics.inter_token_latency,
+                &reported_request_metrics.inter_token_latency,
+            ),
+            (
+                "request_duration_ms",
+                &request_metrics.request_duration,
+                &reported_request_metrics.request_duration,
+            ),
+        ] {
+            report_latency_histogram(client, name, current, reported)?;
+        }
+
         client.flush()?;
 
+        *reported_request_metrics = request_metrics;
+
         Ok(())
     }
 }
@@ -49,27 +177,77 @@ impl Service for StatsdService {
     }
 
     async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        let statsd_sink_socket = UdpSocket::bind("0.0.0.0:0")?;
-        let statsd_sink = UdpMetricSink::from(self.configuration.statsd_addr, statsd_sink_socket)?;
-
-        let client =
-            StatsdClient::builder(&self.configuration.statsd_prefix.to_owned(), statsd_sink)
-                .with_error_handler(|err| error!("Statsd error: {err}"))
-                .build();
-
-        let mut ticker = interval(self.configuration.statsd_reporting_interval);
-
-        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut reported_request_metrics = RequestMetricsSnapshot::default();
 
         loop {
-            tokio::select! {
-                _ = shutdown.recv() => break Ok(()),
-                _ = ticker.tick() => {
-                    if let Err(err) = self.report_metrics(&client).await {
-                        error!("Failed to report metrics: {err}");
-                    }
+            let configuration = self.configuration_rx.borrow_and_update().clone();
+
+            let is_configuration_changed = match configuration {
+                Some(configuration) => {
+                    self.report_until_changed(
+                        configuration,
+                        &mut reported_request_metrics,
+                        &mut shutdown,
+                    )
+                    .await?
                 }
+                None => tokio::select! {
+                    _ = shutdown.recv() => false,
+                    changed = self.configuration_rx.changed() => changed.is_ok(),
+                },
+            };
+
+            if !is_configuration_changed {
+                break Ok(());
             }
         }
     }
 }
+
+fn counter_increment(current: usize, reported: usize) -> i64 {
+    current.saturating_sub(reported) as i64
+}
+
+/// Same buckets as the Prometheus histograms, with the bounds in milliseconds
+fn report_latency_histogram(
+    client: &StatsdClient,
+    name: &str,
+    current: &LatencyHistogramSnapshot,
+    reported: &LatencyHistogramSnapshot,
+) -> Result<()> {
+    for (bucket_index, bound_ms) in LATENCY_HISTOGRAM_BUCKETS_MS.iter().enumerate() {
+        let cumulative_count = |latency_histogram_snapshot: &LatencyHistogramSnapshot| {
+            latency_histogram_snapshot
+                .cumulative_counts
+                .get(bucket_index)
+                .copied()
+                .unwrap_or_default()
+        };
+
+        client
+            .count_with_tags(
+                &format!("{name}_bucket"),
+                counter_increment(cumulative_count(current), cumulative_count(reported)),
+            )
+            .with_tag("le", &bound_ms.to_string())
+            .try_send()?;
+    }
+
+    client
+        .count_with_tags(
+            &format!("{name}_bucket"),
+            counter_increment(current.count, reported.count),
+        )
+        .with_tag("le", "+Inf")
+        .try_send()?;
+    client.count(
+        &format!("{name}_count"),
+        counter_increment(current.count, reported.count),
+    )?;
+    client.count(
+        &format!("{name}_sum"),
+        counter_increment(current.sum_us / 1_000, reported.sum_us / 1_000),
+    )?;
+
+    Ok(())
+}
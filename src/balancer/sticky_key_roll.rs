@@ -0,0 +1,27 @@
+/// FNV-1a, so the same sticky key lands in the same deployment across
+/// balancer restarts, versions and replicas
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Number in the 0..100 range, to compare against the traffic percentage
+pub fn sticky_key_roll(sticky_key: &str) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+
+    for byte in sticky_key.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    hash % 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roll_is_stable() {
+        assert_eq!(sticky_key_roll(""), 37);
+        assert_eq!(sticky_key_roll("user-1"), 8);
+    }
+}
@@ -5,6 +5,7 @@ use actix_web::Error;
 use actix_web::rt;
 use log::error;
 use nanoid::nanoid;
+use serde::Serialize;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::UnboundedReceiverStream;
@@ -20,6 +21,7 @@ use crate::balancer::inference_client::Response as OutgoingResponse;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
 use crate::balancer::manages_senders::ManagesSenders;
 use crate::balancer::request_from_agent::request_from_agent;
+use crate::balancer::request_routing_hints::RequestRoutingHints;
 use crate::controls_session::ControlsSession as _;
 use crate::jsonrpc::Error as JsonRpcError;
 use crate::jsonrpc::ErrorEnvelope;
@@ -29,10 +31,11 @@ pub fn unbounded_stream_from_agent<TParams, TTransformsOutgoingMessage>(
     buffered_request_manager: Arc<BufferedRequestManager>,
     inference_service_configuration: InferenceServiceConfiguration,
     params: TParams,
+    request_routing_hints: RequestRoutingHints,
     transformer: TTransformsOutgoingMessage,
 ) -> Result<UnboundedReceiverStream<String>, Error>
 where
-    TParams: Debug + Into<AgentJsonRpcRequest> + Send + 'static,
+    TParams: Clone + Debug + Into<AgentJsonRpcRequest> + Send + Serialize + 'static,
     AgentController: HandlesAgentStreamingResponse<TParams>,
     <<AgentController as HandlesAgentStreamingResponse<TParams>>::SenderCollection as ManagesSenders>::Value: Debug + Into<OutgoingResponse> + StreamableResult,
     TTransformsOutgoingMessage: Clone + TransformsOutgoingMessage + Send + Sync + 'static,
@@ -50,6 +53,7 @@ where
             inference_service_configuration.clone(),
             params,
             request_id.clone(),
+            request_routing_hints,
             session_controller.clone(),
         )
         .await
@@ -1,4 +1,7 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
+
+use rustls::ServerConfig;
 
 use super::template_data::TemplateData;
 
@@ -6,4 +9,5 @@ use super::template_data::TemplateData;
 pub struct Configuration {
     pub addr: SocketAddr,
     pub template_data: TemplateData,
+    pub tls_server_config: Option<Arc<ServerConfig>>,
 }
@@ -9,11 +9,13 @@ use actix_web::web::Data;
 use anyhow::Result;
 use async_trait::async_trait;
 use log::error;
+use rustls::ServerConfig;
 use tokio::sync::broadcast;
 
 use crate::balancer::web_admin_panel_service::app_data::AppData;
 use crate::balancer::web_admin_panel_service::configuration::Configuration as WebAdminPanelServiceConfiguration;
 use crate::service::Service;
+use crate::service_restart_policy::ServiceRestartPolicy;
 
 pub struct WebAdminPanelService {
     pub configuration: WebAdminPanelServiceConfiguration,
@@ -25,12 +27,16 @@ impl Service for WebAdminPanelService {
         "balancer::web_admin_panel_service"
     }
 
+    fn restart_policy(&self) -> ServiceRestartPolicy {
+        ServiceRestartPolicy::Fatal
+    }
+
     async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
         let app_data: Data<AppData> = Data::new(AppData {
             template_data: self.configuration.template_data.clone(),
         });
 
-        HttpServer::new(move || {
+        let http_server = HttpServer::new(move || {
             App::new()
                 .app_data(app_data.clone())
                 .configure(http_route::favicon::register)
@@ -41,8 +47,15 @@ impl Service for WebAdminPanelService {
             if let Err(err) = shutdown.recv().await {
                 error!("Failed to receive shutdown signal: {err}");
             }
-        })
-        .bind(self.configuration.addr)
+        });
+
+        match &self.configuration.tls_server_config {
+            Some(tls_server_config) => http_server.bind_rustls_0_23(
+                self.configuration.addr,
+                ServerConfig::clone(tls_server_config),
+            ),
+            None => http_server.bind(self.configuration.addr),
+        }
         .expect("Unable to bind server to address")
         .run()
         .await?;
@@ -1,12 +1,14 @@
 use std::net::SocketAddr;
 use std::time::Duration;
 
+use crate::listen_addr::ListenAddr;
+
 #[derive(Clone)]
 pub struct TemplateData {
     pub buffered_request_timeout: Duration,
-    pub compat_openai_addr: Option<SocketAddr>,
-    pub inference_addr: SocketAddr,
-    pub management_addr: SocketAddr,
+    pub compat_openai_addr: Option<ListenAddr>,
+    pub inference_addr: ListenAddr,
+    pub management_addr: ListenAddr,
     pub max_buffered_requests: i32,
     pub statsd_addr: Option<SocketAddr>,
     pub statsd_prefix: String,
@@ -1,6 +1,10 @@
 use crate::agent_desired_state::AgentDesiredState;
+use crate::canary_applicable_state::CanaryApplicableState;
+use crate::shadow_applicable_state::ShadowApplicableState;
 
 #[derive(Clone, Debug)]
 pub struct BalancerApplicableState {
     pub agent_desired_state: AgentDesiredState,
+    pub canary: Option<CanaryApplicableState>,
+    pub shadow: Option<ShadowApplicableState>,
 }
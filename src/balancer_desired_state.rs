@@ -6,19 +6,47 @@ use serde::Serialize;
 use crate::agent_desired_model::AgentDesiredModel;
 use crate::agent_desired_state::AgentDesiredState;
 use crate::balancer_applicable_state::BalancerApplicableState;
+use crate::canary_applicable_state::CanaryApplicableState;
+use crate::canary_deployment::CanaryDeployment;
 use crate::chat_template::ChatTemplate;
 use crate::converts_to_applicable_state::ConvertsToApplicableState;
+use crate::desired_slots::DesiredSlots;
 use crate::inference_parameters::InferenceParameters;
+use crate::shadow_applicable_state::ShadowApplicableState;
+use crate::shadow_deployment::ShadowDeployment;
+use crate::validates::Validates;
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct BalancerDesiredState {
+    #[serde(default)]
+    pub canary: Option<CanaryDeployment>,
     pub chat_template_override: Option<ChatTemplate>,
     pub inference_parameters: InferenceParameters,
     pub model: AgentDesiredModel,
+    #[serde(default)]
+    pub shadow: Option<ShadowDeployment>,
+    /// Number of slots for every agent, agents fall back to their `--slots` flag if not set
+    #[serde(default)]
+    pub slots: Option<DesiredSlots>,
     pub use_chat_template_override: bool,
 }
 
+impl BalancerDesiredState {
+    fn to_agent_desired_state(&self) -> AgentDesiredState {
+        AgentDesiredState {
+            chat_template_override: if self.use_chat_template_override {
+                self.chat_template_override.clone()
+            } else {
+                None
+            },
+            inference_parameters: self.inference_parameters.clone(),
+            model: self.model.clone(),
+            slots: self.slots.clone(),
+        }
+    }
+}
+
 #[async_trait]
 impl ConvertsToApplicableState for BalancerDesiredState {
     type ApplicableState = BalancerApplicableState;
@@ -29,15 +57,47 @@ impl ConvertsToApplicableState for BalancerDesiredState {
         _context: Self::Context,
     ) -> Result<Option<Self::ApplicableState>> {
         Ok(Some(BalancerApplicableState {
-            agent_desired_state: AgentDesiredState {
-                chat_template_override: if self.use_chat_template_override {
-                    self.chat_template_override.clone()
-                } else {
-                    None
+            agent_desired_state: self.to_agent_desired_state(),
+            canary: self.canary.as_ref().map(
+                |CanaryDeployment {
+                     agent_selector,
+                     agents,
+                     desired_state,
+                     sticky,
+                     traffic_percentage,
+                 }| CanaryApplicableState {
+                    agent_selector: agent_selector.clone(),
+                    agent_desired_state: desired_state.to_agent_desired_state(),
+                    agents: *agents,
+                    sticky: *sticky,
+                    traffic_percentage: *traffic_percentage,
                 },
-                inference_parameters: self.inference_parameters.clone(),
-                model: self.model.clone(),
-            },
+            ),
+            shadow: self.shadow.as_ref().map(
+                |ShadowDeployment {
+                     agent_selector,
+                     agents,
+                     desired_state,
+                     mirror_percentage,
+                 }| ShadowApplicableState {
+                    agent_selector: agent_selector.clone(),
+                    agent_desired_state: desired_state.to_agent_desired_state(),
+                    agents: *agents,
+                    mirror_percentage: *mirror_percentage,
+                },
+            ),
         }))
     }
 }
+
+impl Validates<BalancerDesiredState> for BalancerDesiredState {
+    fn validate(self) -> Result<BalancerDesiredState> {
+        Ok(BalancerDesiredState {
+            canary: self.canary.map(|canary| canary.validate()).transpose()?,
+            inference_parameters: self.inference_parameters.validate()?,
+            shadow: self.shadow.map(|shadow| shadow.validate()).transpose()?,
+            slots: self.slots.map(|slots| slots.validate()).transpose()?,
+            ..self
+        })
+    }
+}
@@ -0,0 +1,11 @@
+use crate::agent_desired_state::AgentDesiredState;
+use crate::label_selector::LabelSelector;
+
+#[derive(Clone, Debug)]
+pub struct CanaryApplicableState {
+    pub agent_selector: LabelSelector,
+    pub agent_desired_state: AgentDesiredState,
+    pub agents: usize,
+    pub sticky: bool,
+    pub traffic_percentage: u8,
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::label_selector::LabelSelector;
+use crate::validates::Validates;
+
+/// Second version of the desired state, running on a subset of agents
+/// alongside the stable one until it is promoted or rolled back.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CanaryDeployment {
+    /// Only agents with matching labels can run the canary desired state
+    #[serde(default)]
+    pub agent_selector: LabelSelector,
+    /// How many agents should run the canary desired state
+    pub agents: usize,
+    pub desired_state: Box<BalancerDesiredState>,
+    /// Route requests with the same sticky key to the same deployment
+    pub sticky: bool,
+    /// Percentage (0-100) of requests routed to the canary agents
+    pub traffic_percentage: u8,
+}
+
+impl Validates<CanaryDeployment> for CanaryDeployment {
+    fn validate(self) -> Result<CanaryDeployment> {
+        if self.desired_state.canary.is_some() || self.desired_state.shadow.is_some() {
+            return Err(anyhow!(
+                "Canary deployment cannot have its own canary or shadow deployment"
+            ));
+        }
+
+        if self.traffic_percentage > 100 {
+            return Err(anyhow!(
+                "Canary traffic_percentage must be between 0 and 100"
+            ));
+        }
+
+        Ok(CanaryDeployment {
+            desired_state: Box::new(self.desired_state.validate()?),
+            ..self
+        })
+    }
+}
@@ -1,47 +1,203 @@
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::AtomicI32;
+use std::time::Duration;
 
+use anyhow::Context as _;
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
 use clap::Parser;
 use nanoid::nanoid;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 
+use super::agent_configuration::AgentConfiguration;
+use super::agent_configuration_file::AgentConfigurationFile;
+use super::configuration_file::ConfigurationFile;
 use super::handler::Handler;
+use super::parse_configuration_file_value;
+use super::parse_duration;
+use super::parse_management_addr;
 use super::parse_socket_addr;
+use crate::agent::balancer_connection_state_holder::BalancerConnectionStateHolder;
 use crate::agent::continue_from_conversation_history_request::ContinueFromConversationHistoryRequest;
 use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
 use crate::agent::generate_embedding_batch_request::GenerateEmbeddingBatchRequest;
+use crate::agent::health_service::HealthService;
+use crate::agent::in_process_balancer_client_service::InProcessBalancerClientService;
+use crate::agent::jsonrpc::notification_params::SetStateParams;
 use crate::agent::llamacpp_arbiter_service::LlamaCppArbiterService;
 use crate::agent::management_socket_client_service::ManagementSocketClientService;
 use crate::agent::model_metadata_holder::ModelMetadataHolder;
 use crate::agent::reconciliation_service::ReconciliationService;
+use crate::agent::slot_drain::SlotDrain;
 use crate::agent_applicable_state_holder::AgentApplicableStateHolder;
-use crate::agent_desired_state::AgentDesiredState;
+use crate::atomic_value::AtomicValue;
+use crate::balancer::agent_message_handler::AgentMessageHandler;
+use crate::label_selector::parse_label;
 use crate::service_manager::ServiceManager;
 use crate::slot_aggregated_status_manager::SlotAggregatedStatusManager;
 
 #[derive(Parser)]
 pub struct Agent {
-    #[arg(long, value_parser = parse_socket_addr)]
-    /// Address of the management server that the agent will connect to
-    management_addr: SocketAddr,
+    #[arg(long, env = "PADDLER_CONFIG")]
+    /// TOML configuration file; the agent reads its `[agent]` section.
+    /// Each setting is taken from the first place that has it: command line flags,
+    /// environment variables, the configuration file, and finally the defaults
+    config: Option<PathBuf>,
 
-    #[arg(long)]
+    #[arg(long, env = "PADDLER_AGENT_HEALTH_ADDR", value_parser = parse_socket_addr)]
+    /// Address of the optional HTTP server with the `/health/live` and `/health/ready` endpoints
+    /// (for example, for Kubernetes probes)
+    health_addr: Option<SocketAddr>,
+
+    #[arg(
+        long = "label",
+        env = "PADDLER_AGENT_LABELS",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append,
+        value_parser = parse_label
+    )]
+    /// Label in the key=value format, used to target agents by a label selector
+    /// (can be specified multiple times)
+    labels: Vec<(String, String)>,
+
+    #[arg(
+        long = "management-addr",
+        env = "PADDLER_AGENT_MANAGEMENT_ADDR",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append,
+        value_parser = parse_management_addr
+    )]
+    /// Address of the management server that the agent will connect to (required).
+    /// Can be specified multiple times; the agent fails over between the addresses
+    /// and every address the host names resolve to
+    management_addrs: Vec<String>,
+
+    #[arg(long, env = "PADDLER_AGENT_MANAGEMENT_CA_CERT")]
+    /// PEM file with the certificate authority of the balancer. If specified, the agent connects
+    /// over `wss://` and trusts only the certificates signed by it
+    management_ca_cert: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_AGENT_NAME")]
     /// Name of the agent (optional)
     name: Option<String>,
 
-    #[arg(long)]
-    /// Number of parallel requests of any kind that the agent can handle at once
-    slots: i32,
+    #[arg(long, env = "PADDLER_AGENT_SHUTDOWN_DRAIN_TIMEOUT", value_parser = parse_duration)]
+    /// How long (in milliseconds) to wait for the slots to finish their requests when shutting down
+    /// [default: 30000]
+    shutdown_drain_timeout: Option<Duration>,
+
+    #[arg(long, env = "PADDLER_AGENT_SLOTS")]
+    /// Number of parallel requests of any kind that the agent can handle at once,
+    /// used until the balancer desired state specifies the number of slots [default: 1]
+    slots: Option<i32>,
+
+    #[arg(long, env = "PADDLER_AGENT_STATE_CHANGE_DRAIN_TIMEOUT", value_parser = parse_duration)]
+    /// How long (in milliseconds) to wait for the in-flight requests to finish before the agent
+    /// applies a new desired state [default: 300000]
+    state_change_drain_timeout: Option<Duration>,
+
+    #[arg(long, env = "PADDLER_AGENT_TLS_CERT")]
+    /// PEM file with the client certificate chain that the agent presents to the balancer
+    /// (mutual TLS, requires --tls-key and --management-ca-cert)
+    tls_cert: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_AGENT_TLS_KEY")]
+    /// PEM file with the private key of the client certificate (requires --tls-cert)
+    tls_key: Option<PathBuf>,
 }
 
-#[async_trait]
-impl Handler for Agent {
-    async fn handle(&self, shutdown_rx: oneshot::Receiver<()>) -> Result<()> {
+impl Agent {
+    pub fn resolve_configuration(&self) -> Result<AgentConfiguration> {
+        let file = match &self.config {
+            Some(path) => ConfigurationFile::read(path)?.agent.ok_or_else(|| {
+                anyhow!(
+                    "Configuration file '{}' has no [agent] section",
+                    path.display()
+                )
+            })?,
+            None => AgentConfigurationFile::default(),
+        };
+        let management_ca_cert = self
+            .management_ca_cert
+            .clone()
+            .or(file.management_ca_cert.map(PathBuf::from));
+        let tls_cert = self.tls_cert.clone().or(file.tls_cert.map(PathBuf::from));
+        let tls_key = self.tls_key.clone().or(file.tls_key.map(PathBuf::from));
+
+        if tls_cert.is_some() != tls_key.is_some() {
+            return Err(anyhow!(
+                "Client certificate and key have to be specified together (use --tls-cert and --tls-key)"
+            ));
+        }
+
+        if tls_cert.is_some() && management_ca_cert.is_none() {
+            return Err(anyhow!(
+                "Client certificate requires the balancer certificate authority (use --management-ca-cert)"
+            ));
+        }
+
+        Ok(AgentConfiguration {
+            health_addr: match self.health_addr {
+                Some(health_addr) => Some(health_addr),
+                None => parse_configuration_file_value(
+                    "health_addr",
+                    file.health_addr.as_ref(),
+                    parse_socket_addr,
+                )?,
+            },
+            labels: if self.labels.is_empty() {
+                file.labels.unwrap_or_default()
+            } else {
+                self.labels.iter().cloned().collect::<BTreeMap<_, _>>()
+            },
+            management_addrs: if !self.management_addrs.is_empty() {
+                self.management_addrs.clone()
+            } else {
+                match file.management_addrs {
+                    Some(management_addrs) if !management_addrs.is_empty() => management_addrs
+                        .iter()
+                        .map(|management_addr| {
+                            parse_management_addr(management_addr).context(format!(
+                                "Invalid 'management_addrs' value in the configuration file: '{management_addr}'"
+                            ))
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                    _ => {
+                        return Err(anyhow!(
+                            "Management address is required (use --management-addr, PADDLER_AGENT_MANAGEMENT_ADDR, or the configuration file)"
+                        ));
+                    }
+                }
+            },
+            management_ca_cert,
+            name: self.name.clone().or(file.name),
+            shutdown_drain_timeout: self
+                .shutdown_drain_timeout
+                .or(file.shutdown_drain_timeout.map(Duration::from_millis))
+                .unwrap_or(Duration::from_millis(30000)),
+            slots: self.slots.or(file.slots).unwrap_or(1),
+            state_change_drain_timeout: self
+                .state_change_drain_timeout
+                .or(file.state_change_drain_timeout.map(Duration::from_millis))
+                .unwrap_or(Duration::from_millis(300000)),
+            tls_cert,
+            tls_key,
+        })
+    }
+
+    /// Without an in-process balancer, the agent connects to the management addresses
+    pub fn add_services(
+        configuration: &AgentConfiguration,
+        service_manager: &mut ServiceManager,
+        in_process_balancer: Option<AgentMessageHandler>,
+    ) -> Result<()> {
         let (agent_desired_state_tx, agent_desired_state_rx) =
-            mpsc::unbounded_channel::<AgentDesiredState>();
+            mpsc::unbounded_channel::<SetStateParams>();
         let (
             continue_from_conversation_history_request_tx,
             continue_from_conversation_history_request_rx,
@@ -52,41 +208,68 @@ impl Handler for Agent {
             mpsc::unbounded_channel::<GenerateEmbeddingBatchRequest>();
 
         let agent_applicable_state_holder = Arc::new(AgentApplicableStateHolder::default());
+        let balancer_connection_state_holder = Arc::new(BalancerConnectionStateHolder::default());
         let model_metadata_holder = Arc::new(ModelMetadataHolder::default());
-        let mut service_manager = ServiceManager::default();
-        let slot_aggregated_status_manager = Arc::new(SlotAggregatedStatusManager::new(self.slots));
+        let slot_aggregated_status_manager =
+            Arc::new(SlotAggregatedStatusManager::new(configuration.slots));
 
         service_manager.add_service(LlamaCppArbiterService {
             agent_applicable_state: None,
             agent_applicable_state_holder: agent_applicable_state_holder.clone(),
-            agent_name: self.name.clone(),
+            agent_name: configuration.name.clone(),
             continue_from_conversation_history_request_rx,
             continue_from_raw_prompt_request_rx,
-            desired_slots_total: self.slots,
+            desired_slots_total: configuration.slots,
+            drain_deadline: None,
             generate_embedding_batch_request_rx,
             llamacpp_arbiter_handle: None,
             model_metadata_holder: model_metadata_holder.clone(),
+            requests_in_flight: Arc::new(AtomicValue::<AtomicI32>::new(0)),
             slot_aggregated_status_manager: slot_aggregated_status_manager.clone(),
+            state_change_drain_timeout: configuration.state_change_drain_timeout,
         });
 
-        service_manager.add_service(ManagementSocketClientService {
-            agent_applicable_state_holder: agent_applicable_state_holder.clone(),
-            agent_desired_state_tx,
-            continue_from_conversation_history_request_tx,
-            continue_from_raw_prompt_request_tx,
-            generate_embedding_batch_request_tx,
-            model_metadata_holder,
-            name: self.name.clone(),
-            receive_stream_stopper_collection: Default::default(),
-            slot_aggregated_status: slot_aggregated_status_manager
-                .slot_aggregated_status
-                .clone(),
-            socket_url: format!(
-                "ws://{}/api/v1/agent_socket/{}",
-                self.management_addr,
-                nanoid!()
-            ),
-        });
+        match in_process_balancer {
+            Some(agent_message_handler) => {
+                service_manager.add_service(InProcessBalancerClientService {
+                    agent_applicable_state_holder: agent_applicable_state_holder.clone(),
+                    agent_desired_state_tx,
+                    agent_id: nanoid!(),
+                    agent_message_handler,
+                    balancer_connection_state_holder: balancer_connection_state_holder.clone(),
+                    continue_from_conversation_history_request_tx,
+                    continue_from_raw_prompt_request_tx,
+                    generate_embedding_batch_request_tx,
+                    labels: configuration.labels.clone(),
+                    model_metadata_holder,
+                    name: configuration.name.clone(),
+                    receive_stream_stopper_collection: Default::default(),
+                    slot_aggregated_status: slot_aggregated_status_manager
+                        .slot_aggregated_status
+                        .clone(),
+                });
+            }
+            None => {
+                service_manager.add_service(ManagementSocketClientService {
+                    agent_applicable_state_holder: agent_applicable_state_holder.clone(),
+                    agent_desired_state_tx,
+                    agent_id: nanoid!(),
+                    balancer_connection_state_holder: balancer_connection_state_holder.clone(),
+                    continue_from_conversation_history_request_tx,
+                    continue_from_raw_prompt_request_tx,
+                    generate_embedding_batch_request_tx,
+                    labels: configuration.labels.clone(),
+                    management_addrs: configuration.management_addrs.clone(),
+                    model_metadata_holder,
+                    name: configuration.name.clone(),
+                    receive_stream_stopper_collection: Default::default(),
+                    slot_aggregated_status: slot_aggregated_status_manager
+                        .slot_aggregated_status
+                        .clone(),
+                    tls_client_config: configuration.make_tls_client_config()?,
+                });
+            }
+        }
 
         service_manager.add_service(ReconciliationService {
             agent_applicable_state_holder,
@@ -98,6 +281,38 @@ impl Handler for Agent {
                 .clone(),
         });
 
+        if let Some(health_addr) = configuration.health_addr {
+            service_manager.add_service(HealthService {
+                addr: health_addr,
+                balancer_connection_state_holder,
+                service_health_registry: service_manager.get_service_health_registry(),
+                slot_aggregated_status: slot_aggregated_status_manager
+                    .slot_aggregated_status
+                    .clone(),
+            });
+        }
+
+        service_manager.add_drain(
+            Arc::new(SlotDrain {
+                slot_aggregated_status: slot_aggregated_status_manager
+                    .slot_aggregated_status
+                    .clone(),
+            }),
+            configuration.shutdown_drain_timeout,
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler for Agent {
+    async fn handle(&self, shutdown_rx: oneshot::Receiver<()>) -> Result<()> {
+        let configuration = self.resolve_configuration()?;
+        let mut service_manager = ServiceManager::default();
+
+        Self::add_services(&configuration, &mut service_manager, None)?;
+
         service_manager.run_forever(shutdown_rx).await
     }
 }
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use rustls::ClientConfig;
+
+use super::agent_configuration_file::AgentConfigurationFile;
+use crate::make_tls_client_config::make_tls_client_config;
+
+/// Agent settings after merging the command line flags, environment variables,
+/// configuration file, and defaults
+pub struct AgentConfiguration {
+    pub health_addr: Option<SocketAddr>,
+    pub labels: BTreeMap<String, String>,
+    pub management_addrs: Vec<String>,
+    pub management_ca_cert: Option<PathBuf>,
+    pub name: Option<String>,
+    pub shutdown_drain_timeout: Duration,
+    pub slots: i32,
+    pub state_change_drain_timeout: Duration,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+}
+
+impl AgentConfiguration {
+    /// Agent connects over `wss://` only if the balancer certificate authority is pinned
+    pub fn make_tls_client_config(&self) -> Result<Option<Arc<ClientConfig>>> {
+        let management_ca_cert = match &self.management_ca_cert {
+            Some(management_ca_cert) => management_ca_cert,
+            None => return Ok(None),
+        };
+        let client_certificate = match (&self.tls_cert, &self.tls_key) {
+            (Some(tls_cert), Some(tls_key)) => Some((tls_cert.as_path(), tls_key.as_path())),
+            _ => None,
+        };
+
+        Ok(Some(Arc::new(make_tls_client_config(
+            management_ca_cert,
+            client_certificate,
+        )?)))
+    }
+
+    /// Configuration file section that reproduces these settings
+    pub fn to_configuration_file(&self) -> AgentConfigurationFile {
+        AgentConfigurationFile {
+            health_addr: self.health_addr.map(|addr| addr.to_string()),
+            labels: Some(self.labels.clone()),
+            management_addrs: Some(self.management_addrs.clone()),
+            management_ca_cert: self
+                .management_ca_cert
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            name: self.name.clone(),
+            shutdown_drain_timeout: Some(self.shutdown_drain_timeout.as_millis() as u64),
+            slots: Some(self.slots),
+            state_change_drain_timeout: Some(self.state_change_drain_timeout.as_millis() as u64),
+            tls_cert: self
+                .tls_cert
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            tls_key: self.tls_key.as_ref().map(|path| path.display().to_string()),
+        }
+    }
+}
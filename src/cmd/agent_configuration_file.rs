@@ -0,0 +1,22 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// `[agent]` section of the configuration file. Keys are named after the command line flags.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentConfigurationFile {
+    pub health_addr: Option<String>,
+    pub labels: Option<BTreeMap<String, String>>,
+    pub management_addrs: Option<Vec<String>>,
+    pub management_ca_cert: Option<String>,
+    pub name: Option<String>,
+    /// Milliseconds
+    pub shutdown_drain_timeout: Option<u64>,
+    pub slots: Option<i32>,
+    /// Milliseconds
+    pub state_change_drain_timeout: Option<u64>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+}
@@ -1,191 +1,437 @@
 use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr as _;
 use std::sync::Arc;
 use std::time::Duration;
 
+use anyhow::Context as _;
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
 use clap::Parser;
+use log::info;
 use tokio::sync::broadcast;
 use tokio::sync::oneshot;
+use tokio::sync::watch;
 
+use super::balancer_configuration::BalancerConfiguration;
+use super::balancer_configuration_file::BalancerConfigurationFile;
+use super::balancer_configuration_reload_service::BalancerConfigurationReloadService;
+use super::configuration_file::ConfigurationFile;
 use super::handler::Handler;
+use super::parse_configuration_file_value;
 use super::parse_duration;
+use super::parse_listen_addr;
 use super::parse_socket_addr;
+use super::parse_unix_socket_mode;
 use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::agent_message_handler::AgentMessageHandler;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
 use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
 use crate::balancer::compatibility::openai_service::OpenAIService;
-use crate::balancer::compatibility::openai_service::configuration::Configuration as OpenAIServiceConfiguration;
 use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
 use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
 use crate::balancer::inference_service::InferenceService;
-use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
 use crate::balancer::management_service::ManagementService;
-use crate::balancer::management_service::configuration::Configuration as ManagementServiceConfiguration;
 use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
 use crate::balancer::reconciliation_service::ReconciliationService;
+use crate::balancer::shadow_comparison_log::ShadowComparisonLog;
 use crate::balancer::state_database::File;
 use crate::balancer::state_database::Memory;
+use crate::balancer::state_database::Sqlite;
 use crate::balancer::state_database::StateDatabase;
+use crate::balancer::state_database_file_watch_service::StateDatabaseFileWatchService;
 use crate::balancer::state_database_type::StateDatabaseType;
 use crate::balancer::statsd_service::StatsdService;
-use crate::balancer::statsd_service::configuration::Configuration as StatsdServiceConfiguration;
 #[cfg(feature = "web_admin_panel")]
 use crate::balancer::web_admin_panel_service::WebAdminPanelService;
-#[cfg(feature = "web_admin_panel")]
-use crate::balancer::web_admin_panel_service::configuration::Configuration as WebAdminPanelServiceConfiguration;
-#[cfg(feature = "web_admin_panel")]
-use crate::balancer::web_admin_panel_service::template_data::TemplateData;
 use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
+use crate::listen_addr::ListenAddr;
 use crate::service_manager::ServiceManager;
+use crate::validates::Validates as _;
 
-#[derive(Parser)]
+#[derive(Clone, Parser)]
 pub struct Balancer {
-    #[arg(long, default_value = "10000", value_parser = parse_duration)]
+    #[arg(long, env = "PADDLER_BALANCER_BUFFERED_REQUEST_TIMEOUT", value_parser = parse_duration)]
     /// Specifies how long a request can stay in the buffer before it is processed.
     /// If the request stays in the buffer longer than this time, it is rejected with the 504 error
-    buffered_request_timeout: Duration,
+    /// [default: 10000]
+    buffered_request_timeout: Option<Duration>,
+
+    #[arg(long, env = "PADDLER_BALANCER_COMPAT_OPENAI_ADDR", value_parser = parse_listen_addr)]
+    /// Address of the OpenAI-compatible API server (enabled only if this address is specified).
+    /// Accepts `unix:/path/to.sock` to listen on a Unix socket
+    compat_openai_addr: Option<ListenAddr>,
 
-    #[arg(long, value_parser = parse_socket_addr)]
-    /// Address of the OpenAI-compatible API server (enabled only if this address is specified)
-    compat_openai_addr: Option<SocketAddr>,
+    #[arg(long, env = "PADDLER_CONFIG")]
+    /// TOML configuration file; the balancer reads its `[balancer]` section.
+    /// Each setting is taken from the first place that has it: command line flags,
+    /// environment variables, the configuration file, and finally the defaults
+    config: Option<PathBuf>,
 
-    #[arg(long, default_value = "127.0.0.1:8061", value_parser = parse_socket_addr)]
-    /// Address of the inference server
-    inference_addr: SocketAddr,
+    #[arg(long, env = "PADDLER_BALANCER_INFERENCE_ADDR", value_parser = parse_listen_addr)]
+    /// Address of the inference server, or `unix:/path/to.sock` [default: 127.0.0.1:8061]
+    inference_addr: Option<ListenAddr>,
 
-    #[arg(long, default_value = "5000", value_parser = parse_duration)]
+    #[arg(long, env = "PADDLER_BALANCER_INFERENCE_ITEM_TIMEOUT", value_parser = parse_duration)]
     /// The timeout (in milliseconds) for generating a single token or a single embedding
-    inference_item_timeout: Duration,
+    /// [default: 5000]
+    inference_item_timeout: Option<Duration>,
 
     #[arg(
         long = "inference-cors-allowed-host",
+        env = "PADDLER_BALANCER_INFERENCE_CORS_ALLOWED_HOSTS",
+        value_delimiter = ',',
         action = clap::ArgAction::Append
     )]
     /// Allowed CORS host for the inference service (can be specified multiple times)
     inference_cors_allowed_hosts: Vec<String>,
 
-    #[arg(long, default_value = "127.0.0.1:8060", value_parser = parse_socket_addr)]
-    /// This is where you can manage your Paddler setup and the agents connect to
-    management_addr: SocketAddr,
+    #[arg(long, env = "PADDLER_BALANCER_MANAGEMENT_ADDR", value_parser = parse_listen_addr)]
+    /// This is where you can manage your Paddler setup and the agents connect to.
+    /// Accepts `unix:/path/to.sock` to listen on a Unix socket [default: 127.0.0.1:8060]
+    management_addr: Option<ListenAddr>,
 
     #[arg(
         long = "management-cors-allowed-host",
+        env = "PADDLER_BALANCER_MANAGEMENT_CORS_ALLOWED_HOSTS",
+        value_delimiter = ',',
         action = clap::ArgAction::Append
     )]
     /// Allowed CORS host for the management service (can be specified multiple times)
     management_cors_allowed_hosts: Vec<String>,
 
-    #[arg(long, default_value = "30")]
+    #[arg(long, env = "PADDLER_BALANCER_MAX_BUFFERED_REQUESTS")]
     /// The maximum number of buffered requests.
-    /// If the buffer is full then new requests are rejected with the 503 error
-    max_buffered_requests: i32,
+    /// If the buffer is full then new requests are rejected with the 503 error [default: 30]
+    max_buffered_requests: Option<i32>,
+
+    #[arg(long, env = "PADDLER_BALANCER_MAX_UNAVAILABLE_AGENTS")]
+    /// How many agents can be updated at the same time when the desired state changes.
+    /// Each of them drains its in-flight requests and stops serving until the new state is applied
+    /// [default: 1]
+    max_unavailable_agents: Option<usize>,
+
+    #[arg(long, env = "PADDLER_BALANCER_SHADOW_COMPARISON_LOG")]
+    /// JSONL file to append primary and shadow outputs of mirrored requests to
+    /// (requests are mirrored to the shadow deployment only if it is set)
+    shadow_comparison_log: Option<PathBuf>,
 
-    #[arg(long, default_value = "memory://")]
-    /// Balancer state database URL. Supported: memory, memory://, or file:///path (optional)
-    state_database: StateDatabaseType,
+    #[arg(long, env = "PADDLER_BALANCER_SHUTDOWN_DRAIN_TIMEOUT", value_parser = parse_duration)]
+    /// How long (in milliseconds) to wait for the requests in progress to finish when shutting down.
+    /// New requests are rejected in the meantime, and readiness checks fail [default: 30000]
+    shutdown_drain_timeout: Option<Duration>,
 
-    #[arg(long, value_parser = parse_socket_addr)]
+    #[arg(long, env = "PADDLER_BALANCER_STATE_DATABASE")]
+    /// Balancer state database URL. Supported: memory, memory://, file:///path, or sqlite:///path
+    /// (optional, file keeps only the latest desired state revision and applies changes made
+    /// to it outside of the balancer, sqlite keeps all of them) [default: memory://]
+    state_database: Option<StateDatabaseType>,
+
+    #[arg(long, env = "PADDLER_BALANCER_STATSD_ADDR", value_parser = parse_socket_addr)]
     /// Address for the statsd server to report metrics to (enabled only if this address is specified)
     statsd_addr: Option<SocketAddr>,
 
-    #[arg(long, default_value = "paddler_")]
-    /// Prefix for statsd metrics
-    statsd_prefix: String,
+    #[arg(long, env = "PADDLER_BALANCER_STATSD_PREFIX")]
+    /// Prefix for statsd metrics [default: paddler_]
+    statsd_prefix: Option<String>,
 
-    #[arg(long, default_value = "10000", value_parser = parse_duration)]
+    #[arg(long, env = "PADDLER_BALANCER_STATSD_REPORTING_INTERVAL", value_parser = parse_duration)]
     /// Interval (in milliseconds) at which the balancer will report metrics to statsd
-    statsd_reporting_interval: Duration,
+    /// [default: 10000]
+    statsd_reporting_interval: Option<Duration>,
+
+    #[arg(long, env = "PADDLER_BALANCER_TLS_AGENT_CA")]
+    /// PEM file with the certificate authority that signs the agent client certificates.
+    /// If specified, agents have to present a certificate signed by it to connect
+    /// (mutual TLS, requires --tls-cert)
+    tls_agent_ca: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_BALANCER_TLS_CERT")]
+    /// PEM file with the certificate chain to serve all of the balancer listeners over HTTPS
+    /// (requires --tls-key)
+    tls_cert: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_BALANCER_TLS_KEY")]
+    /// PEM file with the private key of the TLS certificate (requires --tls-cert)
+    tls_key: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_BALANCER_UNIX_SOCKET_MODE", value_parser = parse_unix_socket_mode)]
+    /// Octal file mode (for example, 660) of the Unix sockets that the balancer listens on
+    /// [default: keeps the mode set by the umask]
+    unix_socket_mode: Option<u32>,
 
-    #[arg(long, default_value = None, value_parser = parse_socket_addr)]
+    #[arg(long, env = "PADDLER_BALANCER_WEB_ADMIN_PANEL_ADDR", value_parser = parse_socket_addr)]
     /// Address of the web admin panel (enabled only if this address is specified)
     web_admin_panel_addr: Option<SocketAddr>,
 }
 
 impl Balancer {
-    fn get_management_service_configuration(&self) -> ManagementServiceConfiguration {
-        ManagementServiceConfiguration {
-            addr: self.management_addr,
-            cors_allowed_hosts: self.management_cors_allowed_hosts.clone(),
+    pub fn resolve_configuration(&self) -> Result<BalancerConfiguration> {
+        let file = match &self.config {
+            Some(path) => ConfigurationFile::read(path)?.balancer.ok_or_else(|| {
+                anyhow!(
+                    "Configuration file '{}' has no [balancer] section",
+                    path.display()
+                )
+            })?,
+            None => BalancerConfigurationFile::default(),
+        };
+        let tls_agent_ca = self
+            .tls_agent_ca
+            .clone()
+            .or(file.tls_agent_ca.map(PathBuf::from));
+        let tls_cert = self.tls_cert.clone().or(file.tls_cert.map(PathBuf::from));
+        let tls_key = self.tls_key.clone().or(file.tls_key.map(PathBuf::from));
+
+        if tls_cert.is_some() != tls_key.is_some() {
+            return Err(anyhow!(
+                "TLS certificate and key have to be specified together (use --tls-cert and --tls-key)"
+            ));
         }
-    }
 
-    fn get_inference_service_configuration(&self) -> InferenceServiceConfiguration {
-        InferenceServiceConfiguration {
-            addr: self.inference_addr,
-            cors_allowed_hosts: self.inference_cors_allowed_hosts.clone(),
-            inference_item_timeout: self.inference_item_timeout,
+        if tls_agent_ca.is_some() && tls_cert.is_none() {
+            return Err(anyhow!(
+                "Agent certificate authority requires the TLS certificate (use --tls-cert and --tls-key)"
+            ));
         }
+
+        Ok(BalancerConfiguration {
+            buffered_request_timeout: self
+                .buffered_request_timeout
+                .or(file.buffered_request_timeout.map(Duration::from_millis))
+                .unwrap_or(Duration::from_millis(10000)),
+            compat_openai_addr: match &self.compat_openai_addr {
+                Some(compat_openai_addr) => Some(compat_openai_addr.clone()),
+                None => parse_configuration_file_value(
+                    "compat_openai_addr",
+                    file.compat_openai_addr.as_ref(),
+                    parse_listen_addr,
+                )?,
+            },
+            inference_addr: match &self.inference_addr {
+                Some(inference_addr) => inference_addr.clone(),
+                None => parse_configuration_file_value(
+                    "inference_addr",
+                    file.inference_addr.as_ref(),
+                    parse_listen_addr,
+                )?
+                .unwrap_or(ListenAddr::Tcp(SocketAddr::from(([127, 0, 0, 1], 8061)))),
+            },
+            inference_cors_allowed_hosts: if self.inference_cors_allowed_hosts.is_empty() {
+                file.inference_cors_allowed_hosts.unwrap_or_default()
+            } else {
+                self.inference_cors_allowed_hosts.clone()
+            },
+            inference_item_timeout: self
+                .inference_item_timeout
+                .or(file.inference_item_timeout.map(Duration::from_millis))
+                .unwrap_or(Duration::from_millis(5000)),
+            initial_desired_state: file
+                .initial_desired_state
+                .map(|initial_desired_state| initial_desired_state.validate())
+                .transpose()
+                .context("Invalid initial_desired_state in the configuration file")?,
+            management_addr: match &self.management_addr {
+                Some(management_addr) => management_addr.clone(),
+                None => parse_configuration_file_value(
+                    "management_addr",
+                    file.management_addr.as_ref(),
+                    parse_listen_addr,
+                )?
+                .unwrap_or(ListenAddr::Tcp(SocketAddr::from(([127, 0, 0, 1], 8060)))),
+            },
+            management_cors_allowed_hosts: if self.management_cors_allowed_hosts.is_empty() {
+                file.management_cors_allowed_hosts.unwrap_or_default()
+            } else {
+                self.management_cors_allowed_hosts.clone()
+            },
+            max_buffered_requests: self
+                .max_buffered_requests
+                .or(file.max_buffered_requests)
+                .unwrap_or(30),
+            max_unavailable_agents: self
+                .max_unavailable_agents
+                .or(file.max_unavailable_agents)
+                .unwrap_or(1),
+            shadow_comparison_log: self
+                .shadow_comparison_log
+                .clone()
+                .or(file.shadow_comparison_log.map(PathBuf::from)),
+            shutdown_drain_timeout: self
+                .shutdown_drain_timeout
+                .or(file.shutdown_drain_timeout.map(Duration::from_millis))
+                .unwrap_or(Duration::from_millis(30000)),
+            state_database: match &self.state_database {
+                Some(state_database) => state_database.clone(),
+                None => parse_configuration_file_value(
+                    "state_database",
+                    file.state_database.as_ref(),
+                    StateDatabaseType::from_str,
+                )?
+                .unwrap_or(StateDatabaseType::Memory),
+            },
+            statsd_addr: match self.statsd_addr {
+                Some(statsd_addr) => Some(statsd_addr),
+                None => parse_configuration_file_value(
+                    "statsd_addr",
+                    file.statsd_addr.as_ref(),
+                    parse_socket_addr,
+                )?,
+            },
+            statsd_prefix: self
+                .statsd_prefix
+                .clone()
+                .or(file.statsd_prefix)
+                .unwrap_or_else(|| "paddler_".to_string()),
+            statsd_reporting_interval: self
+                .statsd_reporting_interval
+                .or(file.statsd_reporting_interval.map(Duration::from_millis))
+                .unwrap_or(Duration::from_millis(10000)),
+            tls_agent_ca,
+            tls_cert,
+            tls_key,
+            unix_socket_mode: match self.unix_socket_mode {
+                Some(unix_socket_mode) => Some(unix_socket_mode),
+                None => parse_configuration_file_value(
+                    "unix_socket_mode",
+                    file.unix_socket_mode.as_ref(),
+                    parse_unix_socket_mode,
+                )?,
+            },
+            web_admin_panel_addr: match self.web_admin_panel_addr {
+                Some(web_admin_panel_addr) => Some(web_admin_panel_addr),
+                None => parse_configuration_file_value(
+                    "web_admin_panel_addr",
+                    file.web_admin_panel_addr.as_ref(),
+                    parse_socket_addr,
+                )?,
+            },
+        })
     }
 
-    #[cfg(feature = "web_admin_panel")]
-    fn get_web_admin_panel_service_configuration(
-        &self,
-    ) -> Option<WebAdminPanelServiceConfiguration> {
-        self.web_admin_panel_addr
-            .map(|web_admin_panel_addr| WebAdminPanelServiceConfiguration {
-                addr: web_admin_panel_addr,
-                template_data: TemplateData {
-                    buffered_request_timeout: self.buffered_request_timeout,
-                    compat_openai_addr: self.compat_openai_addr,
-                    max_buffered_requests: self.max_buffered_requests,
-                    management_addr: self.management_addr,
-                    inference_addr: self.inference_addr,
-                    statsd_addr: self.statsd_addr,
-                    statsd_prefix: self.statsd_prefix.clone(),
-                    statsd_reporting_interval: self.statsd_reporting_interval,
-                },
-            })
+    pub fn get_config_path(&self) -> Option<&Path> {
+        self.config.as_deref()
     }
-}
 
-#[async_trait]
-impl Handler for Balancer {
-    async fn handle(&self, shutdown_rx: oneshot::Receiver<()>) -> Result<()> {
+    /// Returns the handler that an in-process agent can pass its messages to instead of
+    /// connecting to the agent socket
+    pub async fn add_services(
+        &self,
+        configuration: &BalancerConfiguration,
+        service_manager: &mut ServiceManager,
+    ) -> Result<AgentMessageHandler> {
         let (balancer_desired_state_tx, balancer_desired_state_rx) = broadcast::channel(100);
 
         let agent_controller_pool = Arc::new(AgentControllerPool::default());
         let balancer_applicable_state_holder = Arc::new(BalancerApplicableStateHolder::default());
         let buffered_request_manager = Arc::new(BufferedRequestManager::new(
             agent_controller_pool.clone(),
-            self.buffered_request_timeout,
-            self.max_buffered_requests,
+            configuration.buffered_request_timeout,
+            configuration.max_buffered_requests,
+            configuration
+                .shadow_comparison_log
+                .clone()
+                .map(|path| Arc::new(ShadowComparisonLog::new(path))),
         ));
         let chat_template_override_sender_collection =
             Arc::new(ChatTemplateOverrideSenderCollection::default());
         let embedding_sender_collection = Arc::new(EmbeddingSenderCollection::default());
         let generate_tokens_sender_collection = Arc::new(GenerateTokensSenderCollection::default());
         let model_metadata_sender_collection = Arc::new(ModelMetadataSenderCollection::default());
-        let mut service_manager = ServiceManager::default();
-        let state_database: Arc<dyn StateDatabase> = match &self.state_database {
-            StateDatabaseType::File(path) => Arc::new(File::new(
+        let inference_service_configuration =
+            configuration.get_inference_service_configuration()?;
+        let management_service_configuration =
+            configuration.get_management_service_configuration()?;
+        let service_health_registry = service_manager.get_service_health_registry();
+        let mut state_database_file = None;
+        let (statsd_service_configuration_tx, statsd_service_configuration_rx) =
+            watch::channel(configuration.get_statsd_service_configuration());
+        let state_database: Arc<dyn StateDatabase> = match &configuration.state_database {
+            StateDatabaseType::File(path) => {
+                let file = Arc::new(File::new(
+                    balancer_desired_state_tx.clone(),
+                    path.to_owned(),
+                ));
+
+                service_manager.add_service(StateDatabaseFileWatchService {
+                    agent_controller_pool: agent_controller_pool.clone(),
+                    file: file.clone(),
+                });
+
+                state_database_file = Some(file.clone());
+
+                file
+            }
+            StateDatabaseType::Memory => Arc::new(Memory::new(balancer_desired_state_tx.clone())),
+            StateDatabaseType::Sqlite(path) => Arc::new(Sqlite::new(
                 balancer_desired_state_tx.clone(),
                 path.to_owned(),
-            )),
-            StateDatabaseType::Memory => Arc::new(Memory::new(balancer_desired_state_tx.clone())),
+            )?),
         };
 
+        match &configuration.initial_desired_state {
+            Some(initial_desired_state)
+                if state_database
+                    .read_current_balancer_desired_state_revision()
+                    .await?
+                    .id
+                    == 0 =>
+            {
+                info!("Storing the initial desired state from the configuration file");
+
+                state_database
+                    .store_balancer_desired_state(
+                        initial_desired_state,
+                        Some("configuration_file".to_string()),
+                        Some(0),
+                    )
+                    .await?;
+            }
+            _ => {}
+        }
+
+        agent_controller_pool.set_desired_state_overrides(
+            state_database.read_agent_desired_state_overrides().await?,
+        );
+
+        service_manager.add_service(BalancerConfigurationReloadService {
+            agent_controller_pool: agent_controller_pool.clone(),
+            balancer: self.clone(),
+            buffered_request_manager: buffered_request_manager.clone(),
+            configuration: configuration.clone(),
+            inference_service_configuration: inference_service_configuration.clone(),
+            management_service_configuration: management_service_configuration.clone(),
+            state_database_file,
+            statsd_service_configuration_tx,
+        });
+
         service_manager.add_service(InferenceService {
             balancer_applicable_state_holder: balancer_applicable_state_holder.clone(),
             buffered_request_manager: buffered_request_manager.clone(),
-            configuration: self.get_inference_service_configuration(),
-            #[cfg(feature = "web_admin_panel")]
-            web_admin_panel_service_configuration: self.get_web_admin_panel_service_configuration(),
+            configuration: inference_service_configuration.clone(),
+            service_health_registry: service_health_registry.clone(),
         });
 
-        service_manager.add_service(ManagementService {
+        let agent_message_handler = AgentMessageHandler {
             agent_controller_pool: agent_controller_pool.clone(),
             balancer_applicable_state_holder: balancer_applicable_state_holder.clone(),
-            buffered_request_manager: buffered_request_manager.clone(),
             chat_template_override_sender_collection,
-            configuration: self.get_management_service_configuration(),
             embedding_sender_collection,
             generate_tokens_sender_collection,
             model_metadata_sender_collection,
+        };
+
+        service_manager.add_service(ManagementService {
+            agent_controller_pool: agent_controller_pool.clone(),
+            agent_message_handler: agent_message_handler.clone(),
+            balancer_applicable_state_holder: balancer_applicable_state_holder.clone(),
+            buffered_request_manager: buffered_request_manager.clone(),
+            configuration: management_service_configuration,
+            service_health_registry: service_health_registry.clone(),
             state_database: state_database.clone(),
-            statsd_prefix: self.statsd_prefix.clone(),
-            #[cfg(feature = "web_admin_panel")]
-            web_admin_panel_service_configuration: self.get_web_admin_panel_service_configuration(),
+            statsd_prefix: configuration.statsd_prefix.clone(),
         });
 
         service_manager.add_service(ReconciliationService {
@@ -194,35 +440,138 @@ impl Handler for Balancer {
             balancer_desired_state: state_database.read_balancer_desired_state().await?,
             balancer_desired_state_rx,
             is_converted_to_applicable_state: false,
+            max_unavailable_agents: configuration.max_unavailable_agents,
         });
 
-        if let Some(statsd_addr) = self.statsd_addr {
-            service_manager.add_service(StatsdService {
-                agent_controller_pool,
-                buffered_request_manager: buffered_request_manager.clone(),
-                configuration: StatsdServiceConfiguration {
-                    statsd_addr,
-                    statsd_prefix: self.statsd_prefix.clone(),
-                    statsd_reporting_interval: self.statsd_reporting_interval,
-                },
-            });
-        }
+        service_manager.add_service(StatsdService {
+            agent_controller_pool,
+            buffered_request_manager: buffered_request_manager.clone(),
+            configuration_rx: statsd_service_configuration_rx,
+        });
 
         #[cfg(feature = "web_admin_panel")]
-        if let Some(configuration) = self.get_web_admin_panel_service_configuration() {
-            service_manager.add_service(WebAdminPanelService { configuration });
+        if let Some(web_admin_panel_service_configuration) =
+            configuration.get_web_admin_panel_service_configuration()?
+        {
+            service_manager.add_service(WebAdminPanelService {
+                configuration: web_admin_panel_service_configuration,
+            });
         }
 
-        if let Some(compat_openai_addr) = self.compat_openai_addr {
+        if let Some(openai_service_configuration) =
+            configuration.get_openai_service_configuration()?
+        {
             service_manager.add_service(OpenAIService {
-                buffered_request_manager,
-                inference_service_configuration: self.get_inference_service_configuration(),
-                openai_service_configuration: OpenAIServiceConfiguration {
-                    addr: compat_openai_addr,
-                },
+                buffered_request_manager: buffered_request_manager.clone(),
+                inference_service_configuration,
+                openai_service_configuration,
+                service_health_registry,
             });
         }
 
+        service_manager.add_drain(
+            buffered_request_manager.request_drain.clone(),
+            configuration.shutdown_drain_timeout,
+        );
+
+        Ok(agent_message_handler)
+    }
+}
+
+#[async_trait]
+impl Handler for Balancer {
+    async fn handle(&self, shutdown_rx: oneshot::Receiver<()>) -> Result<()> {
+        let configuration = self.resolve_configuration()?;
+        let mut service_manager = ServiceManager::default();
+
+        self.add_services(&configuration, &mut service_manager)
+            .await?;
+
         service_manager.run_forever(shutdown_rx).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    #[test]
+    fn test_command_line_flags_take_precedence_over_configuration_file() -> Result<()> {
+        let mut configuration_file = NamedTempFile::new()?;
+
+        write!(
+            configuration_file,
+            r#"
+                [balancer]
+                max_buffered_requests = 10
+                statsd_prefix = "from_file_"
+
+                [balancer.initial_desired_state]
+                model = {{ LocalToAgent = "/models/model.gguf" }}
+                use_chat_template_override = false
+
+                [balancer.initial_desired_state.inference_parameters]
+                batch_n_tokens = 512
+                context_size = 4096
+                enable_embeddings = false
+                min_p = 0.05
+                penalty_frequency = 0.0
+                penalty_last_n = -1
+                penalty_presence = 1.5
+                penalty_repeat = 1.0
+                pooling_type = "Last"
+                temperature = 0.6
+                top_k = 40
+                top_p = 0.8
+            "#
+        )?;
+
+        let configuration = Balancer::try_parse_from([
+            "balancer".into(),
+            "--config".into(),
+            configuration_file.path().as_os_str().to_owned(),
+            "--max-buffered-requests".into(),
+            "20".into(),
+        ])?
+        .resolve_configuration()?;
+
+        assert_eq!(configuration.max_buffered_requests, 20);
+        assert_eq!(configuration.statsd_prefix, "from_file_");
+        assert_eq!(configuration.max_unavailable_agents, 1);
+        assert!(configuration.initial_desired_state.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unix_socket_addresses_are_accepted() -> Result<()> {
+        let configuration = Balancer::try_parse_from([
+            "balancer",
+            "--management-addr",
+            "unix:/run/paddler/management.sock",
+            "--unix-socket-mode",
+            "660",
+        ])?
+        .resolve_configuration()?;
+
+        assert_eq!(
+            configuration.management_addr,
+            ListenAddr::Unix(PathBuf::from("/run/paddler/management.sock"))
+        );
+        assert_eq!(
+            configuration.inference_addr,
+            ListenAddr::Tcp(SocketAddr::from(([127, 0, 0, 1], 8061)))
+        );
+        assert_eq!(configuration.unix_socket_mode, Some(0o660));
+        assert_eq!(
+            configuration.to_configuration_file().unix_socket_mode,
+            Some("660".to_string())
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use anyhow::Result;
+use rustls::ServerConfig;
+
+use super::balancer_configuration_file::BalancerConfigurationFile;
+use crate::balancer::compatibility::openai_service::configuration::Configuration as OpenAIServiceConfiguration;
+use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
+use crate::balancer::management_service::configuration::Configuration as ManagementServiceConfiguration;
+use crate::balancer::state_database_type::StateDatabaseType;
+use crate::balancer::statsd_service::configuration::Configuration as StatsdServiceConfiguration;
+#[cfg(feature = "web_admin_panel")]
+use crate::balancer::web_admin_panel_service::configuration::Configuration as WebAdminPanelServiceConfiguration;
+#[cfg(feature = "web_admin_panel")]
+use crate::balancer::web_admin_panel_service::template_data::TemplateData;
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::cors_allowed_hosts::CorsAllowedHosts;
+use crate::listen_addr::ListenAddr;
+use crate::make_tls_server_config::make_tls_server_config;
+
+/// Balancer settings after merging the command line flags, environment variables,
+/// configuration file, and defaults
+#[derive(Clone)]
+pub struct BalancerConfiguration {
+    pub buffered_request_timeout: Duration,
+    pub compat_openai_addr: Option<ListenAddr>,
+    pub inference_addr: ListenAddr,
+    pub inference_cors_allowed_hosts: Vec<String>,
+    pub inference_item_timeout: Duration,
+    pub initial_desired_state: Option<BalancerDesiredState>,
+    pub management_addr: ListenAddr,
+    pub management_cors_allowed_hosts: Vec<String>,
+    pub max_buffered_requests: i32,
+    pub max_unavailable_agents: usize,
+    pub shadow_comparison_log: Option<PathBuf>,
+    pub shutdown_drain_timeout: Duration,
+    pub state_database: StateDatabaseType,
+    pub statsd_addr: Option<SocketAddr>,
+    pub statsd_prefix: String,
+    pub statsd_reporting_interval: Duration,
+    pub tls_agent_ca: Option<PathBuf>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub unix_socket_mode: Option<u32>,
+    pub web_admin_panel_addr: Option<SocketAddr>,
+}
+
+impl BalancerConfiguration {
+    /// All of the listeners share the certificate. Only the management service
+    /// (that the agents connect to) checks the client certificates.
+    fn make_tls_server_config(
+        &self,
+        client_ca_path: Option<&Path>,
+    ) -> Result<Option<Arc<ServerConfig>>> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(tls_cert), Some(tls_key)) => Ok(Some(Arc::new(make_tls_server_config(
+                tls_cert,
+                tls_key,
+                client_ca_path,
+            )?))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Web admin panel talks to both the inference and the management services
+    fn with_web_admin_panel_host(&self, cors_allowed_hosts: &[String]) -> Vec<String> {
+        #[allow(unused_mut)]
+        let mut cors_allowed_hosts = cors_allowed_hosts.to_vec();
+
+        #[cfg(feature = "web_admin_panel")]
+        if let Some(web_admin_panel_addr) = self.web_admin_panel_addr {
+            cors_allowed_hosts.push(format!(
+                "{}://{web_admin_panel_addr}",
+                if self.tls_cert.is_some() {
+                    "https"
+                } else {
+                    "http"
+                }
+            ));
+        }
+
+        cors_allowed_hosts
+    }
+
+    pub fn get_inference_cors_allowed_hosts(&self) -> Vec<String> {
+        self.with_web_admin_panel_host(&self.inference_cors_allowed_hosts)
+    }
+
+    pub fn get_management_cors_allowed_hosts(&self) -> Vec<String> {
+        self.with_web_admin_panel_host(&self.management_cors_allowed_hosts)
+    }
+
+    pub fn get_management_service_configuration(&self) -> Result<ManagementServiceConfiguration> {
+        Ok(ManagementServiceConfiguration {
+            addr: self.management_addr.clone(),
+            cors_allowed_hosts: Arc::new(CorsAllowedHosts::new(
+                self.get_management_cors_allowed_hosts(),
+            )),
+            // Unix sockets are not encrypted, so the file permissions guard them instead
+            requires_agent_certificate: self.tls_agent_ca.is_some()
+                && matches!(self.management_addr, ListenAddr::Tcp(_)),
+            tls_server_config: self.make_tls_server_config(self.tls_agent_ca.as_deref())?,
+            unix_socket_mode: self.unix_socket_mode,
+        })
+    }
+
+    pub fn get_inference_service_configuration(&self) -> Result<InferenceServiceConfiguration> {
+        Ok(InferenceServiceConfiguration {
+            addr: self.inference_addr.clone(),
+            cors_allowed_hosts: Arc::new(CorsAllowedHosts::new(
+                self.get_inference_cors_allowed_hosts(),
+            )),
+            inference_item_timeout: Arc::new(RwLock::new(self.inference_item_timeout)),
+            tls_server_config: self.make_tls_server_config(None)?,
+            unix_socket_mode: self.unix_socket_mode,
+        })
+    }
+
+    pub fn get_openai_service_configuration(&self) -> Result<Option<OpenAIServiceConfiguration>> {
+        match &self.compat_openai_addr {
+            Some(compat_openai_addr) => Ok(Some(OpenAIServiceConfiguration {
+                addr: compat_openai_addr.clone(),
+                tls_server_config: self.make_tls_server_config(None)?,
+                unix_socket_mode: self.unix_socket_mode,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_statsd_service_configuration(&self) -> Option<StatsdServiceConfiguration> {
+        self.statsd_addr
+            .map(|statsd_addr| StatsdServiceConfiguration {
+                statsd_addr,
+                statsd_prefix: self.statsd_prefix.clone(),
+                statsd_reporting_interval: self.statsd_reporting_interval,
+            })
+    }
+
+    #[cfg(feature = "web_admin_panel")]
+    pub fn get_web_admin_panel_service_configuration(
+        &self,
+    ) -> Result<Option<WebAdminPanelServiceConfiguration>> {
+        let web_admin_panel_addr = match self.web_admin_panel_addr {
+            Some(web_admin_panel_addr) => web_admin_panel_addr,
+            None => return Ok(None),
+        };
+
+        Ok(Some(WebAdminPanelServiceConfiguration {
+            addr: web_admin_panel_addr,
+            template_data: TemplateData {
+                buffered_request_timeout: self.buffered_request_timeout,
+                compat_openai_addr: self.compat_openai_addr.clone(),
+                max_buffered_requests: self.max_buffered_requests,
+                management_addr: self.management_addr.clone(),
+                inference_addr: self.inference_addr.clone(),
+                statsd_addr: self.statsd_addr,
+                statsd_prefix: self.statsd_prefix.clone(),
+                statsd_reporting_interval: self.statsd_reporting_interval,
+            },
+            tls_server_config: self.make_tls_server_config(None)?,
+        }))
+    }
+
+    /// Configuration file section that reproduces these settings
+    pub fn to_configuration_file(&self) -> BalancerConfigurationFile {
+        BalancerConfigurationFile {
+            buffered_request_timeout: Some(self.buffered_request_timeout.as_millis() as u64),
+            compat_openai_addr: self
+                .compat_openai_addr
+                .as_ref()
+                .map(|addr| addr.to_string()),
+            inference_addr: Some(self.inference_addr.to_string()),
+            inference_cors_allowed_hosts: Some(self.inference_cors_allowed_hosts.clone()),
+            inference_item_timeout: Some(self.inference_item_timeout.as_millis() as u64),
+            initial_desired_state: self.initial_desired_state.clone(),
+            management_addr: Some(self.management_addr.to_string()),
+            management_cors_allowed_hosts: Some(self.management_cors_allowed_hosts.clone()),
+            max_buffered_requests: Some(self.max_buffered_requests),
+            max_unavailable_agents: Some(self.max_unavailable_agents),
+            shadow_comparison_log: self
+                .shadow_comparison_log
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            shutdown_drain_timeout: Some(self.shutdown_drain_timeout.as_millis() as u64),
+            state_database: Some(self.state_database.to_string()),
+            statsd_addr: self.statsd_addr.map(|addr| addr.to_string()),
+            statsd_prefix: Some(self.statsd_prefix.clone()),
+            statsd_reporting_interval: Some(self.statsd_reporting_interval.as_millis() as u64),
+            tls_agent_ca: self
+                .tls_agent_ca
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            tls_cert: self
+                .tls_cert
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            tls_key: self.tls_key.as_ref().map(|path| path.display().to_string()),
+            unix_socket_mode: self
+                .unix_socket_mode
+                .map(|unix_socket_mode| format!("{unix_socket_mode:o}")),
+            web_admin_panel_addr: self.web_admin_panel_addr.map(|addr| addr.to_string()),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer_desired_state::BalancerDesiredState;
+
+/// `[balancer]` section of the configuration file. Keys are named after the command line flags,
+/// durations are in milliseconds.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BalancerConfigurationFile {
+    pub buffered_request_timeout: Option<u64>,
+    pub compat_openai_addr: Option<String>,
+    pub inference_addr: Option<String>,
+    pub inference_cors_allowed_hosts: Option<Vec<String>>,
+    pub inference_item_timeout: Option<u64>,
+    /// Stored only if the state database does not have a desired state yet
+    pub initial_desired_state: Option<BalancerDesiredState>,
+    pub management_addr: Option<String>,
+    pub management_cors_allowed_hosts: Option<Vec<String>>,
+    pub max_buffered_requests: Option<i32>,
+    pub max_unavailable_agents: Option<usize>,
+    pub shadow_comparison_log: Option<String>,
+    pub shutdown_drain_timeout: Option<u64>,
+    pub state_database: Option<String>,
+    pub statsd_addr: Option<String>,
+    pub statsd_prefix: Option<String>,
+    pub statsd_reporting_interval: Option<u64>,
+    pub tls_agent_ca: Option<String>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    /// Octal, for example "660"
+    pub unix_socket_mode: Option<String>,
+    pub web_admin_panel_addr: Option<String>,
+}
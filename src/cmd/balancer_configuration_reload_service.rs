@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use log::error;
+use log::info;
+use log::warn;
+use tokio::signal::unix::SignalKind;
+use tokio::signal::unix::signal;
+use tokio::sync::broadcast;
+use tokio::sync::watch;
+
+use super::balancer::Balancer;
+use super::balancer_configuration::BalancerConfiguration;
+use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
+use crate::balancer::management_service::configuration::Configuration as ManagementServiceConfiguration;
+use crate::balancer::state_database::File;
+use crate::balancer::state_database_file_watch_service::apply_external_change;
+use crate::balancer::statsd_service::configuration::Configuration as StatsdServiceConfiguration;
+use crate::service::Service;
+
+/// Re-reads the configuration file on SIGHUP and applies the settings that can change
+/// without dropping connections. Flags and environment variables cannot change while the
+/// process is running, so they keep taking precedence.
+pub struct BalancerConfigurationReloadService {
+    pub agent_controller_pool: Arc<AgentControllerPool>,
+    pub balancer: Balancer,
+    pub buffered_request_manager: Arc<BufferedRequestManager>,
+    pub configuration: BalancerConfiguration,
+    pub inference_service_configuration: InferenceServiceConfiguration,
+    pub management_service_configuration: ManagementServiceConfiguration,
+    pub state_database_file: Option<Arc<File>>,
+    pub statsd_service_configuration_tx: watch::Sender<Option<StatsdServiceConfiguration>>,
+}
+
+impl BalancerConfigurationReloadService {
+    async fn reload(&mut self) {
+        let configuration = match self.balancer.resolve_configuration() {
+            Ok(configuration) => configuration,
+            Err(err) => {
+                error!("Keeping the current configuration, unable to reload it: {err:#}");
+
+                return;
+            }
+        };
+
+        self.warn_about_restart_required_changes(&configuration);
+
+        self.buffered_request_manager
+            .set_buffered_request_timeout(configuration.buffered_request_timeout);
+        self.buffered_request_manager
+            .set_max_buffered_requests(configuration.max_buffered_requests);
+        self.inference_service_configuration
+            .cors_allowed_hosts
+            .set(configuration.get_inference_cors_allowed_hosts());
+        self.inference_service_configuration
+            .set_inference_item_timeout(configuration.inference_item_timeout);
+        self.management_service_configuration
+            .cors_allowed_hosts
+            .set(configuration.get_management_cors_allowed_hosts());
+        self.statsd_service_configuration_tx
+            .send_if_modified(|statsd_service_configuration| {
+                let new_statsd_service_configuration =
+                    configuration.get_statsd_service_configuration();
+
+                if *statsd_service_configuration == new_statsd_service_configuration {
+                    false
+                } else {
+                    *statsd_service_configuration = new_statsd_service_configuration;
+
+                    true
+                }
+            });
+
+        if let Some(state_database_file) = &self.state_database_file {
+            apply_external_change(&self.agent_controller_pool, state_database_file).await;
+        }
+
+        self.configuration = configuration;
+
+        info!("Configuration reloaded");
+    }
+
+    fn warn_about_restart_required_changes(&self, configuration: &BalancerConfiguration) {
+        let current = &self.configuration;
+
+        for (name, is_changed) in [
+            (
+                "compat_openai_addr",
+                current.compat_openai_addr != configuration.compat_openai_addr,
+            ),
+            (
+                "inference_addr",
+                current.inference_addr != configuration.inference_addr,
+            ),
+            (
+                "management_addr",
+                current.management_addr != configuration.management_addr,
+            ),
+            (
+                "max_unavailable_agents",
+                current.max_unavailable_agents != configuration.max_unavailable_agents,
+            ),
+            (
+                "shadow_comparison_log",
+                current.shadow_comparison_log != configuration.shadow_comparison_log,
+            ),
+            (
+                "shutdown_drain_timeout",
+                current.shutdown_drain_timeout != configuration.shutdown_drain_timeout,
+            ),
+            (
+                "state_database",
+                current.state_database.to_string() != configuration.state_database.to_string(),
+            ),
+            (
+                "tls_agent_ca",
+                current.tls_agent_ca != configuration.tls_agent_ca,
+            ),
+            ("tls_cert", current.tls_cert != configuration.tls_cert),
+            ("tls_key", current.tls_key != configuration.tls_key),
+            (
+                "unix_socket_mode",
+                current.unix_socket_mode != configuration.unix_socket_mode,
+            ),
+            (
+                "web_admin_panel_addr",
+                current.web_admin_panel_addr != configuration.web_admin_panel_addr,
+            ),
+        ] {
+            if is_changed {
+                warn!("Ignoring changed '{name}' setting, it needs a restart to take effect");
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Service for BalancerConfigurationReloadService {
+    fn name(&self) -> &'static str {
+        "cmd::balancer_configuration_reload_service"
+    }
+
+    async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let mut sighup = signal(SignalKind::hangup())?;
+
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break Ok(()),
+                _ = sighup.recv() => {
+                    info!("Reloading configuration");
+
+                    self.reload().await;
+                }
+            }
+        }
+    }
+}
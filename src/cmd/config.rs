@@ -0,0 +1,78 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::Parser;
+use clap::Subcommand;
+use tokio::sync::oneshot;
+
+use super::agent::Agent;
+use super::balancer::Balancer;
+use super::configuration_file::ConfigurationFile;
+use super::handler::Handler;
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Validates the configuration file and prints the effective configuration
+    /// (configuration file merged with environment variables and defaults)
+    Check {
+        /// Path to the TOML configuration file
+        path: PathBuf,
+    },
+}
+
+#[derive(Parser)]
+pub struct Config {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+fn check(path: &Path) -> Result<()> {
+    let configuration_file = ConfigurationFile::read(path)?;
+
+    if configuration_file.agent.is_none() && configuration_file.balancer.is_none() {
+        return Err(anyhow!(
+            "Configuration file '{}' has neither [agent] nor [balancer] section",
+            path.display()
+        ));
+    }
+
+    let args = [
+        "paddler".into(),
+        "--config".into(),
+        path.as_os_str().to_owned(),
+    ];
+    let effective_configuration_file = ConfigurationFile {
+        agent: match configuration_file.agent {
+            Some(_) => Some(
+                Agent::try_parse_from(args.clone())?
+                    .resolve_configuration()?
+                    .to_configuration_file(),
+            ),
+            None => None,
+        },
+        balancer: match configuration_file.balancer {
+            Some(_) => Some(
+                Balancer::try_parse_from(args)?
+                    .resolve_configuration()?
+                    .to_configuration_file(),
+            ),
+            None => None,
+        },
+    };
+
+    println!("{}", toml::to_string_pretty(&effective_configuration_file)?);
+
+    Ok(())
+}
+
+#[async_trait]
+impl Handler for Config {
+    async fn handle(&self, _shutdown_rx: oneshot::Receiver<()>) -> Result<()> {
+        match &self.command {
+            ConfigCommand::Check { path } => check(path),
+        }
+    }
+}
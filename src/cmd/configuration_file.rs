@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::agent_configuration_file::AgentConfigurationFile;
+use super::balancer_configuration_file::BalancerConfigurationFile;
+
+/// TOML file that can hold the configuration of both the agent and the balancer,
+/// each of them reads only its own section
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigurationFile {
+    pub agent: Option<AgentConfigurationFile>,
+    pub balancer: Option<BalancerConfigurationFile>,
+}
+
+impl ConfigurationFile {
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).context(format!(
+            "Unable to read configuration file: '{}'",
+            path.display()
+        ))?;
+
+        toml::from_str(&content).context(format!(
+            "Unable to parse configuration file: '{}'",
+            path.display()
+        ))
+    }
+}
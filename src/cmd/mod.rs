@@ -1,14 +1,27 @@
 pub mod agent;
+mod agent_configuration;
+mod agent_configuration_file;
 pub mod balancer;
+mod balancer_configuration;
+mod balancer_configuration_file;
+mod balancer_configuration_reload_service;
+pub mod config;
+mod configuration_file;
 pub mod handler;
+pub mod serve;
 
 use std::net::SocketAddr;
 use std::net::ToSocketAddrs;
+use std::path::PathBuf;
 use std::time::Duration;
 
+use anyhow::Context as _;
 use anyhow::Result;
 use anyhow::anyhow;
 
+use crate::listen_addr::ListenAddr;
+use crate::listen_addr::UNIX_SOCKET_PREFIX;
+
 fn resolve_socket_addr(s: &str) -> Result<SocketAddr> {
     let addrs: Vec<SocketAddr> = s.to_socket_addrs()?.collect();
 
@@ -33,9 +46,56 @@ fn parse_duration(arg: &str) -> Result<Duration> {
     Ok(std::time::Duration::from_millis(milliseconds))
 }
 
+/// Unix sockets are written as `unix:/path/to.sock`
+fn parse_listen_addr(arg: &str) -> Result<ListenAddr> {
+    match arg.strip_prefix(UNIX_SOCKET_PREFIX) {
+        Some("") => Err(anyhow!("Unix socket path is missing: '{arg}'")),
+        Some(path) => Ok(ListenAddr::Unix(PathBuf::from(path))),
+        None => Ok(ListenAddr::Tcp(parse_socket_addr(arg)?)),
+    }
+}
+
+/// Host names are kept as they are, so they can be resolved again on every connection attempt.
+/// Unix sockets are written as `unix:/path/to.sock`.
+fn parse_management_addr(arg: &str) -> Result<String> {
+    match arg.strip_prefix(UNIX_SOCKET_PREFIX) {
+        Some("") => Err(anyhow!("Unix socket path is missing: '{arg}'")),
+        Some(_) => Ok(arg.to_string()),
+        None => match arg.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() => {
+                port.parse::<u16>()
+                    .context(format!("Invalid port in address: '{arg}'"))?;
+
+                Ok(arg.to_string())
+            }
+            _ => Err(anyhow!("Address must be in the host:port format: '{arg}'")),
+        },
+    }
+}
+
+/// Octal, like the `chmod` modes (for example, 660)
+fn parse_unix_socket_mode(arg: &str) -> Result<u32> {
+    u32::from_str_radix(arg, 8).context(format!("Invalid octal Unix socket mode: '{arg}'"))
+}
+
 fn parse_socket_addr(arg: &str) -> Result<SocketAddr> {
     match arg.parse() {
         Ok(socketaddr) => Ok(socketaddr),
         Err(_) => Ok(resolve_socket_addr(arg)?),
     }
 }
+
+/// Uses the same parser as the matching command line flag
+fn parse_configuration_file_value<TValue>(
+    name: &str,
+    value: Option<&String>,
+    parse: impl Fn(&str) -> Result<TValue>,
+) -> Result<Option<TValue>> {
+    value
+        .map(|value| {
+            parse(value).context(format!(
+                "Invalid '{name}' value in the configuration file: '{value}'"
+            ))
+        })
+        .transpose()
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::Parser;
+use tokio::sync::oneshot;
+
+use super::agent::Agent;
+use super::agent_configuration::AgentConfiguration;
+use super::agent_configuration_file::AgentConfigurationFile;
+use super::balancer::Balancer;
+use super::configuration_file::ConfigurationFile;
+use super::handler::Handler;
+use super::parse_configuration_file_value;
+use super::parse_duration;
+use super::parse_socket_addr;
+use crate::label_selector::parse_label;
+use crate::service_manager::ServiceManager;
+
+#[derive(Parser)]
+pub struct Serve {
+    #[arg(
+        long = "agent-shutdown-drain-timeout",
+        env = "PADDLER_AGENT_SHUTDOWN_DRAIN_TIMEOUT",
+        value_parser = parse_duration
+    )]
+    /// How long (in milliseconds) the in-process agent waits for the slots to finish their
+    /// requests when shutting down, next to the balancer --shutdown-drain-timeout [default: 30000]
+    agent_shutdown_drain_timeout: Option<Duration>,
+
+    #[command(flatten)]
+    balancer: Balancer,
+
+    #[arg(long, env = "PADDLER_AGENT_HEALTH_ADDR", value_parser = parse_socket_addr)]
+    /// Address of the optional HTTP server with the `/health/live` and `/health/ready` endpoints
+    /// of the in-process agent (for example, for Kubernetes probes)
+    health_addr: Option<SocketAddr>,
+
+    #[arg(
+        long = "label",
+        env = "PADDLER_AGENT_LABELS",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append,
+        value_parser = parse_label
+    )]
+    /// Label of the in-process agent in the key=value format (can be specified multiple times)
+    labels: Vec<(String, String)>,
+
+    #[arg(long, env = "PADDLER_AGENT_NAME")]
+    /// Name of the in-process agent (optional)
+    name: Option<String>,
+
+    #[arg(long, env = "PADDLER_AGENT_SLOTS")]
+    /// Number of parallel requests of any kind that the in-process agent can handle at once,
+    /// used until the balancer desired state specifies the number of slots [default: 1]
+    slots: Option<i32>,
+
+    #[arg(long, env = "PADDLER_AGENT_STATE_CHANGE_DRAIN_TIMEOUT", value_parser = parse_duration)]
+    /// How long (in milliseconds) to wait for the in-flight requests to finish before the
+    /// in-process agent applies a new desired state [default: 300000]
+    state_change_drain_timeout: Option<Duration>,
+}
+
+impl Serve {
+    /// In-process agent takes the same settings as a standalone one from the `[agent]`
+    /// section, except for the connection settings that it ignores, since it does not
+    /// connect to the balancer over the network
+    fn resolve_agent_configuration(&self) -> Result<AgentConfiguration> {
+        let file = match self.balancer.get_config_path() {
+            Some(path) => ConfigurationFile::read(path)?.agent.unwrap_or_default(),
+            None => AgentConfigurationFile::default(),
+        };
+
+        Ok(AgentConfiguration {
+            health_addr: match self.health_addr {
+                Some(health_addr) => Some(health_addr),
+                None => parse_configuration_file_value(
+                    "health_addr",
+                    file.health_addr.as_ref(),
+                    parse_socket_addr,
+                )?,
+            },
+            labels: if self.labels.is_empty() {
+                file.labels.unwrap_or_default()
+            } else {
+                self.labels.iter().cloned().collect::<BTreeMap<_, _>>()
+            },
+            management_addrs: Vec::new(),
+            management_ca_cert: None,
+            name: self.name.clone().or(file.name),
+            shutdown_drain_timeout: self
+                .agent_shutdown_drain_timeout
+                .or(file.shutdown_drain_timeout.map(Duration::from_millis))
+                .unwrap_or(Duration::from_millis(30000)),
+            slots: self.slots.or(file.slots).unwrap_or(1),
+            state_change_drain_timeout: self
+                .state_change_drain_timeout
+                .or(file.state_change_drain_timeout.map(Duration::from_millis))
+                .unwrap_or(Duration::from_millis(300000)),
+            tls_cert: None,
+            tls_key: None,
+        })
+    }
+}
+
+#[async_trait]
+impl Handler for Serve {
+    async fn handle(&self, shutdown_rx: oneshot::Receiver<()>) -> Result<()> {
+        let balancer_configuration = self.balancer.resolve_configuration()?;
+        let agent_configuration = self.resolve_agent_configuration()?;
+        let mut service_manager = ServiceManager::default();
+
+        let agent_message_handler = self
+            .balancer
+            .add_services(&balancer_configuration, &mut service_manager)
+            .await?;
+
+        Agent::add_services(
+            &agent_configuration,
+            &mut service_manager,
+            Some(agent_message_handler),
+        )?;
+
+        service_manager.run_forever(shutdown_rx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_flags_are_accepted_next_to_the_balancer_flags() -> Result<()> {
+        let serve = Serve::try_parse_from([
+            "serve",
+            "--management-addr",
+            "127.0.0.1:9060",
+            "--label",
+            "zone=local",
+            "--slots",
+            "4",
+            "--health-addr",
+            "127.0.0.1:9070",
+            "--shutdown-drain-timeout",
+            "1000",
+            "--agent-shutdown-drain-timeout",
+            "2000",
+            "--state-change-drain-timeout",
+            "3000",
+        ])?;
+        let balancer_configuration = serve.balancer.resolve_configuration()?;
+        let agent_configuration = serve.resolve_agent_configuration()?;
+
+        assert_eq!(agent_configuration.slots, 4);
+        assert_eq!(
+            agent_configuration.labels.get("zone"),
+            Some(&"local".to_string())
+        );
+        assert!(agent_configuration.management_addrs.is_empty());
+        assert_eq!(
+            agent_configuration.health_addr,
+            Some("127.0.0.1:9070".parse()?)
+        );
+        assert_eq!(
+            balancer_configuration.shutdown_drain_timeout,
+            Duration::from_millis(1000)
+        );
+        assert_eq!(
+            agent_configuration.shutdown_drain_timeout,
+            Duration::from_millis(2000)
+        );
+        assert_eq!(
+            agent_configuration.state_change_drain_timeout,
+            Duration::from_millis(3000)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::RwLock;
+
+/// Allowed CORS origins, can be replaced while the servers are running
+pub struct CorsAllowedHosts {
+    hosts: RwLock<Vec<String>>,
+}
+
+impl CorsAllowedHosts {
+    pub fn new(hosts: Vec<String>) -> Self {
+        Self {
+            hosts: RwLock::new(hosts),
+        }
+    }
+
+    pub fn contains(&self, origin: &[u8]) -> bool {
+        self.hosts
+            .read()
+            .expect("Poisoned lock on CORS allowed hosts")
+            .iter()
+            .any(|host| host.as_bytes() == origin)
+    }
+
+    pub fn set(&self, hosts: Vec<String>) {
+        *self
+            .hosts
+            .write()
+            .expect("Poisoned lock on CORS allowed hosts") = hosts;
+    }
+}
@@ -0,0 +1,25 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::validates::Validates;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub enum DesiredSlots {
+    /// Derive the number of slots from the available memory and the context size
+    Auto,
+    Count(i32),
+}
+
+impl Validates<DesiredSlots> for DesiredSlots {
+    fn validate(self) -> Result<DesiredSlots> {
+        match self {
+            DesiredSlots::Count(count) if count < 1 => {
+                Err(anyhow!("Number of slots must be at least 1, got {count}"))
+            }
+            desired_slots => Ok(desired_slots),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait Drains: Send + Sync {
+    /// Stops taking new work and resolves once the work in progress is done
+    async fn drain(&self);
+}
@@ -16,4 +16,8 @@ impl StreamableResult for EmbeddingResult {
     fn is_done(&self) -> bool {
         matches!(self, EmbeddingResult::Done | EmbeddingResult::Error(_))
     }
+
+    fn is_error(&self) -> bool {
+        matches!(self, EmbeddingResult::Error(_))
+    }
 }
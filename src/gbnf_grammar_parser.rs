@@ -0,0 +1,337 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+/// Follows the llama.cpp grammar parser, so grammars can be rejected before
+/// they reach an agent. Compiling them there needs the model vocabulary.
+pub struct GbnfGrammarParser {
+    chars: Vec<char>,
+    defined_rules: BTreeSet<String>,
+    position: usize,
+    referenced_rules: BTreeSet<String>,
+}
+
+impl GbnfGrammarParser {
+    pub fn check(grammar: &str, root: &str) -> Result<()> {
+        let mut parser = Self {
+            chars: grammar.chars().collect(),
+            defined_rules: BTreeSet::new(),
+            position: 0,
+            referenced_rules: BTreeSet::new(),
+        };
+
+        parser.parse_space(true);
+
+        while parser.peek(0).is_some() {
+            parser.parse_rule()?;
+        }
+
+        if let Some(undefined_rule) = parser
+            .referenced_rules
+            .difference(&parser.defined_rules)
+            .next()
+        {
+            return Err(anyhow!("Undefined rule identifier '{undefined_rule}'"));
+        }
+
+        // llama.cpp needs the "root" rule even if a different one is the entry point
+        for required_rule in ["root", root] {
+            if !parser.defined_rules.contains(required_rule) {
+                return Err(anyhow!("Grammar does not contain a '{required_rule}' rule"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn error_at(&self, message: &str) -> anyhow::Error {
+        anyhow!(
+            "Grammar syntax error, {message} at position {}",
+            self.position
+        )
+    }
+
+    fn is_word_char(character: Option<char>) -> bool {
+        character.is_some_and(|character| character.is_ascii_alphanumeric() || character == '-')
+    }
+
+    fn parse_alternates(&mut self, is_nested: bool) -> Result<()> {
+        self.parse_sequence(is_nested)?;
+
+        while self.peek(0) == Some('|') {
+            self.position += 1;
+            self.parse_space(true);
+            self.parse_sequence(is_nested)?;
+        }
+
+        Ok(())
+    }
+
+    fn parse_char(&mut self) -> Result<()> {
+        match (self.peek(0), self.peek(1)) {
+            (Some('\\'), Some('x')) => self.parse_hex(2),
+            (Some('\\'), Some('u')) => self.parse_hex(4),
+            (Some('\\'), Some('U')) => self.parse_hex(8),
+            (Some('\\'), Some('t' | 'r' | 'n' | '\\' | '"' | '[' | ']')) => {
+                self.position += 2;
+
+                Ok(())
+            }
+            (Some('\\'), _) => Err(self.error_at("unknown escape")),
+            (Some(_), _) => {
+                self.position += 1;
+
+                Ok(())
+            }
+            (None, _) => Err(self.error_at("unexpected end of input")),
+        }
+    }
+
+    fn parse_hex(&mut self, size: usize) -> Result<()> {
+        self.position += 2;
+
+        for _ in 0..size {
+            if !self
+                .peek(0)
+                .is_some_and(|character| character.is_ascii_hexdigit())
+            {
+                return Err(self.error_at(&format!("expecting {size} hex chars")));
+            }
+
+            self.position += 1;
+        }
+
+        Ok(())
+    }
+
+    fn parse_int(&mut self) -> Result<()> {
+        if !self
+            .peek(0)
+            .is_some_and(|character| character.is_ascii_digit())
+        {
+            return Err(self.error_at("expecting an int"));
+        }
+
+        while self
+            .peek(0)
+            .is_some_and(|character| character.is_ascii_digit())
+        {
+            self.position += 1;
+        }
+
+        Ok(())
+    }
+
+    fn parse_name(&mut self) -> Result<String> {
+        let start = self.position;
+
+        while Self::is_word_char(self.peek(0)) {
+            self.position += 1;
+        }
+
+        if self.position == start {
+            return Err(self.error_at("expecting name"));
+        }
+
+        Ok(self.chars[start..self.position].iter().collect())
+    }
+
+    fn parse_repetition(&mut self, is_nested: bool) -> Result<()> {
+        self.parse_int()?;
+        self.parse_space(is_nested);
+
+        match self.peek(0) {
+            Some('}') => {}
+            Some(',') => {
+                self.position += 1;
+                self.parse_space(is_nested);
+
+                if self
+                    .peek(0)
+                    .is_some_and(|character| character.is_ascii_digit())
+                {
+                    self.parse_int()?;
+                    self.parse_space(is_nested);
+                }
+
+                if self.peek(0) != Some('}') {
+                    return Err(self.error_at("expecting '}'"));
+                }
+            }
+            _ => return Err(self.error_at("expecting ','")),
+        }
+
+        self.position += 1;
+        self.parse_space(is_nested);
+
+        Ok(())
+    }
+
+    fn parse_rule(&mut self) -> Result<()> {
+        let name = self.parse_name()?;
+
+        self.parse_space(false);
+
+        if self.chars[self.position..].starts_with(&[':', ':', '=']) {
+            self.position += 3;
+        } else {
+            return Err(self.error_at("expecting ::="));
+        }
+
+        self.parse_space(true);
+        self.parse_alternates(false)?;
+
+        match (self.peek(0), self.peek(1)) {
+            (Some('\r'), Some('\n')) => self.position += 2,
+            (Some('\r' | '\n'), _) => self.position += 1,
+            (None, _) => {}
+            _ => return Err(self.error_at("expecting newline or end")),
+        }
+
+        self.parse_space(true);
+        self.defined_rules.insert(name);
+
+        Ok(())
+    }
+
+    fn parse_sequence(&mut self, is_nested: bool) -> Result<()> {
+        let mut has_preceding_item = false;
+
+        while let Some(character) = self.peek(0) {
+            match character {
+                '"' => {
+                    self.position += 1;
+
+                    while self.peek(0) != Some('"') {
+                        self.parse_char()?;
+                    }
+
+                    self.position += 1;
+                    has_preceding_item = true;
+                }
+                '[' => {
+                    self.position += 1;
+
+                    if self.peek(0) == Some('^') {
+                        self.position += 1;
+                    }
+
+                    while self.peek(0) != Some(']') {
+                        self.parse_char()?;
+
+                        if self.peek(0) == Some('-') && self.peek(1) != Some(']') {
+                            self.position += 1;
+                            self.parse_char()?;
+                        }
+                    }
+
+                    self.position += 1;
+                    has_preceding_item = true;
+                }
+                '(' => {
+                    self.position += 1;
+                    self.parse_space(true);
+                    self.parse_alternates(true)?;
+
+                    if self.peek(0) != Some(')') {
+                        return Err(self.error_at("expecting ')'"));
+                    }
+
+                    self.position += 1;
+                    has_preceding_item = true;
+                }
+                '.' => {
+                    self.position += 1;
+                    has_preceding_item = true;
+                }
+                '*' | '+' | '?' | '{' => {
+                    if !has_preceding_item {
+                        return Err(self.error_at("expecting preceding item to */+/?/{"));
+                    }
+
+                    self.position += 1;
+
+                    if character == '{' {
+                        self.parse_space(is_nested);
+                        self.parse_repetition(is_nested)?;
+
+                        continue;
+                    }
+                }
+                _ if Self::is_word_char(Some(character)) => {
+                    let name = self.parse_name()?;
+
+                    self.referenced_rules.insert(name);
+                    has_preceding_item = true;
+                }
+                _ => break,
+            }
+
+            self.parse_space(is_nested);
+        }
+
+        Ok(())
+    }
+
+    fn parse_space(&mut self, newline_ok: bool) {
+        while let Some(character) = self.peek(0) {
+            match character {
+                ' ' | '\t' => self.position += 1,
+                '\r' | '\n' if newline_ok => self.position += 1,
+                '#' => {
+                    while self
+                        .peek(0)
+                        .is_some_and(|character| character != '\r' && character != '\n')
+                    {
+                        self.position += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_grammar_is_accepted() {
+        let grammar = r#"
+            # yes or no, optionally repeated
+            root ::= answer ("," ws answer){0,3}
+            answer ::= "yes" | "no" | [0-9a-fA-F]+ | "\u00e9"?
+            ws ::= [ \t\n]*
+        "#;
+
+        assert!(GbnfGrammarParser::check(grammar, "root").is_ok());
+        assert!(GbnfGrammarParser::check(grammar, "answer").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_grammars_are_rejected() {
+        for (grammar, root) in [
+            ("root ::= \"yes", "root"),
+            ("root ::= [a-z", "root"),
+            ("root ::= (\"yes\" | \"no\"", "root"),
+            ("root = \"yes\"", "root"),
+            ("root ::= * \"yes\"", "root"),
+            ("root ::= \"yes\"{1,", "root"),
+            ("root ::= \"\\q\"", "root"),
+            ("root ::= answer", "root"),
+            ("answer ::= \"yes\"", "answer"),
+            ("root ::= \"yes\"", "answer"),
+        ] {
+            assert!(
+                GbnfGrammarParser::check(grammar, root).is_err(),
+                "{grammar:?} should be rejected"
+            );
+        }
+    }
+}
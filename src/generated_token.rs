@@ -0,0 +1,14 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::generated_token_logprobs::GeneratedTokenLogprobs;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GeneratedToken {
+    /// Index of the completion this token belongs to, when more than one was requested
+    pub choice_index: u32,
+    pub content: String,
+    /// Only present if the request asked for log probabilities
+    pub logprobs: Option<GeneratedTokenLogprobs>,
+}
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::token_logprob::TokenLogprob;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GeneratedTokenLogprobs {
+    pub bytes: Vec<u8>,
+    pub logprob: f32,
+    pub token_id: i32,
+    /// Most probable alternatives at this position, most probable first
+    pub top_logprobs: Vec<TokenLogprob>,
+}
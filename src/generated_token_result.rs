@@ -1,21 +1,34 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::generated_token::GeneratedToken;
+use crate::generation_summary::GenerationSummary;
 use crate::streamable_result::StreamableResult;
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub enum GeneratedTokenResult {
     ChatTemplateError(String),
-    Done,
-    Token(String),
+    Done(GenerationSummary),
+    /// Request was rejected or failed before generating all the tokens
+    Error(String),
+    Token(GeneratedToken),
 }
 
 impl StreamableResult for GeneratedTokenResult {
     fn is_done(&self) -> bool {
         matches!(
             self,
-            GeneratedTokenResult::ChatTemplateError(_) | GeneratedTokenResult::Done
+            GeneratedTokenResult::ChatTemplateError(_)
+                | GeneratedTokenResult::Done(_)
+                | GeneratedTokenResult::Error(_)
+        )
+    }
+
+    fn is_error(&self) -> bool {
+        matches!(
+            self,
+            GeneratedTokenResult::ChatTemplateError(_) | GeneratedTokenResult::Error(_)
         )
     }
 }
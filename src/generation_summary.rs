@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenerationSummary {
+    /// Number of tokens the prompt was evaluated into
+    pub prompt_tokens: usize,
+    /// Seed the sampler was initialized with; pass it back to replay the generation
+    pub seed: u32,
+}
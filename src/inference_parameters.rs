@@ -1,9 +1,23 @@
+use std::ops::RangeInclusive;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
 use serde::Deserialize;
 use serde::Serialize;
 
 use crate::pooling_type::PoolingType;
+use crate::sampler_stage::SamplerStage;
+use crate::validates::Validates;
+
+const PROBABILITY_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+const TOKEN_PENALTY_RANGE: RangeInclusive<f32> = -2.0..=2.0;
+
+fn default_sampler_chain() -> Vec<SamplerStage> {
+    SamplerStage::default_chain()
+}
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct InferenceParameters {
     pub batch_n_tokens: usize,
@@ -18,6 +32,9 @@ pub struct InferenceParameters {
     /// Penalty for repeating tokens (1.0 = disabled)
     pub penalty_repeat: f32,
     pub pooling_type: PoolingType,
+    /// Ordered stages used to pick the next token
+    #[serde(default = "default_sampler_chain")]
+    pub sampler_chain: Vec<SamplerStage>,
     /// Adjust the randomness of the generated text (0.0 = greedy/deterministic)
     pub temperature: f32,
     /// Limit the next token selection to the K most probable tokens
@@ -26,6 +43,64 @@ pub struct InferenceParameters {
     pub top_p: f32,
 }
 
+impl InferenceParameters {
+    /// Names of the parameters that are out of their ranges, with the explanation
+    pub fn find_out_of_range_parameters(&self) -> Vec<(&'static str, &'static str)> {
+        [
+            (
+                self.batch_n_tokens > 0,
+                "batch_n_tokens",
+                "Batch size has to be greater than 0",
+            ),
+            (
+                self.context_size > 0,
+                "context_size",
+                "Context size has to be greater than 0",
+            ),
+            (
+                PROBABILITY_RANGE.contains(&self.min_p),
+                "min_p",
+                "Minimum probability has to be between 0 and 1",
+            ),
+            (
+                TOKEN_PENALTY_RANGE.contains(&self.penalty_frequency),
+                "penalty_frequency",
+                "Frequency penalty has to be between -2 and 2",
+            ),
+            (
+                self.penalty_last_n >= -1,
+                "penalty_last_n",
+                "Number of tokens to scan for repetitions has to be -1 (context size), 0 (disabled), or greater",
+            ),
+            (
+                TOKEN_PENALTY_RANGE.contains(&self.penalty_presence),
+                "penalty_presence",
+                "Presence penalty has to be between -2 and 2",
+            ),
+            (
+                self.penalty_repeat > 0.0,
+                "penalty_repeat",
+                "Repeat penalty has to be greater than 0 (1 disables it)",
+            ),
+            (
+                self.temperature >= 0.0,
+                "temperature",
+                "Temperature cannot be negative",
+            ),
+            (self.top_k >= 0, "top_k", "Top K cannot be negative"),
+            (
+                PROBABILITY_RANGE.contains(&self.top_p),
+                "top_p",
+                "Top P has to be between 0 and 1",
+            ),
+        ]
+        .into_iter()
+        .filter(|(is_valid, _, _)| !is_valid)
+        .map(|(_, name, message)| (name, message))
+        .collect()
+    }
+}
+
 impl Default for InferenceParameters {
     fn default() -> Self {
         Self {
@@ -38,9 +113,44 @@ impl Default for InferenceParameters {
             penalty_presence: 1.5,
             penalty_repeat: 1.0,
             pooling_type: PoolingType::Last,
+            sampler_chain: SamplerStage::default_chain(),
             temperature: 0.6,
             top_k: 40,
             top_p: 0.8,
         }
     }
 }
+
+impl Validates<InferenceParameters> for InferenceParameters {
+    fn validate(self) -> Result<InferenceParameters> {
+        if let Some((name, message)) = self.find_out_of_range_parameters().first() {
+            return Err(anyhow!("Invalid inference parameter '{name}': {message}"));
+        }
+
+        Ok(InferenceParameters {
+            sampler_chain: self
+                .sampler_chain
+                .validate()
+                .context("Invalid sampler chain")?,
+            ..self
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_rejects_out_of_range_parameters() {
+        assert!(InferenceParameters::default().validate().is_ok());
+        assert!(
+            InferenceParameters {
+                top_p: 1.5,
+                ..InferenceParameters::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use serde_json::Value;
+
+/// Applies a JSON merge patch (RFC 7396): objects are merged recursively,
+/// `null` removes a member, and any other value replaces the target.
+pub fn apply_json_merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch_members) => {
+            if !target.is_object() {
+                *target = Value::Object(serde_json::Map::new());
+            }
+
+            if let Value::Object(target_members) = target {
+                for (name, patch_value) in patch_members {
+                    if patch_value.is_null() {
+                        target_members.remove(name);
+                    } else {
+                        apply_json_merge_patch(
+                            target_members.entry(name.clone()).or_insert(Value::Null),
+                            patch_value,
+                        );
+                    }
+                }
+            }
+        }
+        _ => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_merge_patch_changes_only_given_members() {
+        let mut target = json!({
+            "inference_parameters": { "temperature": 0.6, "top_k": 40 },
+            "model": "None",
+        });
+
+        apply_json_merge_patch(
+            &mut target,
+            &json!({ "inference_parameters": { "temperature": 0.8 } }),
+        );
+
+        assert_eq!(
+            target,
+            json!({
+                "inference_parameters": { "temperature": 0.8, "top_k": 40 },
+                "model": "None",
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_patch_removes_null_members() {
+        let mut target = json!({ "canary": { "agents": 1 }, "model": "None" });
+
+        apply_json_merge_patch(&mut target, &json!({ "canary": null }));
+
+        assert_eq!(target, json!({ "model": "None" }));
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use anyhow::Error;
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Parses a single `key=value` agent label
+pub fn parse_label(label: &str) -> Result<(String, String)> {
+    match label.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(anyhow!(
+            "Label must be in the key=value format, got: '{label}'"
+        )),
+    }
+}
+
+/// Matches agents that have all of the listed labels. Empty selector matches every agent.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LabelSelector(pub BTreeMap<String, String>);
+
+impl LabelSelector {
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.0
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = Error;
+
+    /// Parses comma separated labels, for example: `gpu=a100,zone=eu1`
+    fn from_str(selector: &str) -> Result<Self> {
+        Ok(LabelSelector(
+            selector
+                .split(',')
+                .map(str::trim)
+                .filter(|label| !label.is_empty())
+                .map(parse_label)
+                .collect::<Result<BTreeMap<_, _>>>()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_selector() -> Result<()> {
+        let selector: LabelSelector = "gpu=a100, zone = eu1".parse()?;
+
+        assert_eq!(selector.0.get("gpu"), Some(&"a100".to_string()));
+        assert_eq!(selector.0.get("zone"), Some(&"eu1".to_string()));
+        assert!("gpu".parse::<LabelSelector>().is_err());
+        assert!("=a100".parse::<LabelSelector>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_all_labels() -> Result<()> {
+        let labels = BTreeMap::from([
+            ("gpu".to_string(), "a100".to_string()),
+            ("tier".to_string(), "batch".to_string()),
+        ]);
+
+        assert!(LabelSelector::default().matches(&labels));
+        assert!("gpu=a100".parse::<LabelSelector>()?.matches(&labels));
+        assert!(
+            "gpu=a100,tier=batch"
+                .parse::<LabelSelector>()?
+                .matches(&labels)
+        );
+        assert!(
+            !"gpu=a100,tier=interactive"
+                .parse::<LabelSelector>()?
+                .matches(&labels)
+        );
+        assert!(!"zone=eu1".parse::<LabelSelector>()?.matches(&labels));
+
+        Ok(())
+    }
+}
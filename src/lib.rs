@@ -3,15 +3,19 @@ pub mod agent_applicable_state;
 pub mod agent_applicable_state_holder;
 pub mod agent_desired_model;
 pub mod agent_desired_state;
+pub mod agent_desired_state_override;
 pub mod agent_issue;
 pub mod agent_issue_fix;
 pub mod agent_issue_params;
+pub mod agent_override_target;
 pub mod agent_state_application_status;
 pub mod atomic_value;
 pub mod balancer;
 pub mod balancer_applicable_state;
 pub mod balancer_applicable_state_holder;
 pub mod balancer_desired_state;
+pub mod canary_applicable_state;
+pub mod canary_deployment;
 pub mod chat_template;
 pub mod chat_template_renderer;
 pub mod cmd;
@@ -19,27 +23,49 @@ pub mod controls_session;
 pub mod controls_websocket_endpoint;
 pub mod conversation_message;
 pub mod converts_to_applicable_state;
+pub mod cors_allowed_hosts;
 pub mod create_cors_middleware;
+pub mod desired_slots;
 pub mod dispenses_slots;
+pub mod drains;
 pub mod embedding;
 pub mod embedding_input_document;
 pub mod embedding_input_tokenized;
 pub mod embedding_normalization_method;
 pub mod embedding_result;
+pub mod gbnf_grammar_parser;
+pub mod generated_token;
+pub mod generated_token_logprobs;
 pub mod generated_token_result;
+pub mod generation_summary;
 pub mod huggingface_model_reference;
 pub mod inference_parameters;
+pub mod json_merge_patch;
 pub mod jsonrpc;
+pub mod label_selector;
+pub mod listen_addr;
+pub mod logit_bias_token;
+pub mod logit_distribution;
+pub mod make_tls_client_config;
+pub mod make_tls_server_config;
 pub mod model_metadata;
 pub mod normalization;
 pub mod pooling_type;
 pub mod produces_snapshot;
 pub mod request_params;
 pub mod rpc_message;
+pub mod sampler_stage;
 pub mod sends_rpc_message;
 pub mod service;
+pub mod service_health;
+pub mod service_health_registry;
+pub mod service_health_registry_snapshot;
 pub mod service_manager;
+pub mod service_restart_policy;
+pub mod service_state;
 pub mod sets_desired_state;
+pub mod shadow_applicable_state;
+pub mod shadow_deployment;
 pub mod slot_aggregated_status;
 pub mod slot_aggregated_status_download_progress;
 pub mod slot_aggregated_status_manager;
@@ -49,5 +75,8 @@ pub mod slot_status;
 #[cfg(feature = "web_admin_panel")]
 pub mod static_files;
 pub mod streamable_result;
+pub mod tls_files;
+pub mod token_logit_bias;
+pub mod token_logprob;
 pub mod validates;
 pub mod websocket_session_controller;
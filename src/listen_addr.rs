@@ -0,0 +1,43 @@
+use std::fmt;
+use std::fs::Permissions;
+use std::fs::set_permissions;
+use std::net::SocketAddr;
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use anyhow::Result;
+
+pub const UNIX_SOCKET_PREFIX: &str = "unix:";
+
+/// Address that a service listens on, either a TCP address or a Unix domain socket
+/// (written as `unix:/path/to.sock`)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl ListenAddr {
+    /// Unix sockets are created when the server binds, so the permissions can only be set afterwards
+    pub fn set_unix_socket_mode(&self, unix_socket_mode: Option<u32>) -> Result<()> {
+        match (self, unix_socket_mode) {
+            (ListenAddr::Unix(path), Some(unix_socket_mode)) => {
+                set_permissions(path, Permissions::from_mode(unix_socket_mode)).context(format!(
+                    "Unable to set permissions of the Unix socket '{}'",
+                    path.display()
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddr::Tcp(socket_addr) => write!(formatter, "{socket_addr}"),
+            ListenAddr::Unix(path) => write!(formatter, "{UNIX_SOCKET_PREFIX}{}", path.display()),
+        }
+    }
+}
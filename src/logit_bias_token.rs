@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub enum LogitBiasToken {
+    Id(i32),
+    /// Has to be a single token of the loaded model, with or without a leading space.
+    /// The bias applies to both of these forms.
+    Text(String),
+}
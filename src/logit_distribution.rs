@@ -0,0 +1,105 @@
+/// Log-softmax over the raw logits of the model, read before the sampler chain
+/// runs so truncating samplers (greedy, top-k, min-p) do not collapse it to 0.0.
+pub struct LogitDistribution {
+    /// Token ids with their logits, without the non-finite ones
+    candidates: Vec<(i32, f32)>,
+    log_sum_exp: f32,
+}
+
+impl LogitDistribution {
+    pub fn new(candidates: impl IntoIterator<Item = (i32, f32)>) -> Self {
+        let candidates: Vec<(i32, f32)> = candidates
+            .into_iter()
+            .filter(|(_, logit)| logit.is_finite())
+            .collect();
+        let max_logit = candidates
+            .iter()
+            .map(|(_, logit)| *logit)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let sum_exp = candidates
+            .iter()
+            .fold(0.0, |acc, (_, logit)| acc + (logit - max_logit).exp());
+
+        Self {
+            candidates,
+            log_sum_exp: max_logit + sum_exp.ln(),
+        }
+    }
+
+    pub fn logprob(&self, token_id: i32) -> f32 {
+        match self
+            .candidates
+            .iter()
+            .find(|(candidate_token_id, _)| *candidate_token_id == token_id)
+        {
+            Some((_, logit)) => logit - self.log_sum_exp,
+            None => f32::NEG_INFINITY,
+        }
+    }
+
+    /// Returns up to `n` token ids with the highest probability, most probable first.
+    pub fn top(&self, n: usize) -> Vec<(i32, f32)> {
+        if n == 0 {
+            return vec![];
+        }
+
+        let mut indexed_logits = self.candidates.clone();
+        let n = n.min(indexed_logits.len());
+
+        if n < indexed_logits.len() {
+            indexed_logits.select_nth_unstable_by(n, |a, b| b.1.total_cmp(&a.1));
+            indexed_logits.truncate(n);
+        }
+
+        indexed_logits.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        indexed_logits
+            .into_iter()
+            .map(|(token_id, logit)| (token_id, logit - self.log_sum_exp))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logprobs_sum_to_one() {
+        let logits = vec![1.0, 2.0, 3.0, 0.5];
+        let distribution = LogitDistribution::new((0_i32..).zip(logits.iter().copied()));
+
+        let total: f32 = (0..logits.len() as i32)
+            .map(|token_id| distribution.logprob(token_id).exp())
+            .sum();
+
+        assert!((total - 1.0).abs() < 1e-5);
+        assert_eq!(distribution.logprob(10), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_masked_candidates_are_left_out() {
+        let distribution = LogitDistribution::new(vec![(3, 2.0), (7, f32::NEG_INFINITY), (9, 2.0)]);
+
+        assert!((distribution.logprob(3) - 0.5_f32.ln()).abs() < 1e-5);
+        assert_eq!(distribution.logprob(7), f32::NEG_INFINITY);
+        assert_eq!(distribution.top(5).len(), 2);
+    }
+
+    #[test]
+    fn test_top_is_sorted_and_limited() {
+        let logits = vec![1.0, 4.0, 3.0, 0.5, 2.0];
+        let distribution = LogitDistribution::new((0_i32..).zip(logits.iter().copied()));
+        let top = distribution.top(3);
+
+        assert_eq!(
+            top.iter()
+                .map(|(token_id, _)| *token_id)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 4]
+        );
+        assert!(top[0].1 > top[1].1);
+        assert_eq!(distribution.top(0).len(), 0);
+        assert_eq!(distribution.top(10).len(), 5);
+    }
+}
@@ -6,7 +6,9 @@ use esbuild_metafile::instance::initialize_instance;
 use log::info;
 use paddler::cmd::agent::Agent;
 use paddler::cmd::balancer::Balancer;
+use paddler::cmd::config::Config;
 use paddler::cmd::handler::Handler as _;
+use paddler::cmd::serve::Serve;
 use tokio::signal::unix::SignalKind;
 use tokio::signal::unix::signal;
 use tokio::sync::oneshot;
@@ -29,6 +31,10 @@ enum Commands {
     Agent(Agent),
     /// Distributes incoming requests among agents
     Balancer(Balancer),
+    /// Works with the configuration files
+    Config(Config),
+    /// Runs the balancer together with an in-process agent (for development and small deployments)
+    Serve(Serve),
 }
 
 #[actix_web::main]
@@ -42,10 +48,21 @@ async fn main() -> Result<()> {
         let mut sigint = signal(SignalKind::interrupt()).expect("Failed to listen for SIGINT");
         let mut sighup = signal(SignalKind::hangup()).expect("Failed to listen for SIGHUP");
 
-        tokio::select! {
-            _ = sigterm.recv() => info!("Received SIGTERM"),
-            _ = sigint.recv() => info!("Received SIGINT (Ctrl+C)"),
-            _ = sighup.recv() => info!("Received SIGHUP"),
+        loop {
+            tokio::select! {
+                _ = sigterm.recv() => {
+                    info!("Received SIGTERM");
+
+                    break;
+                }
+                _ = sigint.recv() => {
+                    info!("Received SIGINT (Ctrl+C)");
+
+                    break;
+                }
+                // Balancer reloads its configuration, agents have nothing to reload
+                _ = sighup.recv() => info!("Received SIGHUP"),
+            }
         }
 
         shutdown_tx
@@ -61,6 +78,13 @@ async fn main() -> Result<()> {
 
             Ok(handler.handle(shutdown_rx).await?)
         }
+        Some(Commands::Config(handler)) => Ok(handler.handle(shutdown_rx).await?),
+        Some(Commands::Serve(handler)) => {
+            #[cfg(feature = "web_admin_panel")]
+            initialize_instance(ESBUILD_META_CONTENTS);
+
+            Ok(handler.handle(shutdown_rx).await?)
+        }
         None => Ok(()),
     }
 }
@@ -0,0 +1,27 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use rustls::ClientConfig;
+use rustls::crypto::ring;
+
+use crate::tls_files::read_certificates;
+use crate::tls_files::read_private_key;
+use crate::tls_files::read_root_cert_store;
+
+/// Trusts only the given certificate authority (pinned), and optionally presents
+/// a client certificate for mutual TLS
+pub fn make_tls_client_config(
+    ca_cert_path: &Path,
+    client_certificate: Option<(&Path, &Path)>,
+) -> Result<ClientConfig> {
+    let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(read_root_cert_store(ca_cert_path)?);
+
+    match client_certificate {
+        Some((cert_path, key_path)) => Ok(builder
+            .with_client_auth_cert(read_certificates(cert_path)?, read_private_key(key_path)?)?),
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use rustls::ServerConfig;
+use rustls::crypto::ring;
+use rustls::server::WebPkiClientVerifier;
+
+use crate::tls_files::read_certificates;
+use crate::tls_files::read_private_key;
+use crate::tls_files::read_root_cert_store;
+
+/// With a client certificate authority, clients may present a certificate signed by it.
+/// Clients without a certificate can still connect, so the endpoints that require one
+/// have to check for it.
+pub fn make_tls_server_config(
+    cert_path: &Path,
+    key_path: &Path,
+    client_ca_path: Option<&Path>,
+) -> Result<ServerConfig> {
+    let crypto_provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(crypto_provider.clone())
+        .with_safe_default_protocol_versions()?;
+    let builder = match client_ca_path {
+        Some(client_ca_path) => builder.with_client_cert_verifier(
+            WebPkiClientVerifier::builder_with_provider(
+                Arc::new(read_root_cert_store(client_ca_path)?),
+                crypto_provider,
+            )
+            .allow_unauthenticated()
+            .build()?,
+        ),
+        None => builder.with_no_client_auth(),
+    };
+
+    builder
+        .with_single_cert(read_certificates(cert_path)?, read_private_key(key_path)?)
+        .context(format!(
+            "TLS key '{}' does not match the certificate '{}'",
+            key_path.display(),
+            cert_path.display()
+        ))
+}
@@ -7,18 +7,43 @@ use serde::Serialize;
 use self::tool::Tool;
 use crate::validates::Validates;
 use crate::conversation_message::ConversationMessage;
+use crate::sampler_stage::SamplerStage;
+use crate::token_logit_bias::TokenLogitBias;
 use crate::request_params::continue_from_conversation_history_params::tool::tool_params::function_call::parameters_schema::raw_parameters_schema::RawParametersSchema;
 use crate::request_params::continue_from_conversation_history_params::tool::tool_params::function_call::parameters_schema::validated_parameters_schema::ValidatedParametersSchema;
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+fn default_n() -> u32 {
+    1
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct ContinueFromConversationHistoryParams<TParametersSchema: Default> {
     pub add_generation_prompt: bool,
     pub conversation_history: Vec<ConversationMessage>,
     pub enable_thinking: bool,
+    /// Biases applied to the logits before any other sampler stage
+    #[serde(default)]
+    pub logit_bias: Vec<TokenLogitBias>,
+    /// Return the log probability of each generated token under the model's own
+    /// distribution, before the sampler chain (including logit biases) is applied
+    #[serde(default)]
+    pub logprobs: bool,
     pub max_tokens: i32,
+    /// Number of completions to sample from a single evaluation of the prompt
+    #[serde(default = "default_n")]
+    pub n: u32,
+    /// Replaces the sampler chain configured in the inference parameters for this request
+    #[serde(default)]
+    pub sampler_chain: Option<Vec<SamplerStage>>,
+    /// Seed for the sampler; the same seed, model and parameters reproduce the same output
+    #[serde(default)]
+    pub seed: Option<u32>,
     #[serde(default)]
     pub tools: Vec<Tool<TParametersSchema>>,
+    /// Number of most probable alternatives to return with each token (requires `logprobs`)
+    #[serde(default)]
+    pub top_logprobs: usize,
 }
 
 impl Validates<ContinueFromConversationHistoryParams<ValidatedParametersSchema>>
@@ -29,12 +54,25 @@ impl Validates<ContinueFromConversationHistoryParams<ValidatedParametersSchema>>
             add_generation_prompt: self.add_generation_prompt,
             conversation_history: self.conversation_history,
             enable_thinking: self.enable_thinking,
+            logit_bias: self
+                .logit_bias
+                .into_iter()
+                .map(|token_logit_bias| token_logit_bias.validate())
+                .collect::<Result<Vec<_>>>()?,
+            logprobs: self.logprobs,
             max_tokens: self.max_tokens,
+            n: self.n,
+            sampler_chain: self
+                .sampler_chain
+                .map(|sampler_chain| sampler_chain.validate())
+                .transpose()?,
+            seed: self.seed,
             tools: self
                 .tools
                 .into_iter()
                 .map(|tool| tool.validate())
                 .collect::<Result<Vec<_>>>()?,
+            top_logprobs: self.top_logprobs,
         })
     }
 }
@@ -1,9 +1,35 @@
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+use crate::sampler_stage::SamplerStage;
+use crate::token_logit_bias::TokenLogitBias;
+
+fn default_n() -> u32 {
+    1
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct ContinueFromRawPromptParams {
+    /// Biases applied to the logits before any other sampler stage
+    #[serde(default)]
+    pub logit_bias: Vec<TokenLogitBias>,
+    /// Return the log probability of each generated token under the model's own
+    /// distribution, before the sampler chain (including logit biases) is applied
+    #[serde(default)]
+    pub logprobs: bool,
     pub max_tokens: i32,
+    /// Number of completions to sample from a single evaluation of the prompt
+    #[serde(default = "default_n")]
+    pub n: u32,
     pub raw_prompt: String,
+    /// Replaces the sampler chain configured in the inference parameters for this request
+    #[serde(default)]
+    pub sampler_chain: Option<Vec<SamplerStage>>,
+    /// Seed for the sampler; the same seed, model and parameters reproduce the same output
+    #[serde(default)]
+    pub seed: Option<u32>,
+    /// Number of most probable alternatives to return with each token (requires `logprobs`)
+    #[serde(default)]
+    pub top_logprobs: usize,
 }
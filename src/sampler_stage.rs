@@ -0,0 +1,181 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::gbnf_grammar_parser::GbnfGrammarParser;
+use crate::token_logit_bias::TokenLogitBias;
+use crate::validates::Validates;
+
+/// A single stage of the sampler chain. Stages are applied in order, and the
+/// next token is picked from whatever candidates are left after the last one.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub enum SamplerStage {
+    /// "Don't Repeat Yourself" - penalizes tokens that would extend repeated sequences
+    Dry {
+        allowed_length: i32,
+        base: f32,
+        multiplier: f32,
+        /// How many tokens to scan for repetitions (-1 = context size, 0 = disabled)
+        penalty_last_n: i32,
+        sequence_breakers: Vec<String>,
+    },
+    /// Constrain the output with a GBNF grammar
+    Grammar {
+        grammar: String,
+        root: String,
+    },
+    LogitBias(Vec<TokenLogitBias>),
+    /// Uses `min_p` from the inference parameters
+    MinP,
+    /// Picks the next token itself, so it has to be the last stage
+    Mirostat {
+        eta: f32,
+        m: i32,
+        tau: f32,
+    },
+    /// Picks the next token itself, so it has to be the last stage
+    MirostatV2 {
+        eta: f32,
+        tau: f32,
+    },
+    /// Uses `penalty_*` values from the inference parameters
+    Penalties,
+    /// Uses `temperature` from the inference parameters
+    Temperature,
+    /// Uses `top_k` from the inference parameters
+    TopK,
+    /// Uses `top_p` from the inference parameters
+    TopP,
+    /// Locally typical sampling
+    TypicalP {
+        p: f32,
+    },
+    /// "Exclude Top Choices" - sometimes removes the most probable tokens to make the output less predictable
+    Xtc {
+        probability: f32,
+        threshold: f32,
+    },
+}
+
+impl SamplerStage {
+    pub fn default_chain() -> Vec<SamplerStage> {
+        vec![
+            SamplerStage::Penalties,
+            SamplerStage::TopK,
+            SamplerStage::TopP,
+            SamplerStage::MinP,
+            SamplerStage::Temperature,
+        ]
+    }
+
+    pub fn selects_token(&self) -> bool {
+        matches!(
+            self,
+            SamplerStage::Mirostat { .. } | SamplerStage::MirostatV2 { .. }
+        )
+    }
+}
+
+impl Validates<SamplerStage> for SamplerStage {
+    fn validate(self) -> Result<SamplerStage> {
+        match &self {
+            SamplerStage::Dry {
+                allowed_length,
+                base,
+                multiplier,
+                penalty_last_n,
+                ..
+            } => {
+                if *allowed_length < 0 {
+                    return Err(anyhow!("DRY allowed_length must not be negative"));
+                }
+
+                if *base < 1.0 {
+                    return Err(anyhow!("DRY base must be at least 1.0"));
+                }
+
+                if *multiplier < 0.0 {
+                    return Err(anyhow!("DRY multiplier must not be negative"));
+                }
+
+                if *penalty_last_n < -1 {
+                    return Err(anyhow!("DRY penalty_last_n must be -1 or greater"));
+                }
+            }
+            SamplerStage::Grammar { grammar, root } => {
+                if grammar.trim().is_empty() {
+                    return Err(anyhow!("Grammar must not be empty"));
+                }
+
+                if root.trim().is_empty() {
+                    return Err(anyhow!("Grammar root rule name must not be empty"));
+                }
+
+                GbnfGrammarParser::check(grammar, root)?;
+            }
+            SamplerStage::LogitBias(biases) => {
+                for token_logit_bias in biases {
+                    token_logit_bias.clone().validate()?;
+                }
+            }
+            SamplerStage::Mirostat { eta, m, tau } => {
+                if *eta <= 0.0 || *tau <= 0.0 {
+                    return Err(anyhow!("Mirostat eta and tau must be positive"));
+                }
+
+                if *m <= 0 {
+                    return Err(anyhow!("Mirostat m must be positive"));
+                }
+            }
+            SamplerStage::MirostatV2 { eta, tau } => {
+                if *eta <= 0.0 || *tau <= 0.0 {
+                    return Err(anyhow!("Mirostat v2 eta and tau must be positive"));
+                }
+            }
+            SamplerStage::TypicalP { p } => {
+                if *p <= 0.0 || *p > 1.0 {
+                    return Err(anyhow!("Typical-p must be in the (0, 1] range"));
+                }
+            }
+            SamplerStage::Xtc {
+                probability,
+                threshold,
+            } => {
+                if !(0.0..=1.0).contains(probability) {
+                    return Err(anyhow!("XTC probability must be in the [0, 1] range"));
+                }
+
+                if !(0.0..=0.5).contains(threshold) {
+                    return Err(anyhow!("XTC threshold must be in the [0, 0.5] range"));
+                }
+            }
+            SamplerStage::MinP
+            | SamplerStage::Penalties
+            | SamplerStage::Temperature
+            | SamplerStage::TopK
+            | SamplerStage::TopP => {}
+        }
+
+        Ok(self)
+    }
+}
+
+impl Validates<Vec<SamplerStage>> for Vec<SamplerStage> {
+    fn validate(self) -> Result<Vec<SamplerStage>> {
+        if self
+            .iter()
+            .position(SamplerStage::selects_token)
+            .is_some_and(|position| position != self.len() - 1)
+        {
+            return Err(anyhow!(
+                "Mirostat samplers pick the next token themselves and have to be the last stage of the chain"
+            ));
+        }
+
+        self.into_iter()
+            .map(|stage| stage.validate())
+            .collect::<Result<Vec<_>>>()
+    }
+}
@@ -2,9 +2,15 @@ use anyhow::Result;
 use async_trait::async_trait;
 use tokio::sync::broadcast;
 
+use crate::service_restart_policy::ServiceRestartPolicy;
+
 #[async_trait]
 pub trait Service: Send + 'static {
     fn name(&self) -> &'static str;
 
+    fn restart_policy(&self) -> ServiceRestartPolicy {
+        ServiceRestartPolicy::OnFailure
+    }
+
     async fn run(&mut self, shutdown_rx: broadcast::Receiver<()>) -> Result<()>;
 }
@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+use crate::service_restart_policy::ServiceRestartPolicy;
+use crate::service_state::ServiceState;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServiceHealth {
+    pub last_error: Option<String>,
+    pub restart_policy: ServiceRestartPolicy,
+    pub restarts: u32,
+    pub state: ServiceState,
+}
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use anyhow::Result;
+
+use crate::produces_snapshot::ProducesSnapshot;
+use crate::service_health::ServiceHealth;
+use crate::service_health_registry_snapshot::ServiceHealthRegistrySnapshot;
+use crate::service_restart_policy::ServiceRestartPolicy;
+use crate::service_state::ServiceState;
+
+/// States of the services supervised by the service manager
+#[derive(Default)]
+pub struct ServiceHealthRegistry {
+    services: RwLock<BTreeMap<String, ServiceHealth>>,
+}
+
+impl ServiceHealthRegistry {
+    pub fn is_healthy(&self) -> bool {
+        self.services
+            .read()
+            .expect("Poisoned lock on service health registry")
+            .values()
+            .all(|service_health| service_health.state != ServiceState::Failed)
+    }
+
+    pub fn record_error(&self, service_name: &str, error: String) {
+        self.update(service_name, |service_health| {
+            service_health.last_error = Some(error);
+        });
+    }
+
+    pub fn record_restart(&self, service_name: &str) {
+        self.update(service_name, |service_health| {
+            service_health.restarts += 1;
+            service_health.state = ServiceState::Restarting;
+        });
+    }
+
+    pub fn register(&self, service_name: &str, restart_policy: ServiceRestartPolicy) {
+        self.services
+            .write()
+            .expect("Poisoned lock on service health registry")
+            .insert(
+                service_name.to_string(),
+                ServiceHealth {
+                    last_error: None,
+                    restart_policy,
+                    restarts: 0,
+                    state: ServiceState::Running,
+                },
+            );
+    }
+
+    pub fn set_state(&self, service_name: &str, state: ServiceState) {
+        self.update(service_name, |service_health| {
+            service_health.state = state;
+        });
+    }
+
+    fn update<TModifier>(&self, service_name: &str, modifier: TModifier)
+    where
+        TModifier: FnOnce(&mut ServiceHealth),
+    {
+        if let Some(service_health) = self
+            .services
+            .write()
+            .expect("Poisoned lock on service health registry")
+            .get_mut(service_name)
+        {
+            modifier(service_health);
+        }
+    }
+}
+
+impl ProducesSnapshot for ServiceHealthRegistry {
+    type Snapshot = ServiceHealthRegistrySnapshot;
+
+    fn make_snapshot(&self) -> Result<Self::Snapshot> {
+        Ok(ServiceHealthRegistrySnapshot {
+            is_healthy: self.is_healthy(),
+            services: self
+                .services
+                .read()
+                .expect("Poisoned lock on service health registry")
+                .clone(),
+        })
+    }
+}
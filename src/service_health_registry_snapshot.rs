@@ -0,0 +1,12 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::service_health::ServiceHealth;
+
+#[derive(Debug, Serialize)]
+pub struct ServiceHealthRegistrySnapshot {
+    /// False if any of the services failed for good
+    pub is_healthy: bool,
+    pub services: BTreeMap<String, ServiceHealth>,
+}
@@ -1,64 +1,346 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use actix_web::rt;
 use anyhow::Result;
+use anyhow::anyhow;
 use futures::future::join_all;
 use log::error;
 use log::info;
+use log::warn;
 use tokio::sync::broadcast;
+use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tokio::time::Instant;
+use tokio::time::sleep;
+use tokio::time::timeout;
 
+use crate::drains::Drains;
 use crate::service::Service;
+use crate::service_health_registry::ServiceHealthRegistry;
+use crate::service_restart_policy::ServiceRestartPolicy;
+use crate::service_state::ServiceState;
+
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+
+fn restart_backoff(consecutive_restarts: u32) -> Duration {
+    INITIAL_RESTART_BACKOFF
+        .saturating_mul(2u32.saturating_pow(consecutive_restarts.saturating_sub(1)))
+        .min(MAX_RESTART_BACKOFF)
+}
 
 #[derive(Default)]
 pub struct ServiceManager {
+    drains: Vec<(Arc<dyn Drains>, Duration)>,
+    service_health_registry: Arc<ServiceHealthRegistry>,
     services: Vec<Box<dyn Service>>,
 }
 
 impl ServiceManager {
+    /// Work in progress is drained (up to the timeout) before the services are told to shut down
+    pub fn add_drain(&mut self, drains: Arc<dyn Drains>, drain_timeout: Duration) {
+        self.drains.push((drains, drain_timeout));
+    }
+
     pub fn add_service<TService: Service>(&mut self, service: TService) {
         self.services.push(Box::new(service));
     }
 
+    pub fn get_service_health_registry(&self) -> Arc<ServiceHealthRegistry> {
+        self.service_health_registry.clone()
+    }
+
     pub async fn run_forever(self, shutdown_rx: oneshot::Receiver<()>) -> Result<()> {
         let (shutdown_broadcast_tx, _) = broadcast::channel::<()>(1);
-        let shutdown_broadcast_tx_arc = Arc::new(shutdown_broadcast_tx.clone());
+        let (stopped_tx, mut stopped_rx) = mpsc::unbounded_channel::<Result<()>>();
         let mut service_handles = Vec::with_capacity(self.services.len());
 
-        for mut service in self.services {
-            let service_name = service.name().to_string();
-            let shutdown_broadcast_tx_arc_clone = shutdown_broadcast_tx_arc.clone();
+        for service in self.services {
+            self.service_health_registry
+                .register(service.name(), service.restart_policy());
 
-            service_handles.push(rt::spawn(async move {
-                info!("{service_name}: Starting");
+            service_handles.push(rt::spawn(supervise(
+                shutdown_broadcast_tx.subscribe(),
+                service,
+                self.service_health_registry.clone(),
+                shutdown_broadcast_tx.clone(),
+                stopped_tx.clone(),
+            )));
+        }
 
-                let mut manager_shutdown_rx = shutdown_broadcast_tx_arc_clone.subscribe();
-                let service_shutdown_rx = shutdown_broadcast_tx_arc_clone.subscribe();
+        // Services that stopped for good decide if the process should keep running
+        let result = tokio::select! {
+            shutdown = shutdown_rx => {
+                shutdown?;
 
-                tokio::select! {
-                    _ = manager_shutdown_rx.recv() => {
-                        info!("{service_name}: Received shutdown signal");
-                    }
-                    result = service.run(service_shutdown_rx) => {
-                        match result {
-                            Ok(()) => {
-                                info!("{service_name}: Stopped");
-
-                                if let Err(err) = shutdown_broadcast_tx_arc_clone.send(()) {
-                                    error!("{service_name}: Failed to send shutdown signal: {err}");
-                                }
-                            }
-                            Err(err) => error!("{service_name}: {err}"),
-                        }
+                for (drains, drain_timeout) in self.drains {
+                    info!("Draining work in progress (for up to {drain_timeout:?})");
+
+                    match timeout(drain_timeout, drains.drain()).await {
+                        Ok(()) => info!("Drained work in progress"),
+                        Err(_) => warn!("Drain deadline exceeded, shutting down with work still in progress"),
                     }
                 }
-            }));
-        }
 
-        shutdown_rx.await?;
-        shutdown_broadcast_tx.send(())?;
+                Ok(())
+            }
+            stopped = stopped_rx.recv() => stopped.unwrap_or(Ok(())),
+        };
+
+        // Nothing is listening anymore if every service already stopped on its own
+        let _ = shutdown_broadcast_tx.send(());
+
         join_all(service_handles).await;
 
+        result
+    }
+}
+
+/// `manager_shutdown_rx` is subscribed before the task starts, so it does not miss the shutdown
+async fn supervise(
+    mut manager_shutdown_rx: broadcast::Receiver<()>,
+    mut service: Box<dyn Service>,
+    service_health_registry: Arc<ServiceHealthRegistry>,
+    shutdown_broadcast_tx: broadcast::Sender<()>,
+    stopped_tx: mpsc::UnboundedSender<Result<()>>,
+) {
+    let service_name = service.name().to_string();
+    let restart_policy = service.restart_policy();
+    let mut consecutive_restarts: u32 = 0;
+
+    loop {
+        let service_shutdown_rx = shutdown_broadcast_tx.subscribe();
+
+        if manager_shutdown_rx.try_recv().is_ok() {
+            service_health_registry.set_state(&service_name, ServiceState::Stopped);
+
+            return;
+        }
+
+        info!("{service_name}: Starting");
+
+        service_health_registry.set_state(&service_name, ServiceState::Running);
+
+        let started_at = Instant::now();
+        // Services handle the shutdown signal themselves, so they can finish cleaning up
+        let result = service.run(service_shutdown_rx).await;
+
+        let is_failure = match &result {
+            Ok(()) => {
+                info!("{service_name}: Stopped");
+
+                false
+            }
+            Err(err) => {
+                error!("{service_name}: {err}");
+
+                service_health_registry.record_error(&service_name, format!("{err:#}"));
+
+                true
+            }
+        };
+
+        if manager_shutdown_rx.try_recv().is_ok() {
+            info!("{service_name}: Received shutdown signal");
+
+            service_health_registry.set_state(
+                &service_name,
+                if is_failure {
+                    ServiceState::Failed
+                } else {
+                    ServiceState::Stopped
+                },
+            );
+
+            return;
+        }
+
+        let should_restart = match restart_policy {
+            ServiceRestartPolicy::Always => true,
+            ServiceRestartPolicy::Fatal => false,
+            ServiceRestartPolicy::OnFailure => is_failure,
+        };
+
+        if !should_restart {
+            service_health_registry.set_state(
+                &service_name,
+                if is_failure {
+                    ServiceState::Failed
+                } else {
+                    ServiceState::Stopped
+                },
+            );
+
+            if let Err(err) = stopped_tx
+                .send(result.map_err(|err| anyhow!("{service_name} failed, stopping: {err:#}")))
+            {
+                error!("{service_name}: Failed to send shutdown signal: {err}");
+            }
+
+            return;
+        }
+
+        // Service that worked for a while before stopping starts over with the shortest backoff
+        if started_at.elapsed() > MAX_RESTART_BACKOFF {
+            consecutive_restarts = 0;
+        }
+
+        consecutive_restarts += 1;
+
+        let backoff = restart_backoff(consecutive_restarts);
+
+        warn!("{service_name}: Restarting in {backoff:?}");
+
+        service_health_registry.record_restart(&service_name);
+
+        tokio::select! {
+            _ = manager_shutdown_rx.recv() => {
+                service_health_registry.set_state(&service_name, ServiceState::Stopped);
+
+                return;
+            }
+            _ = sleep(backoff) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::produces_snapshot::ProducesSnapshot as _;
+
+    struct FailingService {
+        runs: Arc<AtomicUsize>,
+        restart_policy: ServiceRestartPolicy,
+    }
+
+    #[async_trait]
+    impl Service for FailingService {
+        fn name(&self) -> &'static str {
+            "test::failing_service"
+        }
+
+        fn restart_policy(&self) -> ServiceRestartPolicy {
+            self.restart_policy
+        }
+
+        async fn run(&mut self, _shutdown_rx: broadcast::Receiver<()>) -> Result<()> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+
+            Err(anyhow!("Service failed"))
+        }
+    }
+
+    struct CleaningUpService {
+        is_cleaned_up: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl Service for CleaningUpService {
+        fn name(&self) -> &'static str {
+            "test::cleaning_up_service"
+        }
+
+        async fn run(&mut self, mut shutdown_rx: broadcast::Receiver<()>) -> Result<()> {
+            let _ = shutdown_rx.recv().await;
+
+            sleep(Duration::from_millis(10)).await;
+
+            self.is_cleaned_up.store(true, Ordering::SeqCst);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_restart_backoff_grows_up_to_the_limit() {
+        assert_eq!(restart_backoff(1), Duration::from_secs(1));
+        assert_eq!(restart_backoff(2), Duration::from_secs(2));
+        assert_eq!(restart_backoff(3), Duration::from_secs(4));
+        assert_eq!(restart_backoff(100), MAX_RESTART_BACKOFF);
+    }
+
+    #[actix_web::test]
+    async fn test_failing_service_is_restarted() -> Result<()> {
+        tokio::time::pause();
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut service_manager = ServiceManager::default();
+        let service_health_registry = service_manager.get_service_health_registry();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+        service_manager.add_service(FailingService {
+            runs: runs.clone(),
+            restart_policy: ServiceRestartPolicy::OnFailure,
+        });
+
+        let service_manager_handle = rt::spawn(service_manager.run_forever(shutdown_rx));
+
+        sleep(Duration::from_secs(4)).await;
+
+        assert!(runs.load(Ordering::SeqCst) >= 3);
+
+        let snapshot = service_health_registry.make_snapshot()?;
+        let service_health = &snapshot.services["test::failing_service"];
+
+        assert!(snapshot.is_healthy);
+        assert!(service_health.restarts >= 2);
+        assert_eq!(
+            service_health.last_error,
+            Some("Service failed".to_string())
+        );
+
+        shutdown_tx
+            .send(())
+            .expect("Service manager should be running");
+        service_manager_handle.await??;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_fatal_service_stops_the_process() {
+        let mut service_manager = ServiceManager::default();
+        let service_health_registry = service_manager.get_service_health_registry();
+        let (_shutdown_tx, shutdown_rx) = oneshot::channel();
+
+        service_manager.add_service(FailingService {
+            runs: Arc::new(AtomicUsize::new(0)),
+            restart_policy: ServiceRestartPolicy::Fatal,
+        });
+
+        assert!(service_manager.run_forever(shutdown_rx).await.is_err());
+        assert!(!service_health_registry.is_healthy());
+    }
+
+    #[actix_web::test]
+    async fn test_service_finishes_its_shutdown() -> Result<()> {
+        let is_cleaned_up = Arc::new(AtomicBool::new(false));
+        let mut service_manager = ServiceManager::default();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+        service_manager.add_service(CleaningUpService {
+            is_cleaned_up: is_cleaned_up.clone(),
+        });
+
+        let service_manager_handle = rt::spawn(service_manager.run_forever(shutdown_rx));
+
+        sleep(Duration::from_millis(10)).await;
+
+        shutdown_tx
+            .send(())
+            .expect("Service manager should be running");
+        service_manager_handle.await??;
+
+        assert!(is_cleaned_up.load(Ordering::SeqCst));
+
         Ok(())
     }
 }
@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum ServiceRestartPolicy {
+    /// Restart whenever the service stops, with a backoff
+    Always,
+    /// Stop the whole process when the service stops or fails
+    Fatal,
+    /// Restart after errors, with a backoff; stopping without an error stops the process
+    OnFailure,
+}
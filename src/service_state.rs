@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum ServiceState {
+    /// Failed and is not going to be restarted
+    Failed,
+    /// Waiting for the backoff to pass before starting again
+    Restarting,
+    Running,
+    Stopped,
+}
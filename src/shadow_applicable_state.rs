@@ -0,0 +1,10 @@
+use crate::agent_desired_state::AgentDesiredState;
+use crate::label_selector::LabelSelector;
+
+#[derive(Clone, Debug)]
+pub struct ShadowApplicableState {
+    pub agent_selector: LabelSelector,
+    pub agent_desired_state: AgentDesiredState,
+    pub agents: usize,
+    pub mirror_percentage: u8,
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::label_selector::LabelSelector;
+use crate::validates::Validates;
+
+/// Candidate desired state that runs on a subset of agents and only receives
+/// copies of live requests. Its responses are never returned to clients.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShadowDeployment {
+    /// Only agents with matching labels can run the shadow desired state
+    #[serde(default)]
+    pub agent_selector: LabelSelector,
+    /// How many agents should run the shadow desired state
+    pub agents: usize,
+    pub desired_state: Box<BalancerDesiredState>,
+    /// Percentage (0-100) of live requests mirrored to the shadow agents
+    pub mirror_percentage: u8,
+}
+
+impl Validates<ShadowDeployment> for ShadowDeployment {
+    fn validate(self) -> Result<ShadowDeployment> {
+        if self.desired_state.canary.is_some() || self.desired_state.shadow.is_some() {
+            return Err(anyhow!(
+                "Shadow deployment cannot have its own canary or shadow deployment"
+            ));
+        }
+
+        if self.mirror_percentage > 100 {
+            return Err(anyhow!(
+                "Shadow mirror_percentage must be between 0 and 100"
+            ));
+        }
+
+        Ok(ShadowDeployment {
+            desired_state: Box::new(self.desired_state.validate()?),
+            ..self
+        })
+    }
+}
@@ -16,10 +16,19 @@ use crate::produces_snapshot::ProducesSnapshot;
 use crate::slot_aggregated_status_snapshot::SlotAggregatedStatusSnapshot;
 
 pub struct SlotAggregatedStatus {
-    desired_slots_total: i32,
+    applied_desired_state_generation: AtomicValue<AtomicI32>,
+    /// Generation of the most recent desired state the agent received
+    desired_state_generation: AtomicValue<AtomicI32>,
+    desired_slots_total: AtomicValue<AtomicI32>,
     download_current: AtomicValue<AtomicUsize>,
     download_filename: RwLock<Option<String>>,
     download_total: AtomicValue<AtomicUsize>,
+    /// Agent received a new desired state that is not applied yet
+    is_desired_state_pending: AtomicValue<AtomicBool>,
+    /// Agent does not accept new requests and waits for the in-flight ones to finish
+    is_draining: AtomicValue<AtomicBool>,
+    /// Agent drains before it shuts down; unlike a state change drain, it is never cleared
+    is_shutting_down: AtomicValue<AtomicBool>,
     issues: DashSet<AgentIssue>,
     model_path: RwLock<Option<String>>,
     slots_processing: AtomicValue<AtomicI32>,
@@ -33,10 +42,15 @@ pub struct SlotAggregatedStatus {
 impl SlotAggregatedStatus {
     pub fn new(desired_slots_total: i32) -> Self {
         Self {
-            desired_slots_total,
+            applied_desired_state_generation: AtomicValue::<AtomicI32>::new(0),
+            desired_slots_total: AtomicValue::<AtomicI32>::new(desired_slots_total),
+            desired_state_generation: AtomicValue::<AtomicI32>::new(0),
             download_current: AtomicValue::<AtomicUsize>::new(0),
             download_filename: RwLock::new(None),
             download_total: AtomicValue::<AtomicUsize>::new(0),
+            is_desired_state_pending: AtomicValue::<AtomicBool>::new(false),
+            is_draining: AtomicValue::<AtomicBool>::new(false),
+            is_shutting_down: AtomicValue::<AtomicBool>::new(false),
             issues: DashSet::new(),
             model_path: RwLock::new(None),
             state_application_status_code: AtomicValue::<AtomicI32>::new(
@@ -56,6 +70,14 @@ impl SlotAggregatedStatus {
         self.update_notifier.notify_waiters();
     }
 
+    pub fn get_desired_state_generation(&self) -> i32 {
+        self.desired_state_generation.get()
+    }
+
+    pub fn get_slots_processing(&self) -> i32 {
+        self.slots_processing.get()
+    }
+
     pub fn get_state_application_status(&self) -> Result<AgentStateApplicationStatus> {
         self.state_application_status_code.get().try_into()
     }
@@ -117,6 +139,27 @@ impl SlotAggregatedStatus {
         self.update_notifier.notify_waiters();
     }
 
+    pub fn set_applied_desired_state_generation(&self, applied_desired_state_generation: i32) {
+        if self
+            .applied_desired_state_generation
+            .set_check(applied_desired_state_generation)
+        {
+            self.version.increment();
+            self.update_notifier.notify_waiters();
+        }
+    }
+
+    pub fn set_desired_slots_total(&self, desired_slots_total: i32) {
+        if self.desired_slots_total.set_check(desired_slots_total) {
+            self.version.increment();
+            self.update_notifier.notify_waiters();
+        }
+    }
+
+    pub fn set_desired_state_generation(&self, desired_state_generation: i32) {
+        self.desired_state_generation.set(desired_state_generation);
+    }
+
     pub fn set_download_status(&self, current: usize, total: usize, filename: Option<String>) {
         self.download_current.set(current);
         self.download_total.set(total);
@@ -135,6 +178,24 @@ impl SlotAggregatedStatus {
         self.update_notifier.notify_waiters();
     }
 
+    pub fn set_is_desired_state_pending(&self, is_desired_state_pending: bool) {
+        self.is_desired_state_pending.set(is_desired_state_pending);
+        self.version.increment();
+        self.update_notifier.notify_waiters();
+    }
+
+    pub fn set_is_draining(&self, is_draining: bool) {
+        self.is_draining.set(is_draining);
+        self.version.increment();
+        self.update_notifier.notify_waiters();
+    }
+
+    pub fn set_is_shutting_down(&self) {
+        self.is_shutting_down.set(true);
+        self.version.increment();
+        self.update_notifier.notify_waiters();
+    }
+
     pub fn set_model_path(&self, model_path: Option<String>) {
         let mut path_lock = self.model_path.write().unwrap_or_else(|err| {
             panic!("Lock poisoned when setting model path: {model_path:?}, error: {err:?}")
@@ -178,8 +239,9 @@ impl ProducesSnapshot for SlotAggregatedStatus {
 
     fn make_snapshot(&self) -> Result<Self::Snapshot> {
         Ok(SlotAggregatedStatusSnapshot {
+            applied_desired_state_generation: self.applied_desired_state_generation.get(),
             issues: self.issues.iter().map(|item| item.clone()).collect(),
-            desired_slots_total: self.desired_slots_total,
+            desired_slots_total: self.desired_slots_total.get(),
             download_current: self.download_current.get(),
             download_filename: self
                 .download_filename
@@ -187,6 +249,8 @@ impl ProducesSnapshot for SlotAggregatedStatus {
                 .expect("Lock poisoned when getting download filename")
                 .clone(),
             download_total: self.download_total.get(),
+            is_desired_state_pending: self.is_desired_state_pending.get(),
+            is_draining: self.is_draining.get() || self.is_shutting_down.get(),
             model_path: self
                 .model_path
                 .read()
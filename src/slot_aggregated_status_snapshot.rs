@@ -9,10 +9,14 @@ use crate::agent_state_application_status::AgentStateApplicationStatus;
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct SlotAggregatedStatusSnapshot {
+    /// Generation of the most recent desired state that the agent applied
+    pub applied_desired_state_generation: i32,
     pub desired_slots_total: i32,
     pub download_current: usize,
     pub download_filename: Option<String>,
     pub download_total: usize,
+    pub is_desired_state_pending: bool,
+    pub is_draining: bool,
     pub issues: BTreeSet<AgentIssue>,
     pub model_path: Option<String>,
     pub slots_processing: i32,
@@ -1,3 +1,6 @@
 pub trait StreamableResult {
     fn is_done(&self) -> bool;
+
+    /// Final response that reports a failure instead of a result
+    fn is_error(&self) -> bool;
 }
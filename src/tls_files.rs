@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+use rustls::RootCertStore;
+use rustls::pki_types::CertificateDer;
+use rustls::pki_types::PrivateKeyDer;
+use rustls::pki_types::pem::PemObject as _;
+
+pub fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certificates = CertificateDer::pem_file_iter(path)
+        .context(format!(
+            "Unable to read certificates from '{}'",
+            path.display()
+        ))?
+        .collect::<Result<Vec<_>, _>>()
+        .context(format!("Invalid certificate in '{}'", path.display()))?;
+
+    if certificates.is_empty() {
+        return Err(anyhow!("No certificates found in '{}'", path.display()));
+    }
+
+    Ok(certificates)
+}
+
+pub fn read_private_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path).context(format!(
+        "Unable to read private key from '{}'",
+        path.display()
+    ))
+}
+
+/// Only the certificate authorities from the file are trusted, the system ones are not
+pub fn read_root_cert_store(path: &Path) -> Result<RootCertStore> {
+    let mut root_cert_store = RootCertStore::empty();
+
+    for certificate in read_certificates(path)? {
+        root_cert_store.add(certificate).context(format!(
+            "Invalid certificate authority in '{}'",
+            path.display()
+        ))?;
+    }
+
+    Ok(root_cert_store)
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::logit_bias_token::LogitBiasToken;
+use crate::validates::Validates;
+
+/// Biases at or below it ban the token, as in the OpenAI API. JSON has no
+/// infinity, so a ban cannot be sent as one.
+pub const LOGIT_BIAS_BAN: f32 = -100.0;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenLogitBias {
+    /// Added to the token's logit (-100 or less bans the token)
+    pub bias: f32,
+    pub token: LogitBiasToken,
+}
+
+impl TokenLogitBias {
+    pub fn logit_offset(&self) -> f32 {
+        if self.bias <= LOGIT_BIAS_BAN {
+            f32::NEG_INFINITY
+        } else {
+            self.bias
+        }
+    }
+}
+
+impl Validates<TokenLogitBias> for TokenLogitBias {
+    fn validate(self) -> Result<TokenLogitBias> {
+        match &self.token {
+            LogitBiasToken::Id(token_id) => {
+                if *token_id < 0 {
+                    return Err(anyhow!("Logit bias token id must not be negative"));
+                }
+            }
+            LogitBiasToken::Text(text) => {
+                if text.is_empty() {
+                    return Err(anyhow!("Logit bias text must not be empty"));
+                }
+            }
+        }
+
+        if !self.bias.is_finite() {
+            return Err(anyhow!(
+                "Logit bias for {:?} must be a finite number, use {LOGIT_BIAS_BAN} to ban the token",
+                self.token
+            ));
+        }
+
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::request_params::ContinueFromRawPromptParams;
+    use crate::sampler_stage::SamplerStage;
+
+    #[test]
+    fn test_ban_survives_forwarding_to_the_agent() -> Result<()> {
+        let client_params: ContinueFromRawPromptParams = serde_json::from_value(json!({
+            "logit_bias": [{ "bias": -100, "token": { "Id": 42 } }],
+            "max_tokens": 10,
+            "raw_prompt": "Hello",
+        }))?;
+        let forwarded_params: ContinueFromRawPromptParams =
+            serde_json::from_str(&serde_json::to_string(&client_params)?)?;
+        let sampler_chain =
+            vec![SamplerStage::LogitBias(forwarded_params.logit_bias)].validate()?;
+
+        match &sampler_chain[0] {
+            SamplerStage::LogitBias(biases) => {
+                assert_eq!(biases[0].logit_offset(), f32::NEG_INFINITY);
+            }
+            other => panic!("Unexpected sampler stage: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_finite_bias_is_rejected() {
+        assert!(
+            TokenLogitBias {
+                bias: f32::NEG_INFINITY,
+                token: LogitBiasToken::Id(42),
+            }
+            .validate()
+            .is_err()
+        );
+        assert_eq!(
+            TokenLogitBias {
+                bias: 2.5,
+                token: LogitBiasToken::Id(42),
+            }
+            .logit_offset(),
+            2.5
+        );
+    }
+}